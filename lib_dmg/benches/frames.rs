@@ -0,0 +1,26 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use lib_dmg::cpu::CPU;
+
+const ROM: &[u8] = include_bytes!("../../src/bomberman.gb");
+const ONE_FRAME_IN_CYCLES: usize = 70224;
+const FRAMES: usize = 60;
+
+// One second of emulated time from power on, without a window or audio output
+fn run_frames(c: &mut Criterion) {
+    c.bench_function("bomberman 60 frames", |b| {
+        b.iter_batched(
+            || CPU::new(ROM).unwrap(),
+            |mut cpu| {
+                let mut cycles = 0;
+                while cycles < FRAMES * ONE_FRAME_IN_CYCLES {
+                    cycles += cpu.step().unwrap() as usize;
+                }
+                cpu
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group!(benches, run_frames);
+criterion_main!(benches);
@@ -0,0 +1,142 @@
+const TRANSFER_LENGTH: u16 = 0xA0;
+// M-cycles between the write to 0xFF46 and the first byte being copied
+const STARTUP_DELAY: u8 = 1;
+
+// Sources from 0xE000 up read the WRAM echo, 0xFE00 and 0xFF00 included
+fn source_address(value: u8) -> u16 {
+    let source = (value as u16) << 8;
+    if source >= 0xE000 {
+        source - 0x2000
+    } else {
+        source
+    }
+}
+
+// OAM DMA copies one byte per M-cycle from value << 8 to OAM, 160 bytes in total
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct DMA {
+    pub register: u8,
+    source: u16,
+    index: u16,                  // next byte to copy, TRANSFER_LENGTH once done
+    requested: Option<(u16, u8)>, // source of a new transfer and M-cycles before it starts
+}
+
+impl DMA {
+    pub fn new() -> DMA {
+        DMA {
+            register: 0xFF,
+            source: 0,
+            index: TRANSFER_LENGTH,
+            requested: None,
+        }
+    }
+
+    // Writing again during a transfer restarts it, the old one keeps going until the
+    // new one is done starting up
+    pub fn start(&mut self, value: u8) {
+        self.register = value;
+        self.requested = Some((source_address(value), STARTUP_DELAY));
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.index < TRANSFER_LENGTH
+    }
+
+    // The CPU only has HRAM to itself while a transfer runs
+    pub fn blocks(&self, address: u16) -> bool {
+        self.is_active() && !(0xFF80..=0xFFFE).contains(&address)
+    }
+
+    // Advances by one M-cycle, returns the source address and OAM index of the byte to
+    // copy during it, if any
+    pub fn tick(&mut self) -> Option<(u16, usize)> {
+        if let Some((source, delay)) = self.requested {
+            if delay == 0 {
+                self.source = source;
+                self.index = 0;
+                self.requested = None;
+            } else {
+                self.requested = Some((source, delay - 1));
+            }
+        }
+        if !self.is_active() {
+            return None;
+        }
+        let transfer = (self.source + self.index, self.index as usize);
+        self.index += 1;
+        Some(transfer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cart::header::test_rom;
+
+    fn run_m_cycles(bus: &mut Bus, m_cycles: usize) {
+        for _ in 0..m_cycles {
+            bus.step(4);
+        }
+    }
+
+    #[test]
+    fn test_transfer_timing() {
+        let mut dma = DMA::new();
+        dma.start(0xC1);
+        assert_eq!(dma.tick(), None);
+        assert_eq!(dma.tick(), Some((0xC100, 0)));
+        for index in 1..0xA0 {
+            assert_eq!(dma.tick(), Some((0xC100 + index as u16, index)));
+        }
+        assert!(!dma.is_active());
+        assert_eq!(dma.tick(), None);
+    }
+
+    #[test]
+    fn test_restart() {
+        let mut dma = DMA::new();
+        dma.start(0xC0);
+        for _ in 0..11 {
+            dma.tick();
+        }
+        dma.start(0xD0);
+        // The running transfer keeps going during the startup delay
+        assert_eq!(dma.tick(), Some((0xC00A, 10)));
+        assert_eq!(dma.tick(), Some((0xD000, 0)));
+    }
+
+    #[test]
+    fn test_echo_sources() {
+        assert_eq!(source_address(0xE1), 0xC100);
+        assert_eq!(source_address(0xFE), 0xDE00);
+        assert_eq!(source_address(0xFF), 0xDF00);
+    }
+
+    #[test]
+    fn test_only_hram_during_transfer() {
+        let mut bus = Bus::new(&test_rom(0x00, 0x00)).unwrap();
+        for offset in 0..0xA0 {
+            bus.bus_write(0xC000 + offset, offset as u8);
+        }
+        bus.bus_write(0xFF80, 0x42);
+        bus.bus_write(0xFF46, 0xC0);
+        assert_eq!(bus.bus_read(0xFF46), 0xC0);
+
+        run_m_cycles(&mut bus, 11);
+        // Only HRAM can be used
+        for address in [0x0000, 0x8000, 0xC000, 0xFE00, 0xFF40] {
+            assert_eq!(bus.bus_read(address), 0xFF, "{:04X}", address);
+        }
+        bus.bus_write(0x8000, 0x24);
+        bus.bus_write(0xFF80, 0x43);
+        assert_eq!(bus.bus_read(0xFF80), 0x43);
+
+        run_m_cycles(&mut bus, 150);
+        assert_eq!(bus.bus_read(0x8000), 0x00);
+        assert_eq!(bus.bus_read(0xC000), 0);
+        for offset in 0..0xA0 {
+            assert_eq!(bus.bus_read(0xFE00 + offset), offset as u8);
+        }
+    }
+}
@@ -1,35 +1,64 @@
 use crate::cart::Cartridge;
-use crate::gpu::{GPU, InterruptRequest};
+use crate::cart::header::CartridgeError;
+use crate::gpu::{GPU, InterruptRequest, Renderer};
 use crate::io::IO;
 use crate::ram::RAM;
+use self::dma::DMA;
+
+mod dma;
 
 pub const VBLANK_VECTOR: u16 = 0x40;
 pub const LCDSTAT_VECTOR: u16 = 0x48;
 pub const TIMER_VECTOR: u16 = 0x50;
+pub const SERIAL_VECTOR: u16 = 0x58;
+pub const JOYPAD_VECTOR: u16 = 0x60;
+
+// Indexed by the interrupt's bit in IE/IF, lower bits have priority
+const INTERRUPT_VECTORS: [u16; 5] = [VBLANK_VECTOR, LCDSTAT_VECTOR, TIMER_VECTOR, SERIAL_VECTOR, JOYPAD_VECTOR];
 
 
 
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Bus {
-    cart: Cartridge,
+    pub cart: Cartridge,
     ram: RAM,
     pub io: IO,
     pub gpu: GPU,
+    dma: DMA,
 }
 
 impl Bus {
-    pub fn new(data: &[u8]) -> Bus {
-        Bus {
-            cart: Cartridge::new(data),
+    pub fn new(data: &[u8]) -> Result<Bus, CartridgeError> {
+        Bus::with_renderer(data, Renderer::Scanline)
+    }
+
+    pub fn with_renderer(data: &[u8], renderer: Renderer) -> Result<Bus, CartridgeError> {
+        Ok(Bus {
+            cart: Cartridge::new(data)?,
             ram: RAM::new(),
             io: IO::new(),
-            gpu: GPU::new(),
-        }
+            gpu: GPU::with_renderer(renderer),
+            dma: DMA::new(),
+        })
     }
 
     pub fn step(&mut self, cycles: u8){
+        self.cart.step(cycles);
+
+        for _ in 0..cycles / 4 {
+            self.step_dma();
+        }
+
         if self.io.timer.step(cycles) {
             self.io.interrupt_flag.timer = true;
         }
+        self.io.apu.step(cycles, self.io.timer.system_counter());
+        if self.io.serial.step(cycles) {
+            self.io.interrupt_flag.serial = true;
+        }
+        if self.io.joypad.falling_edge() {
+            self.io.interrupt_flag.joypad = true;
+        }
 
         let (vblank, lcd) = match self.gpu.step(cycles) {
             InterruptRequest::Both => (true, true),
@@ -46,20 +75,40 @@ impl Bus {
         }
     }
 
+    fn step_dma(&mut self) {
+        if let Some((source, index)) = self.dma.tick() {
+            // Unlike the CPU, the DMA can read VRAM whatever the PPU is doing
+            let value = match source {
+                0x8000..=0x9FFF => self.gpu.vram[(source - 0x8000) as usize],
+                _ => self.read(source),
+            };
+            self.gpu.write_oam(index, value);
+        }
+    }
+
     pub fn bus_read(&mut self, address: u16) -> u8 {
+        if self.dma.blocks(address) {
+            return 0xFF;
+        }
+        self.read(address)
+    }
+
+    fn read(&mut self, address: u16) -> u8 {
         match address {
             0x0000..=0x3FFF => self.cart.cart_read(address), // ROM Bank 00
             0x4000..=0x7FFF => self.cart.cart_read(address), // ROM Bank 01->NN
             0x8000..=0x9FFF => self.gpu.gpu_read(address), // GPU VRAM
-            0xA000..=0xBFFF => self.ram.ram_read(address), // 8 KiB External RAM
+            0xA000..=0xBFFF => self.cart.cart_read(address), // 8 KiB External RAM
             0xC000..=0xCFFF => self.ram.ram_read(address), // 4 KiB Work RAM (WRAM)
             0xD000..=0xDFFF => self.ram.ram_read(address), // 4 KiB Work RAM (WRAM)
+            0xE000..=0xFDFF => self.ram.ram_read(address), // Echo of 0xC000-0xDDFF
             0xFE00..=0xFE9F => self.gpu.gpu_read(address), // Object attribute memory (OAM)
+            0xFEA0..=0xFEFF => self.gpu.gpu_read(address), // Not Usable
+            0xFF46 => self.dma.register, // OAM DMA
             0xFF40..=0xFF4B => self.gpu.gpu_read(address), // GPU LCD
             0xFF00..=0xFF7F => self.io.io_read(address), // I/O Registers
             0xFF80..=0xFFFE => self.ram.ram_read(address), // High ram
             0xFFFF => self.io.io_read(address), // Interrupt master
-            _ => panic!("Bus read address not implemented: {:04X}", address)
         }
     }
 
@@ -71,6 +120,23 @@ impl Bus {
             || (self.io.interrupt_enable.joypad && self.io.interrupt_flag.joypad)
     }
 
+    // Vector of the highest priority interrupt that is both enabled and requested
+    pub fn pending_interrupt(&self) -> Option<u16> {
+        let pending = self.io.interrupt_enable.to_byte() & self.io.interrupt_flag.to_byte() & 0x1F;
+        if pending == 0 {
+            None
+        } else {
+            Some(INTERRUPT_VECTORS[pending.trailing_zeros() as usize])
+        }
+    }
+
+    // Clears the IF bit of the interrupt jumping to vector
+    pub fn acknowledge_interrupt(&mut self, vector: u16) {
+        let bit = (vector - VBLANK_VECTOR) / 8;
+        let flag = self.io.interrupt_flag.to_byte();
+        self.io.interrupt_flag.from_byte(flag & !(1 << bit));
+    }
+
     pub fn bus_read_word(&mut self, address: u16) -> u16 {
         let lsb = self.bus_read(address) as u16;
         let msb = self.bus_read(address + 1) as u16;
@@ -78,35 +144,30 @@ impl Bus {
     }
 
     pub fn bus_write(&mut self, address: u16, value: u8) {
+        if self.dma.blocks(address) {
+            return;
+        }
         match address {
             0x0000..=0x3FFF => self.cart.cart_write(address, value),// ROM Bank 00
             0x4000..=0x7FFF => self.cart.cart_write(address, value),// ROM Bank 01->NN
-            0x8000..=0x9FFF => self.gpu.gpu_write(address, value), // GPU VRAM
-            0xA000..=0xBFFF => self.ram.ram_write(address, value), // 8 KiB External RAM
+            0x8000..=0x9FFF => { self.gpu.gpu_write(address, value); } // GPU VRAM
+            0xA000..=0xBFFF => self.cart.cart_write(address, value), // 8 KiB External RAM
             0xC000..=0xCFFF => self.ram.ram_write(address, value), // 4 KiB Work RAM (WRAM)
             0xD000..=0xDFFF => self.ram.ram_write(address, value), // 4 KiB Work RAM (WRAM)
-            0xE000..=0xFDFF => (), //Not Usable
-            0xFE00..=0xFE9F => self.gpu.gpu_write(address, value), // Object attribute memory (OAM)
-            0xFEA0..=0xFEFF => (), //Not Usable
-            0xFF46 => { //GPU LCD
-                // TODO: account for the fact this takes 160 microseconds
-                let dma_source = (value as u16) << 8;
-                let dma_destination = 0xFE00;
-                for offset in 0..150 {
-                    let addr = dma_destination + offset;
-                    let val = self.bus_read(dma_source + offset);
-                    self.bus_write(
-                        addr,
-                        val
-                    )
+            0xE000..=0xFDFF => self.ram.ram_write(address, value), // Echo of 0xC000-0xDDFF
+            0xFE00..=0xFE9F => { self.gpu.gpu_write(address, value); } // Object attribute memory (OAM)
+            0xFEA0..=0xFEFF => (), // Not Usable, writes are ignored
+            0xFF46 => self.dma.start(value), // OAM DMA, copied in the background by step
+            0xFF40..=0xFF4B => {
+                // GPU LCD
+                if self.gpu.gpu_write(address, value) {
+                    self.io.interrupt_flag.lcdstat = true;
                 }
-            },
-            0xFF40..=0xFF4B => self.gpu.gpu_write(address, value), // GPU LCD
+            }
             0xFF50 => self.cart.boot_rom = None,
             0xFF00..=0xFF7F => self.io.io_write(address, value), // I/O Registers
             0xFF80..=0xFFFE => self.ram.ram_write(address, value), // High ram
             0xFFFF => self.io.io_write(address, value), // Interrupt master
-            _ => panic!("Bus write address not implemented: {:04X}", address)
         }
     }
 
@@ -118,4 +179,67 @@ impl Bus {
         let (addr, res) = address.overflowing_add(1);
         self.bus_write(addr, msb);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cart::header::test_rom;
+
+    #[test]
+    fn test_echo_ram() {
+        let mut bus = Bus::new(&test_rom(0x00, 0x00)).unwrap();
+        bus.bus_write(0xC123, 0x45);
+        assert_eq!(bus.bus_read(0xE123), 0x45);
+        bus.bus_write(0xFDFF, 0x67);
+        assert_eq!(bus.bus_read(0xDDFF), 0x67);
+    }
+
+    #[test]
+    fn test_unusable_region() {
+        let mut bus = Bus::new(&test_rom(0x00, 0x00)).unwrap();
+        bus.bus_write(0xFEA0, 0x12);
+        assert_eq!(bus.bus_read(0xFEA0), 0x00);
+        bus.gpu.gpu_write(0xFF40, 0x80);
+        assert_eq!(bus.bus_read(0xFEFF), 0xFF);
+    }
+
+    #[test]
+    fn test_no_address_panics() {
+        let mut bus = Bus::new(&test_rom(0x00, 0x00)).unwrap();
+        for address in 0..=0xFFFF {
+            bus.bus_read(address);
+            bus.bus_write(address, 0x00);
+        }
+    }
+
+    #[test]
+    fn test_interrupt_priority() {
+        let mut bus = Bus::new(&test_rom(0x00, 0x00)).unwrap();
+        assert_eq!(bus.pending_interrupt(), None);
+        bus.bus_write(0xFFFF, 0x1C);
+        bus.bus_write(0xFF0F, 0x1B);
+        assert_eq!(bus.pending_interrupt(), Some(SERIAL_VECTOR));
+        bus.acknowledge_interrupt(SERIAL_VECTOR);
+        assert_eq!(bus.pending_interrupt(), Some(JOYPAD_VECTOR));
+        assert_eq!(bus.bus_read(0xFF0F) & 0x1F, 0x13);
+    }
+
+    #[test]
+    fn test_joypad_interrupt_on_press() {
+        let mut bus = Bus::new(&test_rom(0x00, 0x00)).unwrap();
+        bus.bus_write(0xFF0F, 0x00);
+        bus.io.joypad.a = true;
+        bus.step(4);
+        assert_eq!(bus.bus_read(0xFF0F) & 0x10, 0x00); // A isn't on the selected lines
+
+        bus.bus_write(0xFF00, 0x10); // Select the buttons
+        bus.step(4);
+        assert_eq!(bus.bus_read(0xFF0F) & 0x10, 0x10);
+
+        // Holding the button doesn't request it again
+        bus.bus_write(0xFF0F, 0x00);
+        bus.step(4);
+        assert_eq!(bus.bus_read(0xFF0F) & 0x10, 0x00);
+    }
+}
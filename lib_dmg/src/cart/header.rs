@@ -0,0 +1,355 @@
+use std::fmt;
+
+use crate::cart::NINTENDO_LOGO;
+
+const HEADER_END: usize = 0x0150;
+const LOGO_ADDRESS: usize = 0x0104;
+const TITLE_ADDRESS: usize = 0x0134;
+const MANUFACTURER_CODE_ADDRESS: usize = 0x013F;
+const CGB_FLAG_ADDRESS: usize = 0x0143;
+const NEW_LICENSEE_CODE_ADDRESS: usize = 0x0144;
+const SGB_FLAG_ADDRESS: usize = 0x0146;
+const CARTRIDGE_TYPE_ADDRESS: usize = 0x0147;
+const ROM_SIZE_ADDRESS: usize = 0x0148;
+const RAM_SIZE_ADDRESS: usize = 0x0149;
+const DESTINATION_ADDRESS: usize = 0x014A;
+const OLD_LICENSEE_CODE_ADDRESS: usize = 0x014B;
+const VERSION_ADDRESS: usize = 0x014C;
+const HEADER_CHECKSUM_ADDRESS: usize = 0x014D;
+const GLOBAL_CHECKSUM_ADDRESS: usize = 0x014E;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CartridgeError {
+    TooShort(usize),
+    InvalidLogo,
+    InvalidHeaderChecksum { expected: u8, actual: u8 },
+    InvalidGlobalChecksum { expected: u16, actual: u16 },
+    UnsupportedCartridgeType(u8),
+    InvalidRomSize(u8),
+    InvalidRamSize(u8),
+    RomSizeMismatch { expected: usize, actual: usize },
+    InvalidSaveSize { expected: usize, actual: usize },
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CartridgeError::TooShort(length) =>
+                write!(f, "ROM is {} bytes long, too short to contain a header", length),
+            CartridgeError::InvalidLogo =>
+                write!(f, "Nintendo logo in the header doesn't match"),
+            CartridgeError::InvalidHeaderChecksum { expected, actual } =>
+                write!(f, "header checksum is {:02X} but the header sums to {:02X}", expected, actual),
+            CartridgeError::InvalidGlobalChecksum { expected, actual } =>
+                write!(f, "global checksum is {:04X} but the ROM sums to {:04X}", expected, actual),
+            CartridgeError::UnsupportedCartridgeType(cartridge_type) =>
+                write!(f, "cartridge type {:02X} is not supported", cartridge_type),
+            CartridgeError::InvalidRomSize(code) =>
+                write!(f, "invalid ROM size code {:02X}", code),
+            CartridgeError::InvalidRamSize(code) =>
+                write!(f, "invalid RAM size code {:02X}", code),
+            CartridgeError::RomSizeMismatch { expected, actual } =>
+                write!(f, "header declares a {} bytes ROM but the dump is only {} bytes", expected, actual),
+            CartridgeError::InvalidSaveSize { expected, actual } =>
+                write!(f, "save file is {} bytes but the cartridge has {} bytes of RAM", actual, expected),
+        }
+    }
+}
+
+impl std::error::Error for CartridgeError {}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum CartridgeType {
+    RomOnly,
+    RomRam,
+    RomRamBattery,
+    MBC1,
+    MBC1Ram,
+    MBC1RamBattery,
+    MBC2,
+    MBC2Battery,
+    MBC3TimerBattery,
+    MBC3TimerRamBattery,
+    MBC3,
+    MBC3Ram,
+    MBC3RamBattery,
+    MBC5,
+    MBC5Ram,
+    MBC5RamBattery,
+    MBC5Rumble,
+    MBC5RumbleRam,
+    MBC5RumbleRamBattery,
+}
+
+impl CartridgeType {
+    pub fn from_byte(byte: u8) -> Option<CartridgeType> {
+        match byte {
+            0x00 => Some(CartridgeType::RomOnly),
+            0x01 => Some(CartridgeType::MBC1),
+            0x02 => Some(CartridgeType::MBC1Ram),
+            0x03 => Some(CartridgeType::MBC1RamBattery),
+            0x05 => Some(CartridgeType::MBC2),
+            0x06 => Some(CartridgeType::MBC2Battery),
+            0x08 => Some(CartridgeType::RomRam),
+            0x09 => Some(CartridgeType::RomRamBattery),
+            0x0F => Some(CartridgeType::MBC3TimerBattery),
+            0x10 => Some(CartridgeType::MBC3TimerRamBattery),
+            0x11 => Some(CartridgeType::MBC3),
+            0x12 => Some(CartridgeType::MBC3Ram),
+            0x13 => Some(CartridgeType::MBC3RamBattery),
+            0x19 => Some(CartridgeType::MBC5),
+            0x1A => Some(CartridgeType::MBC5Ram),
+            0x1B => Some(CartridgeType::MBC5RamBattery),
+            0x1C => Some(CartridgeType::MBC5Rumble),
+            0x1D => Some(CartridgeType::MBC5RumbleRam),
+            0x1E => Some(CartridgeType::MBC5RumbleRamBattery),
+            _ => None,
+        }
+    }
+
+    pub fn has_battery(&self) -> bool {
+        matches!(
+            self,
+            CartridgeType::RomRamBattery
+                | CartridgeType::MBC1RamBattery
+                | CartridgeType::MBC2Battery
+                | CartridgeType::MBC3TimerBattery
+                | CartridgeType::MBC3TimerRamBattery
+                | CartridgeType::MBC3RamBattery
+                | CartridgeType::MBC5RamBattery
+                | CartridgeType::MBC5RumbleRamBattery
+        )
+    }
+
+    pub fn has_rtc(&self) -> bool {
+        matches!(self, CartridgeType::MBC3TimerBattery | CartridgeType::MBC3TimerRamBattery)
+    }
+
+    pub fn has_rumble(&self) -> bool {
+        matches!(
+            self,
+            CartridgeType::MBC5Rumble | CartridgeType::MBC5RumbleRam | CartridgeType::MBC5RumbleRamBattery
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum CgbFlag {
+    None,
+    Supported, // 0x80, works on both DMG and CGB
+    Only,      // 0xC0
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Destination {
+    Japanese,
+    Overseas,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct CartridgeHeader {
+    pub title: String,
+    pub manufacturer_code: Option<String>,
+    pub cgb_flag: CgbFlag,
+    pub new_licensee_code: String,
+    pub sgb_flag: bool,
+    pub cartridge_type: CartridgeType,
+    pub rom_size: usize,
+    pub ram_size: usize,
+    pub destination: Destination,
+    pub old_licensee_code: u8,
+    pub version: u8,
+    pub header_checksum: u8,
+    pub global_checksum: u16,
+}
+
+impl CartridgeHeader {
+    pub fn parse(rom: &[u8]) -> Result<CartridgeHeader, CartridgeError> {
+        if rom.len() < HEADER_END {
+            return Err(CartridgeError::TooShort(rom.len()));
+        }
+
+        let cgb_flag = match rom[CGB_FLAG_ADDRESS] {
+            0xC0 => CgbFlag::Only,
+            flag if (flag & 0x80) != 0 => CgbFlag::Supported,
+            _ => CgbFlag::None,
+        };
+
+        // Newer carts shortened the title to fit a manufacturer code and the CGB flag,
+        // older ones use all 16 bytes for the title.
+        let manufacturer_code = &rom[MANUFACTURER_CODE_ADDRESS..CGB_FLAG_ADDRESS];
+        let has_manufacturer_code = cgb_flag != CgbFlag::None
+            && manufacturer_code.iter().all(|byte| byte.is_ascii_uppercase() || byte.is_ascii_digit());
+        let title_end = if has_manufacturer_code {
+            MANUFACTURER_CODE_ADDRESS
+        } else if cgb_flag != CgbFlag::None {
+            CGB_FLAG_ADDRESS
+        } else {
+            NEW_LICENSEE_CODE_ADDRESS
+        };
+
+        let cartridge_type_byte = rom[CARTRIDGE_TYPE_ADDRESS];
+        let cartridge_type = CartridgeType::from_byte(cartridge_type_byte)
+            .ok_or(CartridgeError::UnsupportedCartridgeType(cartridge_type_byte))?;
+
+        let rom_size_code = rom[ROM_SIZE_ADDRESS];
+        if rom_size_code > 0x08 {
+            return Err(CartridgeError::InvalidRomSize(rom_size_code));
+        }
+
+        let ram_size_code = rom[RAM_SIZE_ADDRESS];
+        let ram_size = match ram_size_code {
+            0x00 => 0,
+            0x01 => 0x800,
+            0x02 => 0x2000,
+            0x03 => 0x8000,
+            0x04 => 0x20000,
+            0x05 => 0x10000,
+            _ => return Err(CartridgeError::InvalidRamSize(ram_size_code)),
+        };
+
+        Ok(CartridgeHeader {
+            title: ascii_string(&rom[TITLE_ADDRESS..title_end]),
+            manufacturer_code: if has_manufacturer_code {
+                Some(ascii_string(manufacturer_code))
+            } else {
+                None
+            },
+            cgb_flag,
+            new_licensee_code: ascii_string(&rom[NEW_LICENSEE_CODE_ADDRESS..SGB_FLAG_ADDRESS]),
+            sgb_flag: rom[SGB_FLAG_ADDRESS] == 0x03,
+            cartridge_type,
+            rom_size: 0x8000 << rom_size_code,
+            ram_size,
+            destination: if rom[DESTINATION_ADDRESS] == 0x00 {
+                Destination::Japanese
+            } else {
+                Destination::Overseas
+            },
+            old_licensee_code: rom[OLD_LICENSEE_CODE_ADDRESS],
+            version: rom[VERSION_ADDRESS],
+            header_checksum: rom[HEADER_CHECKSUM_ADDRESS],
+            global_checksum: ((rom[GLOBAL_CHECKSUM_ADDRESS] as u16) << 8)
+                | rom[GLOBAL_CHECKSUM_ADDRESS + 1] as u16,
+        })
+    }
+
+    pub fn verify_logo(&self, rom: &[u8]) -> Result<(), CartridgeError> {
+        if rom.len() < HEADER_END {
+            return Err(CartridgeError::TooShort(rom.len()));
+        }
+        if rom[LOGO_ADDRESS..LOGO_ADDRESS + NINTENDO_LOGO.len()] != NINTENDO_LOGO {
+            return Err(CartridgeError::InvalidLogo);
+        }
+        Ok(())
+    }
+
+    // Checked by the boot ROM, a mismatch locks up real hardware
+    pub fn verify_header_checksum(&self, rom: &[u8]) -> Result<(), CartridgeError> {
+        if rom.len() < HEADER_END {
+            return Err(CartridgeError::TooShort(rom.len()));
+        }
+        let actual = rom[TITLE_ADDRESS..HEADER_CHECKSUM_ADDRESS]
+            .iter()
+            .fold(0u8, |checksum, byte| checksum.wrapping_sub(*byte).wrapping_sub(1));
+        if actual != self.header_checksum {
+            return Err(CartridgeError::InvalidHeaderChecksum { expected: self.header_checksum, actual });
+        }
+        Ok(())
+    }
+
+    // Never checked by the hardware, but a good way to spot a bad dump
+    pub fn verify_global_checksum(&self, rom: &[u8]) -> Result<(), CartridgeError> {
+        let actual = rom
+            .iter()
+            .enumerate()
+            .filter(|(address, _)| *address != GLOBAL_CHECKSUM_ADDRESS && *address != GLOBAL_CHECKSUM_ADDRESS + 1)
+            .fold(0u16, |checksum, (_, byte)| checksum.wrapping_add(*byte as u16));
+        if actual != self.global_checksum {
+            return Err(CartridgeError::InvalidGlobalChecksum { expected: self.global_checksum, actual });
+        }
+        Ok(())
+    }
+}
+
+fn ascii_string(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take_while(|byte| **byte != 0)
+        .map(|byte| if byte.is_ascii_graphic() || *byte == b' ' { *byte as char } else { '?' })
+        .collect()
+}
+
+// Smallest ROM that passes validation, for tests that need a real Cartridge
+#[cfg(test)]
+pub fn test_rom(cartridge_type: u8, ram_size_code: u8) -> Vec<u8> {
+    let mut rom = vec![0; 0x8000];
+    rom[LOGO_ADDRESS..LOGO_ADDRESS + NINTENDO_LOGO.len()].copy_from_slice(&NINTENDO_LOGO);
+    rom[TITLE_ADDRESS..TITLE_ADDRESS + 6].copy_from_slice(b"TETRIS");
+    rom[CARTRIDGE_TYPE_ADDRESS] = cartridge_type;
+    rom[RAM_SIZE_ADDRESS] = ram_size_code;
+    rom[OLD_LICENSEE_CODE_ADDRESS] = 0x01;
+    rom[VERSION_ADDRESS] = 0x01;
+    rom[HEADER_CHECKSUM_ADDRESS] = rom[TITLE_ADDRESS..HEADER_CHECKSUM_ADDRESS]
+        .iter()
+        .fold(0u8, |checksum, byte| checksum.wrapping_sub(*byte).wrapping_sub(1));
+    let global_checksum = rom.iter().fold(0u16, |checksum, byte| checksum.wrapping_add(*byte as u16));
+    rom[GLOBAL_CHECKSUM_ADDRESS..GLOBAL_CHECKSUM_ADDRESS + 2].copy_from_slice(&global_checksum.to_be_bytes());
+    rom
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        let rom = test_rom(0x00, 0x00);
+        let header = CartridgeHeader::parse(&rom).unwrap();
+        assert_eq!(header.title, "TETRIS");
+        assert_eq!(header.manufacturer_code, None);
+        assert_eq!(header.cgb_flag, CgbFlag::None);
+        assert_eq!(header.cartridge_type, CartridgeType::RomOnly);
+        assert_eq!(header.rom_size, 0x8000);
+        assert_eq!(header.ram_size, 0);
+        assert_eq!(header.destination, Destination::Japanese);
+        assert_eq!(header.version, 0x01);
+        assert_eq!(header.verify_logo(&rom), Ok(()));
+        assert_eq!(header.verify_header_checksum(&rom), Ok(()));
+        assert_eq!(header.verify_global_checksum(&rom), Ok(()));
+    }
+
+    #[test]
+    fn test_detect_bad_dump() {
+        let mut rom = test_rom(0x00, 0x00);
+        rom[0x4000] = 0xFF;
+        let header = CartridgeHeader::parse(&rom).unwrap();
+        assert_eq!(header.verify_header_checksum(&rom), Ok(()));
+        assert!(header.verify_global_checksum(&rom).is_err());
+
+        rom[TITLE_ADDRESS] = b'X';
+        assert!(header.verify_header_checksum(&rom).is_err());
+
+        rom[LOGO_ADDRESS] = 0x00;
+        assert_eq!(header.verify_logo(&rom), Err(CartridgeError::InvalidLogo));
+    }
+
+    #[test]
+    fn test_verify_short_rom() {
+        let rom = test_rom(0x00, 0x00);
+        let header = CartridgeHeader::parse(&rom).unwrap();
+        assert_eq!(header.verify_logo(&rom[..0x100]), Err(CartridgeError::TooShort(0x100)));
+        assert_eq!(header.verify_header_checksum(&rom[..0x140]), Err(CartridgeError::TooShort(0x140)));
+    }
+
+    #[test]
+    fn test_unsupported_cartridge_type() {
+        let mut rom = test_rom(0x00, 0x00);
+        rom[CARTRIDGE_TYPE_ADDRESS] = 0xFC;
+        assert_eq!(CartridgeHeader::parse(&rom), Err(CartridgeError::UnsupportedCartridgeType(0xFC)));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::cart::{NINTENDO_LOGO, pad_rom, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MBC1 {
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    rom_bank: u8,     // BANK1 register, 5 bits
+    upper_bits: u8,   // BANK2 register, 2 bits (upper ROM bank bits or RAM bank)
+    advanced_banking: bool,
+    multicart: bool,
+}
+
+impl MBC1 {
+    pub fn new(rom_data: &[u8], ram_size: usize) -> MBC1 {
+        let rom = pad_rom(rom_data);
+        let multicart = is_multicart(&rom);
+        MBC1 {
+            rom,
+            ram: vec![0; ram_size],
+            ram_enabled: false,
+            rom_bank: 1,
+            upper_bits: 0,
+            advanced_banking: false,
+            multicart,
+        }
+    }
+
+    pub fn rom_read(&self, address: u16) -> u8 {
+        let bank = match address {
+            0x0000..=0x3FFF => {
+                // In mode 1 the BANK2 bits also apply to the 0x0000 area, so banks
+                // 0x20/0x40/0x60 (0x10/0x20/0x30 on multicarts) can be mapped there.
+                if self.advanced_banking {
+                    self.upper_bits << self.upper_shift()
+                } else {
+                    0
+                }
+            }
+            0x4000..=0x7FFF => {
+                (self.upper_bits << self.upper_shift()) | (self.rom_bank & self.lower_mask())
+            }
+            _ => panic!("MBC1 ROM read address not implemented: {:04X}", address)
+        };
+        self.rom[self.rom_offset(bank, address)]
+    }
+
+    pub fn rom_write(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = (value & 0x0F) == 0x0A,
+            0x2000..=0x3FFF => {
+                // The zero check is done on all 5 bits, which is why banks 0x20, 0x40
+                // and 0x60 can never be selected for the 0x4000 area.
+                self.rom_bank = value & 0x1F;
+                if self.rom_bank == 0 {
+                    self.rom_bank = 1;
+                }
+            }
+            0x4000..=0x5FFF => self.upper_bits = value & 0x03,
+            0x6000..=0x7FFF => self.advanced_banking = (value & 0x01) == 0x01,
+            _ => panic!("MBC1 ROM write address not implemented: {:04X}", address)
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    pub fn rom_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.rom
+    }
+
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+
+    pub fn ram_read(&self, address: u16) -> u8 {
+        if !self.ram_enabled || self.ram.is_empty() {
+            return 0xFF;
+        }
+        self.ram[self.ram_offset(address)]
+    }
+
+    pub fn ram_write(&mut self, address: u16, value: u8) -> bool {
+        if !self.ram_enabled || self.ram.is_empty() {
+            return false;
+        }
+        let offset = self.ram_offset(address);
+        self.ram[offset] = value;
+        true
+    }
+
+    fn upper_shift(&self) -> u8 {
+        // MBC1M multicarts don't wire the highest bit of BANK1
+        if self.multicart { 4 } else { 5 }
+    }
+
+    fn lower_mask(&self) -> u8 {
+        if self.multicart { 0x0F } else { 0x1F }
+    }
+
+    fn rom_offset(&self, bank: u8, address: u16) -> usize {
+        let bank_count = self.rom.len() / ROM_BANK_SIZE;
+        let bank = bank as usize & (bank_count - 1);
+        bank * ROM_BANK_SIZE + (address as usize & (ROM_BANK_SIZE - 1))
+    }
+
+    fn ram_offset(&self, address: u16) -> usize {
+        let bank = if self.advanced_banking {
+            self.upper_bits as usize
+        } else {
+            0
+        };
+        let offset = bank * RAM_BANK_SIZE + (address as usize - 0xA000);
+        offset & (self.ram.len() - 1)
+    }
+}
+
+// MBC1M multicarts are 8 Mbit ROMs containing several games, each one with its own
+// header. The only reliable way to detect them is to look for a second Nintendo logo
+// at the start of bank 0x10.
+fn is_multicart(rom: &[u8]) -> bool {
+    if rom.len() != 0x100000 {
+        return false;
+    }
+    let logo_start = 0x10 * ROM_BANK_SIZE + 0x0104;
+    rom[logo_start..logo_start + NINTENDO_LOGO.len()] == NINTENDO_LOGO
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every bank starts with its own bank number so reads tell us which bank is mapped
+    fn banked_rom(banks: usize) -> Vec<u8> {
+        let mut rom = vec![0; banks * ROM_BANK_SIZE];
+        for bank in 0..banks {
+            rom[bank * ROM_BANK_SIZE] = bank as u8;
+        }
+        rom
+    }
+
+    #[test]
+    fn test_rom_bank_switching() {
+        let mut mbc = MBC1::new(&banked_rom(128), 0);
+        assert_eq!(mbc.rom_read(0x4000), 1);
+
+        mbc.rom_write(0x2000, 0x05);
+        assert_eq!(mbc.rom_read(0x4000), 5);
+
+        mbc.rom_write(0x4000, 0x01);
+        assert_eq!(mbc.rom_read(0x4000), 0x25);
+        assert_eq!(mbc.rom_read(0x0000), 0);
+    }
+
+    #[test]
+    fn test_bank_zero_aliasing() {
+        let mut mbc = MBC1::new(&banked_rom(128), 0);
+        mbc.rom_write(0x2000, 0x00);
+        assert_eq!(mbc.rom_read(0x4000), 1);
+
+        // 0x20 can't be mapped at 0x4000, it becomes 0x21
+        mbc.rom_write(0x2000, 0x20);
+        mbc.rom_write(0x4000, 0x01);
+        assert_eq!(mbc.rom_read(0x4000), 0x21);
+
+        // ...but it shows up at 0x0000 in mode 1
+        mbc.rom_write(0x6000, 0x01);
+        assert_eq!(mbc.rom_read(0x0000), 0x20);
+    }
+
+    #[test]
+    fn test_bank_number_masked_to_rom_size() {
+        let mut mbc = MBC1::new(&banked_rom(8), 0);
+        mbc.rom_write(0x2000, 0x09);
+        assert_eq!(mbc.rom_read(0x4000), 1);
+    }
+
+    #[test]
+    fn test_ram_enable_and_banking() {
+        let mut mbc = MBC1::new(&banked_rom(4), 0x8000);
+        mbc.ram_write(0xA000, 0x42);
+        assert_eq!(mbc.ram_read(0xA000), 0xFF);
+
+        mbc.rom_write(0x0000, 0x0A);
+        mbc.ram_write(0xA000, 0x42);
+        assert_eq!(mbc.ram_read(0xA000), 0x42);
+
+        mbc.rom_write(0x6000, 0x01);
+        mbc.rom_write(0x4000, 0x02);
+        assert_eq!(mbc.ram_read(0xA000), 0x00);
+        mbc.ram_write(0xA000, 0x24);
+
+        mbc.rom_write(0x4000, 0x00);
+        assert_eq!(mbc.ram_read(0xA000), 0x42);
+    }
+
+    #[test]
+    fn test_multicart_wiring() {
+        let mut rom = banked_rom(64);
+        let logo_start = 0x10 * ROM_BANK_SIZE + 0x0104;
+        rom[logo_start..logo_start + NINTENDO_LOGO.len()].copy_from_slice(&NINTENDO_LOGO);
+        let mut mbc = MBC1::new(&rom, 0);
+
+        mbc.rom_write(0x4000, 0x01);
+        mbc.rom_write(0x2000, 0x12);
+        assert_eq!(mbc.rom_read(0x4000), 0x12);
+
+        mbc.rom_write(0x6000, 0x01);
+        assert_eq!(mbc.rom_read(0x0000), 0x10);
+    }
+}
@@ -0,0 +1,113 @@
+use crate::cart::{pad_rom, ROM_BANK_SIZE};
+
+const RAM_SIZE: usize = 0x200;
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MBC2 {
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    rom: Vec<u8>,
+    ram: Vec<u8>, // 512 half-bytes, only the lower nibble is stored
+    ram_enabled: bool,
+    rom_bank: u8,
+}
+
+impl MBC2 {
+    pub fn new(rom_data: &[u8]) -> MBC2 {
+        MBC2 {
+            rom: pad_rom(rom_data),
+            ram: vec![0; RAM_SIZE],
+            ram_enabled: false,
+            rom_bank: 1,
+        }
+    }
+
+    pub fn rom_read(&self, address: u16) -> u8 {
+        let bank = match address {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => self.rom_bank as usize,
+            _ => panic!("MBC2 ROM read address not implemented: {:04X}", address)
+        };
+        let bank_count = self.rom.len() / ROM_BANK_SIZE;
+        let bank = bank & (bank_count - 1);
+        self.rom[bank * ROM_BANK_SIZE + (address as usize & (ROM_BANK_SIZE - 1))]
+    }
+
+    pub fn rom_write(&mut self, address: u16, value: u8) {
+        match address {
+            // Both registers share the range, address bit 8 tells them apart
+            0x0000..=0x3FFF => {
+                if (address & 0x0100) == 0 {
+                    self.ram_enabled = (value & 0x0F) == 0x0A;
+                } else {
+                    self.rom_bank = value & 0x0F;
+                    if self.rom_bank == 0 {
+                        self.rom_bank = 1;
+                    }
+                }
+            }
+            0x4000..=0x7FFF => {}
+            _ => panic!("MBC2 ROM write address not implemented: {:04X}", address)
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    pub fn rom_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.rom
+    }
+
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+
+    pub fn ram_read(&self, address: u16) -> u8 {
+        if !self.ram_enabled {
+            return 0xFF;
+        }
+        // Only 9 address lines are wired, the 512 nibbles repeat over 0xA000-0xBFFF
+        0xF0 | self.ram[address as usize & (RAM_SIZE - 1)]
+    }
+
+    pub fn ram_write(&mut self, address: u16, value: u8) -> bool {
+        if !self.ram_enabled {
+            return false;
+        }
+        self.ram[address as usize & (RAM_SIZE - 1)] = value & 0x0F;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_select_by_address_bit_8() {
+        let mut rom = vec![0; 16 * ROM_BANK_SIZE];
+        rom[5 * ROM_BANK_SIZE] = 0x05;
+        let mut mbc = MBC2::new(&rom);
+
+        // Bit 8 clear: RAM enable, the ROM bank doesn't change
+        mbc.rom_write(0x2000, 0x05);
+        assert_eq!(mbc.rom_read(0x4000), 0x00);
+
+        mbc.rom_write(0x2100, 0x05);
+        assert_eq!(mbc.rom_read(0x4000), 0x05);
+
+        mbc.rom_write(0x0000, 0x0A);
+        mbc.ram_write(0xA000, 0x12);
+        assert_eq!(mbc.ram_read(0xA000), 0xF2);
+    }
+
+    #[test]
+    fn test_ram_echoes_every_512_bytes() {
+        let mut mbc = MBC2::new(&[0; 2 * ROM_BANK_SIZE]);
+        mbc.rom_write(0x0000, 0x0A);
+        mbc.ram_write(0xA1FF, 0x07);
+        assert_eq!(mbc.ram_read(0xA3FF), 0xF7);
+        assert_eq!(mbc.ram_read(0xBFFF), 0xF7);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::cart::{pad_rom, RAM_BANK_SIZE, ROM_BANK_SIZE};
+use crate::cart::rtc::RTC;
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MBC3 {
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    pub rtc: Option<RTC>,
+    ram_and_rtc_enabled: bool,
+    rom_bank: u8,
+    ram_bank_or_rtc_select: u8,
+    latch_armed: bool,
+}
+
+impl MBC3 {
+    pub fn new(rom_data: &[u8], ram_size: usize, has_rtc: bool) -> MBC3 {
+        MBC3 {
+            rom: pad_rom(rom_data),
+            ram: vec![0; ram_size],
+            rtc: if has_rtc { Some(RTC::new()) } else { None },
+            ram_and_rtc_enabled: false,
+            rom_bank: 1,
+            ram_bank_or_rtc_select: 0,
+            latch_armed: false,
+        }
+    }
+
+    pub fn step(&mut self, cycles: u8) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.step(cycles);
+        }
+    }
+
+    pub fn rom_read(&self, address: u16) -> u8 {
+        let bank = match address {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => self.rom_bank as usize,
+            _ => panic!("MBC3 ROM read address not implemented: {:04X}", address)
+        };
+        let bank_count = self.rom.len() / ROM_BANK_SIZE;
+        let bank = bank & (bank_count - 1);
+        self.rom[bank * ROM_BANK_SIZE + (address as usize & (ROM_BANK_SIZE - 1))]
+    }
+
+    pub fn rom_write(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_and_rtc_enabled = (value & 0x0F) == 0x0A,
+            0x2000..=0x3FFF => {
+                // Unlike MBC1 the whole 7-bit number is checked, only bank 0 maps to 1
+                self.rom_bank = value & 0x7F;
+                if self.rom_bank == 0 {
+                    self.rom_bank = 1;
+                }
+            }
+            0x4000..=0x5FFF => self.ram_bank_or_rtc_select = value,
+            0x6000..=0x7FFF => {
+                // Writing 0x00 then 0x01 latches the clock
+                if self.latch_armed && value == 0x01 {
+                    if let Some(rtc) = &mut self.rtc {
+                        rtc.latch();
+                    }
+                }
+                self.latch_armed = value == 0x00;
+            }
+            _ => panic!("MBC3 ROM write address not implemented: {:04X}", address)
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    pub fn rom_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.rom
+    }
+
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+
+    pub fn ram_read(&self, address: u16) -> u8 {
+        if !self.ram_and_rtc_enabled {
+            return 0xFF;
+        }
+        match self.ram_bank_or_rtc_select {
+            0x00..=0x03 if !self.ram.is_empty() => self.ram[self.ram_offset(address)],
+            0x08..=0x0C => match &self.rtc {
+                Some(rtc) => rtc.rtc_read(self.ram_bank_or_rtc_select),
+                None => 0xFF,
+            },
+            _ => 0xFF,
+        }
+    }
+
+    // The RTC registers count as stored too, they are part of the save
+    pub fn ram_write(&mut self, address: u16, value: u8) -> bool {
+        if !self.ram_and_rtc_enabled {
+            return false;
+        }
+        match self.ram_bank_or_rtc_select {
+            0x00..=0x03 if !self.ram.is_empty() => {
+                let offset = self.ram_offset(address);
+                self.ram[offset] = value;
+                true
+            }
+            0x08..=0x0C => match &mut self.rtc {
+                Some(rtc) => {
+                    rtc.rtc_write(self.ram_bank_or_rtc_select, value);
+                    true
+                }
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn ram_offset(&self, address: u16) -> usize {
+        let offset = self.ram_bank_or_rtc_select as usize * RAM_BANK_SIZE + (address as usize - 0xA000);
+        offset & (self.ram.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CYCLES_PER_SECOND: usize = 4194304;
+
+    fn mbc3_with_rtc() -> MBC3 {
+        let mut mbc = MBC3::new(&[0; 4 * ROM_BANK_SIZE], 0x8000, true);
+        mbc.rom_write(0x0000, 0x0A);
+        mbc
+    }
+
+    fn run_seconds(mbc: &mut MBC3, seconds: usize) {
+        for _ in 0..(seconds * CYCLES_PER_SECOND / 64) {
+            mbc.step(64);
+        }
+    }
+
+    fn latch(mbc: &mut MBC3) {
+        mbc.rom_write(0x6000, 0x00);
+        mbc.rom_write(0x6000, 0x01);
+    }
+
+    fn read_rtc(mbc: &mut MBC3, register: u8) -> u8 {
+        mbc.rom_write(0x4000, register);
+        mbc.ram_read(0xA000)
+    }
+
+    #[test]
+    fn test_rom_bank_switching() {
+        let mut rom = vec![0; 128 * ROM_BANK_SIZE];
+        rom[0x7F * ROM_BANK_SIZE] = 0x7F;
+        rom[ROM_BANK_SIZE] = 0x01;
+        let mut mbc = MBC3::new(&rom, 0, false);
+
+        mbc.rom_write(0x2000, 0x7F);
+        assert_eq!(mbc.rom_read(0x4000), 0x7F);
+        mbc.rom_write(0x2000, 0x00);
+        assert_eq!(mbc.rom_read(0x4000), 0x01);
+    }
+
+    #[test]
+    fn test_rtc_only_moves_when_latched() {
+        let mut mbc = mbc3_with_rtc();
+        run_seconds(&mut mbc, 2);
+        assert_eq!(read_rtc(&mut mbc, 0x08), 0);
+
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, 0x08), 2);
+    }
+
+    #[test]
+    fn test_rtc_rollover_and_day_carry() {
+        let mut mbc = mbc3_with_rtc();
+        mbc.rom_write(0x4000, 0x08);
+        mbc.ram_write(0xA000, 59);
+        mbc.rom_write(0x4000, 0x09);
+        mbc.ram_write(0xA000, 59);
+        mbc.rom_write(0x4000, 0x0A);
+        mbc.ram_write(0xA000, 23);
+        mbc.rom_write(0x4000, 0x0B);
+        mbc.ram_write(0xA000, 0xFF);
+        mbc.rom_write(0x4000, 0x0C);
+        mbc.ram_write(0xA000, 0x01);
+
+        run_seconds(&mut mbc, 1);
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, 0x08), 0);
+        assert_eq!(read_rtc(&mut mbc, 0x09), 0);
+        assert_eq!(read_rtc(&mut mbc, 0x0A), 0);
+        assert_eq!(read_rtc(&mut mbc, 0x0B), 0);
+        assert_eq!(read_rtc(&mut mbc, 0x0C), 0x80);
+    }
+
+    #[test]
+    fn test_rtc_halt() {
+        let mut mbc = mbc3_with_rtc();
+        mbc.rom_write(0x4000, 0x0C);
+        mbc.ram_write(0xA000, 0x40);
+        run_seconds(&mut mbc, 3);
+        latch(&mut mbc);
+        assert_eq!(read_rtc(&mut mbc, 0x08), 0);
+    }
+
+    #[test]
+    fn test_rtc_state_round_trip() {
+        let mut mbc = mbc3_with_rtc();
+        run_seconds(&mut mbc, 5);
+        latch(&mut mbc);
+        let state = mbc.rtc.as_mut().unwrap().save_state();
+
+        let mut restored = mbc3_with_rtc();
+        restored.rtc.as_mut().unwrap().load_state(&state);
+        assert_eq!(read_rtc(&mut restored, 0x08), 5);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::cart::{pad_rom, RAM_BANK_SIZE, ROM_BANK_SIZE};
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct MBC5 {
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    rom_bank: u16, // 9 bits
+    ram_bank: u8,
+    has_rumble: bool,
+    rumble_active: bool,
+}
+
+impl MBC5 {
+    pub fn new(rom_data: &[u8], ram_size: usize, has_rumble: bool) -> MBC5 {
+        MBC5 {
+            rom: pad_rom(rom_data),
+            ram: vec![0; ram_size],
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            has_rumble,
+            rumble_active: false,
+        }
+    }
+
+    pub fn rumble_active(&self) -> bool {
+        self.rumble_active
+    }
+
+    pub fn rom_read(&self, address: u16) -> u8 {
+        let bank = match address {
+            0x0000..=0x3FFF => 0,
+            0x4000..=0x7FFF => self.rom_bank as usize,
+            _ => panic!("MBC5 ROM read address not implemented: {:04X}", address)
+        };
+        let bank_count = self.rom.len() / ROM_BANK_SIZE;
+        let bank = bank & (bank_count - 1);
+        self.rom[bank * ROM_BANK_SIZE + (address as usize & (ROM_BANK_SIZE - 1))]
+    }
+
+    pub fn rom_write(&mut self, address: u16, value: u8) {
+        match address {
+            0x0000..=0x1FFF => self.ram_enabled = value == 0x0A,
+            // MBC5 has no bank 0 quirk, bank 0 can be mapped at 0x4000
+            0x2000..=0x2FFF => self.rom_bank = (self.rom_bank & 0x100) | value as u16,
+            0x3000..=0x3FFF => self.rom_bank = (self.rom_bank & 0xFF) | (((value & 0x01) as u16) << 8),
+            0x4000..=0x5FFF => {
+                if self.has_rumble {
+                    // Bit 3 drives the rumble motor instead of selecting a RAM bank
+                    self.rumble_active = (value & 0x08) != 0;
+                    self.ram_bank = value & 0x07;
+                } else {
+                    self.ram_bank = value & 0x0F;
+                }
+            }
+            0x6000..=0x7FFF => {}
+            _ => panic!("MBC5 ROM write address not implemented: {:04X}", address)
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    pub fn rom_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.rom
+    }
+
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+
+    pub fn ram_read(&self, address: u16) -> u8 {
+        if !self.ram_enabled || self.ram.is_empty() {
+            return 0xFF;
+        }
+        self.ram[self.ram_offset(address)]
+    }
+
+    pub fn ram_write(&mut self, address: u16, value: u8) -> bool {
+        if !self.ram_enabled || self.ram.is_empty() {
+            return false;
+        }
+        let offset = self.ram_offset(address);
+        self.ram[offset] = value;
+        true
+    }
+
+    fn ram_offset(&self, address: u16) -> usize {
+        let offset = self.ram_bank as usize * RAM_BANK_SIZE + (address as usize - 0xA000);
+        offset & (self.ram.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nine_bit_rom_bank() {
+        let mut rom = vec![0; 512 * ROM_BANK_SIZE];
+        rom[0x1FF * ROM_BANK_SIZE] = 0xAB;
+        rom[0x100 * ROM_BANK_SIZE] = 0xCD;
+        let mut mbc = MBC5::new(&rom, 0, false);
+
+        mbc.rom_write(0x2000, 0xFF);
+        mbc.rom_write(0x3000, 0x01);
+        assert_eq!(mbc.rom_read(0x4000), 0xAB);
+
+        mbc.rom_write(0x2000, 0x00);
+        assert_eq!(mbc.rom_read(0x4000), 0xCD);
+
+        mbc.rom_write(0x3000, 0x00);
+        assert_eq!(mbc.rom_read(0x4000), mbc.rom_read(0x0000));
+    }
+
+    #[test]
+    fn test_rumble_bit_is_not_a_ram_bank() {
+        let mut mbc = MBC5::new(&[0; 2 * ROM_BANK_SIZE], 0x8000, true);
+        mbc.rom_write(0x0000, 0x0A);
+        mbc.ram_write(0xA000, 0x42);
+
+        mbc.rom_write(0x4000, 0x08);
+        assert!(mbc.rumble_active());
+        assert_eq!(mbc.ram_read(0xA000), 0x42);
+    }
+}
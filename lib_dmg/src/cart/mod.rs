@@ -1,3 +1,20 @@
+use crate::cart::header::{CartridgeError, CartridgeHeader, CartridgeType};
+use crate::cart::mbc1::MBC1;
+use crate::cart::mbc2::MBC2;
+use crate::cart::mbc3::MBC3;
+use crate::cart::mbc5::MBC5;
+use crate::cart::rom_only::RomOnly;
+
+pub use crate::cart::rtc::ClockSource;
+
+pub mod header;
+mod mbc1;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+mod rom_only;
+mod rtc;
+
 const DATA: [u8; 256] = [
     0x31, 0xFE, 0xFF, 0xAF, 0x21, 0xFF, 0x9F, 0x32, 0xCB, 0x7C, 0x20, 0xFB,
     0x21, 0x26, 0xFF, 0x0E, 0x11, 0x3E, 0x80, 0x32, 0xE2, 0x0C, 0x3E, 0xF3,
@@ -24,55 +41,303 @@ const DATA: [u8; 256] = [
 ];
 
 
+pub const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83,
+    0x00, 0x0C, 0x00, 0x0D, 0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E,
+    0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99, 0xBB, 0xBB, 0x67, 0x63,
+    0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+
+pub const ROM_BANK_SIZE: usize = 0x4000;
+pub const RAM_BANK_SIZE: usize = 0x2000;
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum MBC {
+    RomOnly(RomOnly),
+    MBC1(MBC1),
+    MBC2(MBC2),
+    MBC3(MBC3),
+    MBC5(MBC5),
+}
+
+impl MBC {
+    pub fn step(&mut self, cycles: u8) {
+        if let MBC::MBC3(mbc) = self {
+            mbc.step(cycles);
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    pub fn rom_mut(&mut self) -> &mut Vec<u8> {
+        match self {
+            MBC::RomOnly(mbc) => mbc.rom_mut(),
+            MBC::MBC1(mbc) => mbc.rom_mut(),
+            MBC::MBC2(mbc) => mbc.rom_mut(),
+            MBC::MBC3(mbc) => mbc.rom_mut(),
+            MBC::MBC5(mbc) => mbc.rom_mut(),
+        }
+    }
+
+    pub fn ram(&self) -> &[u8] {
+        match self {
+            MBC::RomOnly(mbc) => mbc.ram(),
+            MBC::MBC1(mbc) => mbc.ram(),
+            MBC::MBC2(mbc) => mbc.ram(),
+            MBC::MBC3(mbc) => mbc.ram(),
+            MBC::MBC5(mbc) => mbc.ram(),
+        }
+    }
+
+    pub fn ram_mut(&mut self) -> &mut [u8] {
+        match self {
+            MBC::RomOnly(mbc) => mbc.ram_mut(),
+            MBC::MBC1(mbc) => mbc.ram_mut(),
+            MBC::MBC2(mbc) => mbc.ram_mut(),
+            MBC::MBC3(mbc) => mbc.ram_mut(),
+            MBC::MBC5(mbc) => mbc.ram_mut(),
+        }
+    }
+
+    pub fn rom_read(&self, address: u16) -> u8 {
+        match self {
+            MBC::RomOnly(mbc) => mbc.rom_read(address),
+            MBC::MBC1(mbc) => mbc.rom_read(address),
+            MBC::MBC2(mbc) => mbc.rom_read(address),
+            MBC::MBC3(mbc) => mbc.rom_read(address),
+            MBC::MBC5(mbc) => mbc.rom_read(address),
+        }
+    }
+
+    pub fn rom_write(&mut self, address: u16, value: u8) {
+        match self {
+            MBC::RomOnly(mbc) => mbc.rom_write(address, value),
+            MBC::MBC1(mbc) => mbc.rom_write(address, value),
+            MBC::MBC2(mbc) => mbc.rom_write(address, value),
+            MBC::MBC3(mbc) => mbc.rom_write(address, value),
+            MBC::MBC5(mbc) => mbc.rom_write(address, value),
+        }
+    }
+
+    pub fn ram_read(&self, address: u16) -> u8 {
+        match self {
+            MBC::RomOnly(mbc) => mbc.ram_read(address),
+            MBC::MBC1(mbc) => mbc.ram_read(address),
+            MBC::MBC2(mbc) => mbc.ram_read(address),
+            MBC::MBC3(mbc) => mbc.ram_read(address),
+            MBC::MBC5(mbc) => mbc.ram_read(address),
+        }
+    }
+
+    // Returns false when the write was dropped, RAM disabled or missing
+    pub fn ram_write(&mut self, address: u16, value: u8) -> bool {
+        match self {
+            MBC::RomOnly(mbc) => mbc.ram_write(address, value),
+            MBC::MBC1(mbc) => mbc.ram_write(address, value),
+            MBC::MBC2(mbc) => mbc.ram_write(address, value),
+            MBC::MBC3(mbc) => mbc.ram_write(address, value),
+            MBC::MBC5(mbc) => mbc.ram_write(address, value),
+        }
+    }
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Cartridge {
+    pub header: CartridgeHeader,
     pub boot_rom: Option<Vec<u8>>,
-    rom_bank_0: Vec<u8>,
+    mbc: MBC,
+    ram_dirty: bool,
 }
 
 impl Cartridge {
-    pub fn new(rom_data: &[u8]) -> Cartridge {
-        let mut boot_rom = DATA.to_vec();
-        let mut rom_bank = vec![0; 0x8000];
+    pub fn new(rom_data: &[u8]) -> Result<Cartridge, CartridgeError> {
+        let header = CartridgeHeader::parse(rom_data)?;
+        header.verify_logo(rom_data)?;
+        header.verify_header_checksum(rom_data)?;
+        // Overdumps and padded homebrew carry extra data that is never mapped, only a
+        // truncated ROM can't be used
+        if rom_data.len() < header.rom_size {
+            return Err(CartridgeError::RomSizeMismatch { expected: header.rom_size, actual: rom_data.len() });
+        }
 
-        // Copy rom_data into rom_bank.
-        let rom_length = rom_data.len().min(rom_bank.len());
-        rom_bank.splice(0..rom_length, rom_data.iter().cloned());
+        let boot_rom = DATA.to_vec();
+        let ram_size = header.ram_size;
+        let mbc = match header.cartridge_type {
+            CartridgeType::RomOnly | CartridgeType::RomRam | CartridgeType::RomRamBattery =>
+                MBC::RomOnly(RomOnly::new(rom_data, ram_size)),
+            CartridgeType::MBC1 | CartridgeType::MBC1Ram | CartridgeType::MBC1RamBattery =>
+                MBC::MBC1(MBC1::new(rom_data, ram_size)),
+            CartridgeType::MBC2 | CartridgeType::MBC2Battery =>
+                MBC::MBC2(MBC2::new(rom_data)),
+            CartridgeType::MBC3TimerBattery | CartridgeType::MBC3TimerRamBattery
+            | CartridgeType::MBC3 | CartridgeType::MBC3Ram | CartridgeType::MBC3RamBattery =>
+                MBC::MBC3(MBC3::new(rom_data, ram_size, header.cartridge_type.has_rtc())),
+            CartridgeType::MBC5 | CartridgeType::MBC5Ram | CartridgeType::MBC5RamBattery
+            | CartridgeType::MBC5Rumble | CartridgeType::MBC5RumbleRam | CartridgeType::MBC5RumbleRamBattery =>
+                MBC::MBC5(MBC5::new(rom_data, ram_size, header.cartridge_type.has_rumble())),
+        };
 
-        Cartridge {
+        Ok(Cartridge {
+            header,
             boot_rom: Some(boot_rom),
-            rom_bank_0: rom_bank,
+            mbc,
+            ram_dirty: false,
+        })
+    }
+
+    pub fn step(&mut self, cycles: u8) {
+        self.mbc.step(cycles);
+    }
+
+    pub fn set_rtc_clock_source(&mut self, clock_source: ClockSource) {
+        if let MBC::MBC3(MBC3 { rtc: Some(rtc), .. }) = &mut self.mbc {
+            rtc.set_clock_source(clock_source);
         }
     }
 
+    pub fn has_battery(&self) -> bool {
+        self.header.cartridge_type.has_battery()
+    }
+
+    // True when the battery RAM was written to since the last export
+    pub fn is_save_dirty(&self) -> bool {
+        self.ram_dirty && self.has_battery()
+    }
 
-    pub fn cart_read(&mut self, mut address: u16) -> u8 {
+    // Battery RAM in the usual .sav layout: the raw RAM banks, followed for MBC3
+    // carts with a clock by the 48 bytes RTC block used by VBA-M, BGB and mGBA.
+    pub fn export_save(&mut self) -> Option<Vec<u8>> {
+        if !self.has_battery() {
+            return None;
+        }
+        let mut data = self.mbc.ram().to_vec();
+        if let MBC::MBC3(MBC3 { rtc: Some(rtc), .. }) = &mut self.mbc {
+            data.extend_from_slice(&rtc.save_state());
+        }
+        self.ram_dirty = false;
+        Some(data)
+    }
+
+    pub fn load_save(&mut self, data: &[u8]) -> Result<(), CartridgeError> {
+        let ram = self.mbc.ram_mut();
+        let ram_size = ram.len();
+        if data.len() < ram_size {
+            return Err(CartridgeError::InvalidSaveSize { expected: ram_size, actual: data.len() });
+        }
+        ram.copy_from_slice(&data[..ram_size]);
+
+        if let MBC::MBC3(MBC3 { rtc: Some(rtc), .. }) = &mut self.mbc {
+            // Saves from emulators that don't store the clock simply leave it at 0
+            rtc.load_state(&data[ram_size..]);
+        }
+        self.ram_dirty = false;
+        Ok(())
+    }
+
+    // Save states don't carry the ROM, a restored cartridge takes it over from the
+    // one currently inserted. Both must be the same game.
+    #[cfg(feature = "serialize")]
+    pub fn take_rom(&mut self, other: &mut Cartridge) {
+        std::mem::swap(self.mbc.rom_mut(), other.mbc.rom_mut());
+    }
+
+    pub fn is_rumbling(&self) -> bool {
+        match &self.mbc {
+            MBC::MBC5(mbc) => mbc.rumble_active(),
+            _ => false,
+        }
+    }
+
+    pub fn cart_read(&mut self, address: u16) -> u8 {
         match address {
-            0x0000..=0x00FF=> {
-                if let Some(boot_rom) = &self.boot_rom{
+            0x0000..=0x00FF => {
+                if let Some(boot_rom) = &self.boot_rom {
                     boot_rom[address as usize]
-                }else{
-                    self.rom_bank_0[address as usize]
+                } else {
+                    self.mbc.rom_read(address)
                 }
-
             },
-            0x0100..=0x7FFF => self.rom_bank_0[address as usize],
-            /*0x4000..=0x7FFF => {
-                address -= 4000;
-                self.rom_bank_nn[address as usize]
-            }*/
-            _ => panic!("Cart read address not implemented: {:04X}", address)
+            0x0100..=0x7FFF => self.mbc.rom_read(address),
+            0xA000..=0xBFFF => self.mbc.ram_read(address),
+            _ => 0xFF, // Not mapped
         }
     }
 
-    pub fn cart_write(&mut self, mut address: u16, value: u8) {
+    pub fn cart_write(&mut self, address: u16, value: u8) {
         match address {
-            0x0000..=0x7FFF => self.rom_bank_0[address as usize] = value,
-            /*0x4000..=0x7FFF => {
-                address -= 4000;
-                self.rom_bank_nn[address as usize] = value
-            }*/
-            _ => panic!("Cart write address not implemented: {:04X}", address)
+            0x0000..=0x7FFF => self.mbc.rom_write(address, value),
+            0xA000..=0xBFFF => self.ram_dirty |= self.mbc.ram_write(address, value),
+            _ => (),
         }
     }
 }
 
+// Pad the ROM to a power of two number of banks (at least 2) so bank numbers can
+// simply be masked like the real address lines do.
+pub fn pad_rom(rom_data: &[u8]) -> Vec<u8> {
+    let size = rom_data.len().max(2 * ROM_BANK_SIZE).next_power_of_two();
+    let mut rom = vec![0; size];
+    rom[..rom_data.len()].copy_from_slice(rom_data);
+    rom
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cart::header::test_rom;
+
+    #[test]
+    fn test_save_round_trip_with_rtc() {
+        // MBC3+TIMER+RAM+BATTERY with 32 KiB of RAM
+        let mut cart = Cartridge::new(&test_rom(0x10, 0x03)).unwrap();
+        // Dropped while RAM is disabled, nothing to save yet
+        cart.cart_write(0xA123, 0x42);
+        assert!(!cart.is_save_dirty());
+
+        cart.cart_write(0x0000, 0x0A);
+        cart.cart_write(0x4000, 0x03);
+        cart.cart_write(0xA123, 0x42);
+        assert!(cart.is_save_dirty());
+
+        let save = cart.export_save().unwrap();
+        assert_eq!(save.len(), 0x8000 + 48);
+        assert!(!cart.is_save_dirty());
+
+        let mut restored = Cartridge::new(&test_rom(0x10, 0x03)).unwrap();
+        restored.load_save(&save).unwrap();
+        restored.cart_write(0x0000, 0x0A);
+        restored.cart_write(0x4000, 0x03);
+        assert_eq!(restored.cart_read(0xA123), 0x42);
+    }
+
+    #[test]
+    fn test_no_save_without_battery() {
+        // MBC1+RAM, no battery
+        let mut cart = Cartridge::new(&test_rom(0x02, 0x02)).unwrap();
+        cart.cart_write(0x0000, 0x0A);
+        cart.cart_write(0xA000, 0x42);
+        assert!(!cart.is_save_dirty());
+        assert_eq!(cart.export_save(), None);
+    }
+
+    #[test]
+    fn test_rom_size() {
+        let mut rom = test_rom(0x00, 0x00);
+        rom.resize(0x10000, 0xFF);
+        assert!(Cartridge::new(&rom).is_ok());
+
+        rom.truncate(0x4000);
+        assert!(matches!(
+            Cartridge::new(&rom),
+            Err(CartridgeError::RomSizeMismatch { expected: 0x8000, actual: 0x4000 })
+        ));
+    }
+
+    #[test]
+    fn test_reject_short_save() {
+        let mut cart = Cartridge::new(&test_rom(0x03, 0x02)).unwrap();
+        assert_eq!(
+            cart.load_save(&[0; 0x100]),
+            Err(CartridgeError::InvalidSaveSize { expected: 0x2000, actual: 0x100 })
+        );
+    }
+}
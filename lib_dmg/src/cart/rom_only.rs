@@ -0,0 +1,54 @@
+use crate::cart::pad_rom;
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct RomOnly {
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+}
+
+impl RomOnly {
+    pub fn new(rom_data: &[u8], ram_size: usize) -> RomOnly {
+        RomOnly {
+            rom: pad_rom(rom_data),
+            ram: vec![0; ram_size],
+        }
+    }
+
+    pub fn rom_read(&self, address: u16) -> u8 {
+        self.rom[address as usize]
+    }
+
+    pub fn rom_write(&mut self, _address: u16, _value: u8) {
+        // No mapper to talk to, writes to ROM are ignored
+    }
+
+    #[cfg(feature = "serialize")]
+    pub fn rom_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.rom
+    }
+
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    pub fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+
+    pub fn ram_read(&self, address: u16) -> u8 {
+        if self.ram.is_empty() {
+            return 0xFF;
+        }
+        self.ram[(address as usize - 0xA000) % self.ram.len()]
+    }
+
+    pub fn ram_write(&mut self, address: u16, value: u8) -> bool {
+        if self.ram.is_empty() {
+            return false;
+        }
+        let offset = (address as usize - 0xA000) % self.ram.len();
+        self.ram[offset] = value;
+        true
+    }
+}
@@ -0,0 +1,293 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CYCLES_PER_SECOND: u32 = 4194304;
+
+// Size of the RTC block other emulators (VBA-M, BGB, mGBA...) append to MBC3 saves
+pub const RTC_STATE_SIZE: usize = 48;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum ClockSource {
+    Emulated, // Advanced by CPU cycles, deterministic
+    Host,     // Follows the host wall clock
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+struct ClockRegisters {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    day_low: u8,
+    day_high: u8, // bit 0: day bit 8, bit 6: halt, bit 7: day carry
+}
+
+impl ClockRegisters {
+    fn read(&self, register: u8) -> u8 {
+        match register {
+            0x08 => self.seconds & 0x3F,
+            0x09 => self.minutes & 0x3F,
+            0x0A => self.hours & 0x1F,
+            0x0B => self.day_low,
+            0x0C => self.day_high & 0xC1,
+            _ => panic!("RTC register not implemented: {:02X}", register)
+        }
+    }
+
+    fn write(&mut self, register: u8, value: u8) {
+        match register {
+            0x08 => self.seconds = value & 0x3F,
+            0x09 => self.minutes = value & 0x3F,
+            0x0A => self.hours = value & 0x1F,
+            0x0B => self.day_low = value,
+            0x0C => self.day_high = value & 0xC1,
+            _ => panic!("RTC register not implemented: {:02X}", register)
+        }
+    }
+
+    fn days(&self) -> u16 {
+        (((self.day_high & 0x01) as u16) << 8) | self.day_low as u16
+    }
+
+    fn set_days(&mut self, days: u16) {
+        self.day_low = days as u8;
+        self.day_high = (self.day_high & 0xFE) | ((days >> 8) as u8 & 0x01);
+    }
+
+    fn in_range(&self) -> bool {
+        self.seconds < 60 && self.minutes < 60 && self.hours < 24
+    }
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct RTC {
+    current: ClockRegisters,
+    latched: ClockRegisters,
+    cycles: u32,
+    clock_source: ClockSource,
+    last_sync: u64,
+}
+
+impl RTC {
+    pub fn new() -> RTC {
+        RTC {
+            current: ClockRegisters::default(),
+            latched: ClockRegisters::default(),
+            cycles: 0,
+            clock_source: ClockSource::Emulated,
+            last_sync: unix_time(),
+        }
+    }
+
+    pub fn set_clock_source(&mut self, clock_source: ClockSource) {
+        self.clock_source = clock_source;
+        self.last_sync = unix_time();
+    }
+
+    pub fn step(&mut self, cycles: u8) {
+        if self.clock_source != ClockSource::Emulated || self.is_halted() {
+            return;
+        }
+        self.cycles += cycles as u32;
+        while self.cycles >= CYCLES_PER_SECOND {
+            self.cycles -= CYCLES_PER_SECOND;
+            self.tick_second();
+        }
+    }
+
+    pub fn latch(&mut self) {
+        self.sync();
+        self.latched = self.current;
+    }
+
+    pub fn rtc_read(&self, register: u8) -> u8 {
+        self.latched.read(register)
+    }
+
+    pub fn rtc_write(&mut self, register: u8, value: u8) {
+        self.sync();
+        if register == 0x08 {
+            // Writing the seconds resets the sub-second divider
+            self.cycles = 0;
+        }
+        self.current.write(register, value);
+        self.latched.write(register, value);
+    }
+
+    // Layout: current S/M/H/DL/DH and latched S/M/H/DL/DH as little-endian u32,
+    // followed by the UNIX timestamp of the save as a little-endian u64.
+    pub fn save_state(&mut self) -> [u8; RTC_STATE_SIZE] {
+        self.sync();
+        let mut data = [0; RTC_STATE_SIZE];
+        let registers = [
+            self.current.seconds, self.current.minutes, self.current.hours,
+            self.current.day_low, self.current.day_high,
+            self.latched.seconds, self.latched.minutes, self.latched.hours,
+            self.latched.day_low, self.latched.day_high,
+        ];
+        for (index, register) in registers.iter().enumerate() {
+            data[index * 4..index * 4 + 4].copy_from_slice(&(*register as u32).to_le_bytes());
+        }
+        data[40..48].copy_from_slice(&unix_time().to_le_bytes());
+        data
+    }
+
+    pub fn load_state(&mut self, data: &[u8]) {
+        if data.len() < 44 {
+            return;
+        }
+        let register = |index: usize| data[index * 4];
+        self.current = ClockRegisters {
+            seconds: register(0), minutes: register(1), hours: register(2),
+            day_low: register(3), day_high: register(4),
+        };
+        self.latched = ClockRegisters {
+            seconds: register(5), minutes: register(6), hours: register(7),
+            day_low: register(8), day_high: register(9),
+        };
+        self.cycles = 0;
+
+        // Some emulators only store a 32-bit timestamp
+        let mut timestamp = [0; 8];
+        let timestamp_length = (data.len() - 40).min(8);
+        timestamp[..timestamp_length].copy_from_slice(&data[40..40 + timestamp_length]);
+        let timestamp = u64::from_le_bytes(timestamp);
+        // A missing or future timestamp doesn't tell how long the game was off
+        let now = unix_time();
+        self.last_sync = if timestamp == 0 || timestamp > now { now } else { timestamp };
+
+        // Catch up with the time that went by while the game wasn't running
+        if self.clock_source == ClockSource::Host {
+            self.sync();
+        } else {
+            self.last_sync = unix_time();
+        }
+    }
+
+    fn is_halted(&self) -> bool {
+        (self.current.day_high & 0x40) != 0
+    }
+
+    fn sync(&mut self) {
+        if self.clock_source != ClockSource::Host {
+            return;
+        }
+        let now = unix_time();
+        if !self.is_halted() {
+            // Nothing to catch up if the host clock went backwards
+            self.advance(now.saturating_sub(self.last_sync));
+        }
+        self.last_sync = now;
+    }
+
+    // Same as ticking that many seconds, the game may have been off for years
+    fn advance(&mut self, mut seconds: u64) {
+        // Out of range values wrap without carrying, tick through them first. That's a
+        // few hours at most
+        while seconds > 0 && !self.current.in_range() {
+            self.tick_second();
+            seconds -= 1;
+        }
+        if seconds == 0 {
+            return;
+        }
+
+        let total = seconds
+            + self.current.seconds as u64
+            + self.current.minutes as u64 * 60
+            + self.current.hours as u64 * 3600;
+        self.current.seconds = (total % 60) as u8;
+        self.current.minutes = (total / 60 % 60) as u8;
+        self.current.hours = (total / 3600 % 24) as u8;
+
+        let days = self.current.days() as u64 + total / 86400;
+        if days > 0x1FF {
+            self.current.day_high |= 0x80;
+        }
+        self.current.set_days((days & 0x1FF) as u16);
+    }
+
+    fn tick_second(&mut self) {
+        // Out of range values keep counting up to the register width and wrap to 0
+        // without carrying into the next register.
+        self.current.seconds = (self.current.seconds + 1) & 0x3F;
+        if self.current.seconds != 60 {
+            return;
+        }
+        self.current.seconds = 0;
+
+        self.current.minutes = (self.current.minutes + 1) & 0x3F;
+        if self.current.minutes != 60 {
+            return;
+        }
+        self.current.minutes = 0;
+
+        self.current.hours = (self.current.hours + 1) & 0x1F;
+        if self.current.hours != 24 {
+            return;
+        }
+        self.current.hours = 0;
+
+        let days = self.current.days() + 1;
+        if days > 0x1FF {
+            self.current.day_high |= 0x80;
+        }
+        self.current.set_days(days & 0x1FF);
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance_matches_ticking() {
+        let start = [
+            ClockRegisters { seconds: 12, minutes: 34, hours: 5, day_low: 0xFE, day_high: 0x01 },
+            ClockRegisters { seconds: 62, minutes: 61, hours: 30, day_low: 0, day_high: 0 },
+        ];
+        for registers in start {
+            let mut ticked = RTC::new();
+            ticked.current = registers;
+            for _ in 0..200_000 {
+                ticked.tick_second();
+            }
+
+            let mut advanced = RTC::new();
+            advanced.current = registers;
+            advanced.advance(200_000);
+            assert_eq!(advanced.current, ticked.current);
+        }
+    }
+
+    #[test]
+    fn test_long_absence_sets_day_carry() {
+        let mut rtc = RTC::new();
+        rtc.advance(600 * 86400 + 61);
+        assert_eq!(rtc.current.days(), 600 - 512);
+        assert_eq!(rtc.current.day_high & 0x80, 0x80);
+        assert_eq!((rtc.current.minutes, rtc.current.seconds), (1, 1));
+    }
+
+    #[test]
+    fn test_missing_timestamp_is_ignored() {
+        let mut rtc = RTC::new();
+        rtc.set_clock_source(ClockSource::Host);
+        let mut state = rtc.save_state();
+        state[0] = 7;
+        state[40..48].copy_from_slice(&0u64.to_le_bytes());
+        rtc.load_state(&state);
+        assert_eq!(rtc.current.seconds, 7);
+
+        state[40..48].copy_from_slice(&u64::MAX.to_le_bytes());
+        rtc.load_state(&state);
+        assert_eq!(rtc.current.seconds, 7);
+    }
+}
@@ -6,7 +6,7 @@ pub fn rlc(cpu: &mut CPU, register: RegisterName) {
     let mut value: u8;
 
     if register.is_u16_registers() {
-        value = cpu.bus.bus_read(cpu.registers.value_of_u16(&register));
+        value = cpu.read(cpu.registers.value_of_u16(&register));
     } else {
         value = cpu.registers.value_of(&register);
     }
@@ -16,7 +16,7 @@ pub fn rlc(cpu: &mut CPU, register: RegisterName) {
     value = (value << 1) | (if carry_set { 1 } else { 0 });
 
     if register.is_u16_registers() {
-        cpu.bus.bus_write(cpu.registers.value_of_u16(&register), value);
+        cpu.write(cpu.registers.value_of_u16(&register), value);
     } else {
         cpu.registers.write_in(register, value);
     }
@@ -31,7 +31,7 @@ pub fn rrc(cpu: &mut CPU, register: RegisterName) {
     let mut value: u8;
 
     if register.is_u16_registers() {
-        value = cpu.bus.bus_read(cpu.registers.value_of_u16(&register));
+        value = cpu.read(cpu.registers.value_of_u16(&register));
     } else {
         value = cpu.registers.value_of(&register);
     }
@@ -41,7 +41,7 @@ pub fn rrc(cpu: &mut CPU, register: RegisterName) {
     value = (value >> 1) | (if carry_set { 0x80 } else { 0 });
 
     if register.is_u16_registers() {
-        cpu.bus.bus_write(cpu.registers.value_of_u16(&register), value);
+        cpu.write(cpu.registers.value_of_u16(&register), value);
     } else {
         cpu.registers.write_in(register, value);
     }
@@ -56,7 +56,7 @@ pub fn rl(cpu: &mut CPU, register: RegisterName) {
     let mut value: u8;
 
     if register.is_u16_registers() {
-        value = cpu.bus.bus_read(cpu.registers.value_of_u16(&register));
+        value = cpu.read(cpu.registers.value_of_u16(&register));
     } else {
         value = cpu.registers.value_of(&register);
     }
@@ -67,7 +67,7 @@ pub fn rl(cpu: &mut CPU, register: RegisterName) {
     value = (value << 1) | (if old_carry { 1 } else { 0 });
 
     if register.is_u16_registers() {
-        cpu.bus.bus_write(cpu.registers.value_of_u16(&register), value);
+        cpu.write(cpu.registers.value_of_u16(&register), value);
     } else {
         cpu.registers.write_in(register, value);
     }
@@ -83,7 +83,7 @@ pub fn rr(cpu: &mut CPU, register: RegisterName) {
     let mut value: u8;
 
     if register.is_u16_registers() {
-        value = cpu.bus.bus_read(cpu.registers.value_of_u16(&register));
+        value = cpu.read(cpu.registers.value_of_u16(&register));
     } else {
         value = cpu.registers.value_of(&register);
     }
@@ -93,7 +93,7 @@ pub fn rr(cpu: &mut CPU, register: RegisterName) {
     value = (value >> 1) | (if cpu.registers.check_flag(Flag::C) { 0x80 } else { 0 });
 
     if register.is_u16_registers() {
-        cpu.bus.bus_write(cpu.registers.value_of_u16(&register), value);
+        cpu.write(cpu.registers.value_of_u16(&register), value);
     } else {
         cpu.registers.write_in(register, value);
     }
@@ -109,7 +109,7 @@ pub fn sla(cpu: &mut CPU, register: RegisterName) {
     let mut value: u8;
 
     if register.is_u16_registers() {
-        value = cpu.bus.bus_read(cpu.registers.value_of_u16(&register));
+        value = cpu.read(cpu.registers.value_of_u16(&register));
     } else {
         value = cpu.registers.value_of(&register);
     }
@@ -121,7 +121,7 @@ pub fn sla(cpu: &mut CPU, register: RegisterName) {
     value <<= 1;
 
     if register.is_u16_registers() {
-        cpu.bus.bus_write(cpu.registers.value_of_u16(&register), value);
+        cpu.write(cpu.registers.value_of_u16(&register), value);
     } else {
         cpu.registers.write_in(register, value);
     }
@@ -137,7 +137,7 @@ pub fn sra(cpu: &mut CPU, register: RegisterName) {
     let mut value: u8;
 
     if register.is_u16_registers() {
-        value = cpu.bus.bus_read(cpu.registers.value_of_u16(&register));
+        value = cpu.read(cpu.registers.value_of_u16(&register));
     } else {
         value = cpu.registers.value_of(&register);
     }
@@ -150,7 +150,7 @@ pub fn sra(cpu: &mut CPU, register: RegisterName) {
     value = (value >> 1) | msb;
 
     if register.is_u16_registers() {
-        cpu.bus.bus_write(cpu.registers.value_of_u16(&register), value);
+        cpu.write(cpu.registers.value_of_u16(&register), value);
     } else {
         cpu.registers.write_in(register, value);
     }
@@ -167,7 +167,7 @@ pub fn swap(cpu: &mut CPU, register: RegisterName) {
     let mut value: u8;
 
     if register.is_u16_registers() {
-        value = cpu.bus.bus_read(cpu.registers.value_of_u16(&register));
+        value = cpu.read(cpu.registers.value_of_u16(&register));
     } else {
         value = cpu.registers.value_of(&register);
     }
@@ -178,7 +178,7 @@ pub fn swap(cpu: &mut CPU, register: RegisterName) {
     value = (lower_nibble << 4) | upper_nibble;
 
     if register.is_u16_registers() {
-        cpu.bus.bus_write(cpu.registers.value_of_u16(&register), value);
+        cpu.write(cpu.registers.value_of_u16(&register), value);
     } else {
         cpu.registers.write_in(register, value);
     }
@@ -195,7 +195,7 @@ pub fn srl(cpu: &mut CPU, register: RegisterName) {
     let mut value: u8;
 
     if register.is_u16_registers() {
-        value = cpu.bus.bus_read(cpu.registers.value_of_u16(&register));
+        value = cpu.read(cpu.registers.value_of_u16(&register));
     } else {
         value = cpu.registers.value_of(&register);
     }
@@ -205,7 +205,7 @@ pub fn srl(cpu: &mut CPU, register: RegisterName) {
     value >>= 1;
 
     if register.is_u16_registers() {
-        cpu.bus.bus_write(cpu.registers.value_of_u16(&register), value);
+        cpu.write(cpu.registers.value_of_u16(&register), value);
     } else {
         cpu.registers.write_in(register, value);
     }
@@ -223,7 +223,7 @@ pub fn bit(cpu: &mut CPU, bit: u8, register: RegisterName) {
     let value: u8;
 
     if register.is_u16_registers() {
-        value = cpu.bus.bus_read(cpu.registers.value_of_u16(&register));
+        value = cpu.read(cpu.registers.value_of_u16(&register));
     } else {
         value = cpu.registers.value_of(&register);
     }
@@ -246,8 +246,8 @@ pub fn res(cpu: &mut CPU, bit: u8, register: RegisterName) {
     if register.is_u16_registers() {
         // If register points to memory (is a 16-bit register)
         let addr = cpu.registers.value_of_u16(&register);
-        let value = cpu.bus.bus_read(addr);
-        cpu.bus.bus_write(addr, value & mask);
+        let value = cpu.read(addr);
+        cpu.write(addr, value & mask);
     } else {
         // If register is a standard 8-bit register
         let value = cpu.registers.value_of(&register);
@@ -263,8 +263,8 @@ pub fn set(cpu: &mut CPU, bit: u8, register: RegisterName) {
     if register.is_u16_registers() {
         // If register points to memory (is a 16-bit register)
         let addr = cpu.registers.value_of_u16(&register);
-        let value = cpu.bus.bus_read(addr);
-        cpu.bus.bus_write(addr, value | mask);
+        let value = cpu.read(addr);
+        cpu.write(addr, value | mask);
     } else {
         // If register is a standard 8-bit register
         let value = cpu.registers.value_of(&register);
@@ -1,293 +1,291 @@
-use std::collections::HashMap;
-use crate::bus::Bus;
 use crate::cpu::cb_function::*;
 use crate::cpu::CPU;
-use crate::cpu::instructions::OpCodeHandler;
+use crate::cpu::instructions::Instruction;
 use crate::cpu::registers::RegisterName;
 
 
 pub type CBOpCodeHandler = fn(&mut CPU);
 
+const fn cb(mnemonic: &'static str, cycles: u8, flags: &'static str, handler: CBOpCodeHandler) -> Instruction<CBOpCodeHandler> {
+    // Lengths and cycles include the 0xCB prefix
+    Instruction { mnemonic, length: 2, cycles, branch_cycles: cycles, flags, handler: Some(handler) }
+}
 
-pub fn init_cb_opcode_handlers() -> HashMap<u8, CBOpCodeHandler> {
-    let mut map: HashMap<u8, CBOpCodeHandler> = HashMap::new();
-
-
-    map.insert(0x00, |cpu| rlc(cpu, RegisterName::B));
-    map.insert(0x01, |cpu| rlc(cpu, RegisterName::C));
-    map.insert(0x02, |cpu| rlc(cpu, RegisterName::D));
-    map.insert(0x03, |cpu| rlc(cpu, RegisterName::E));
-    map.insert(0x04, |cpu| rlc(cpu, RegisterName::H));
-    map.insert(0x05, |cpu| rlc(cpu, RegisterName::L));
-    map.insert(0x06, |cpu| rlc(cpu, RegisterName::HL));
-    map.insert(0x07, |cpu| rlc(cpu, RegisterName::A));
-
-    map.insert(0x08, |cpu| rrc(cpu, RegisterName::B));
-    map.insert(0x09, |cpu| rrc(cpu, RegisterName::C));
-    map.insert(0x0A, |cpu| rrc(cpu, RegisterName::D));
-    map.insert(0x0B, |cpu| rrc(cpu, RegisterName::E));
-    map.insert(0x0C, |cpu| rrc(cpu, RegisterName::H));
-    map.insert(0x0D, |cpu| rrc(cpu, RegisterName::L));
-    map.insert(0x0E, |cpu| rrc(cpu, RegisterName::HL));
-    map.insert(0x0F, |cpu| rrc(cpu, RegisterName::A));
+pub static CB_INSTRUCTIONS: [Instruction<CBOpCodeHandler>; 256] = init_cb_instructions();
 
-    map.insert(0x10, |cpu| rl(cpu, RegisterName::B));
-    map.insert(0x11, |cpu| rl(cpu, RegisterName::C));
-    map.insert(0x12, |cpu| rl(cpu, RegisterName::D));
-    map.insert(0x13, |cpu| rl(cpu, RegisterName::E));
-    map.insert(0x14, |cpu| rl(cpu, RegisterName::H));
-    map.insert(0x15, |cpu| rl(cpu, RegisterName::L));
-    map.insert(0x16, |cpu| rl(cpu, RegisterName::HL));
-    map.insert(0x17, |cpu| rl(cpu, RegisterName::A));
+const fn init_cb_instructions() -> [Instruction<CBOpCodeHandler>; 256] {
+    let mut table = [Instruction { mnemonic: "", length: 2, cycles: 8, branch_cycles: 8, flags: "----", handler: None }; 256];
 
-    map.insert(0x18, |cpu| rr(cpu, RegisterName::B));
-    map.insert(0x19, |cpu| rr(cpu, RegisterName::C));
-    map.insert(0x1A, |cpu| rr(cpu, RegisterName::D));
-    map.insert(0x1B, |cpu| rr(cpu, RegisterName::E));
-    map.insert(0x1C, |cpu| rr(cpu, RegisterName::H));
-    map.insert(0x1D, |cpu| rr(cpu, RegisterName::L));
-    map.insert(0x1E, |cpu| rr(cpu, RegisterName::HL));
-    map.insert(0x1F, |cpu| rr(cpu, RegisterName::A));
+    table[0x00] = cb("RLC B", 8, "Z00C", |cpu| rlc(cpu, RegisterName::B));
+    table[0x01] = cb("RLC C", 8, "Z00C", |cpu| rlc(cpu, RegisterName::C));
+    table[0x02] = cb("RLC D", 8, "Z00C", |cpu| rlc(cpu, RegisterName::D));
+    table[0x03] = cb("RLC E", 8, "Z00C", |cpu| rlc(cpu, RegisterName::E));
+    table[0x04] = cb("RLC H", 8, "Z00C", |cpu| rlc(cpu, RegisterName::H));
+    table[0x05] = cb("RLC L", 8, "Z00C", |cpu| rlc(cpu, RegisterName::L));
+    table[0x06] = cb("RLC (HL)", 16, "Z00C", |cpu| rlc(cpu, RegisterName::HL));
+    table[0x07] = cb("RLC A", 8, "Z00C", |cpu| rlc(cpu, RegisterName::A));
+    table[0x08] = cb("RRC B", 8, "Z00C", |cpu| rrc(cpu, RegisterName::B));
+    table[0x09] = cb("RRC C", 8, "Z00C", |cpu| rrc(cpu, RegisterName::C));
+    table[0x0A] = cb("RRC D", 8, "Z00C", |cpu| rrc(cpu, RegisterName::D));
+    table[0x0B] = cb("RRC E", 8, "Z00C", |cpu| rrc(cpu, RegisterName::E));
+    table[0x0C] = cb("RRC H", 8, "Z00C", |cpu| rrc(cpu, RegisterName::H));
+    table[0x0D] = cb("RRC L", 8, "Z00C", |cpu| rrc(cpu, RegisterName::L));
+    table[0x0E] = cb("RRC (HL)", 16, "Z00C", |cpu| rrc(cpu, RegisterName::HL));
+    table[0x0F] = cb("RRC A", 8, "Z00C", |cpu| rrc(cpu, RegisterName::A));
 
-    map.insert(0x20, |cpu| sla(cpu, RegisterName::B));
-    map.insert(0x21, |cpu| sla(cpu, RegisterName::C));
-    map.insert(0x22, |cpu| sla(cpu, RegisterName::D));
-    map.insert(0x23, |cpu| sla(cpu, RegisterName::E));
-    map.insert(0x24, |cpu| sla(cpu, RegisterName::H));
-    map.insert(0x25, |cpu| sla(cpu, RegisterName::L));
-    map.insert(0x26, |cpu| sla(cpu, RegisterName::HL));
-    map.insert(0x27, |cpu| sla(cpu, RegisterName::A));
+    table[0x10] = cb("RL B", 8, "Z00C", |cpu| rl(cpu, RegisterName::B));
+    table[0x11] = cb("RL C", 8, "Z00C", |cpu| rl(cpu, RegisterName::C));
+    table[0x12] = cb("RL D", 8, "Z00C", |cpu| rl(cpu, RegisterName::D));
+    table[0x13] = cb("RL E", 8, "Z00C", |cpu| rl(cpu, RegisterName::E));
+    table[0x14] = cb("RL H", 8, "Z00C", |cpu| rl(cpu, RegisterName::H));
+    table[0x15] = cb("RL L", 8, "Z00C", |cpu| rl(cpu, RegisterName::L));
+    table[0x16] = cb("RL (HL)", 16, "Z00C", |cpu| rl(cpu, RegisterName::HL));
+    table[0x17] = cb("RL A", 8, "Z00C", |cpu| rl(cpu, RegisterName::A));
+    table[0x18] = cb("RR B", 8, "Z00C", |cpu| rr(cpu, RegisterName::B));
+    table[0x19] = cb("RR C", 8, "Z00C", |cpu| rr(cpu, RegisterName::C));
+    table[0x1A] = cb("RR D", 8, "Z00C", |cpu| rr(cpu, RegisterName::D));
+    table[0x1B] = cb("RR E", 8, "Z00C", |cpu| rr(cpu, RegisterName::E));
+    table[0x1C] = cb("RR H", 8, "Z00C", |cpu| rr(cpu, RegisterName::H));
+    table[0x1D] = cb("RR L", 8, "Z00C", |cpu| rr(cpu, RegisterName::L));
+    table[0x1E] = cb("RR (HL)", 16, "Z00C", |cpu| rr(cpu, RegisterName::HL));
+    table[0x1F] = cb("RR A", 8, "Z00C", |cpu| rr(cpu, RegisterName::A));
 
-    map.insert(0x28, |cpu| sra(cpu, RegisterName::B));
-    map.insert(0x29, |cpu| sra(cpu, RegisterName::C));
-    map.insert(0x2A, |cpu| sra(cpu, RegisterName::D));
-    map.insert(0x2B, |cpu| sra(cpu, RegisterName::E));
-    map.insert(0x2C, |cpu| sra(cpu, RegisterName::H));
-    map.insert(0x2D, |cpu| sra(cpu, RegisterName::L));
-    map.insert(0x2E, |cpu| sra(cpu, RegisterName::HL));
-    map.insert(0x2F, |cpu| sra(cpu, RegisterName::A));
+    table[0x20] = cb("SLA B", 8, "Z00C", |cpu| sla(cpu, RegisterName::B));
+    table[0x21] = cb("SLA C", 8, "Z00C", |cpu| sla(cpu, RegisterName::C));
+    table[0x22] = cb("SLA D", 8, "Z00C", |cpu| sla(cpu, RegisterName::D));
+    table[0x23] = cb("SLA E", 8, "Z00C", |cpu| sla(cpu, RegisterName::E));
+    table[0x24] = cb("SLA H", 8, "Z00C", |cpu| sla(cpu, RegisterName::H));
+    table[0x25] = cb("SLA L", 8, "Z00C", |cpu| sla(cpu, RegisterName::L));
+    table[0x26] = cb("SLA (HL)", 16, "Z00C", |cpu| sla(cpu, RegisterName::HL));
+    table[0x27] = cb("SLA A", 8, "Z00C", |cpu| sla(cpu, RegisterName::A));
+    table[0x28] = cb("SRA B", 8, "Z00C", |cpu| sra(cpu, RegisterName::B));
+    table[0x29] = cb("SRA C", 8, "Z00C", |cpu| sra(cpu, RegisterName::C));
+    table[0x2A] = cb("SRA D", 8, "Z00C", |cpu| sra(cpu, RegisterName::D));
+    table[0x2B] = cb("SRA E", 8, "Z00C", |cpu| sra(cpu, RegisterName::E));
+    table[0x2C] = cb("SRA H", 8, "Z00C", |cpu| sra(cpu, RegisterName::H));
+    table[0x2D] = cb("SRA L", 8, "Z00C", |cpu| sra(cpu, RegisterName::L));
+    table[0x2E] = cb("SRA (HL)", 16, "Z00C", |cpu| sra(cpu, RegisterName::HL));
+    table[0x2F] = cb("SRA A", 8, "Z00C", |cpu| sra(cpu, RegisterName::A));
 
-    map.insert(0x30, |cpu| swap(cpu, RegisterName::B));
-    map.insert(0x31, |cpu| swap(cpu, RegisterName::C));
-    map.insert(0x32, |cpu| swap(cpu, RegisterName::D));
-    map.insert(0x33, |cpu| swap(cpu, RegisterName::E));
-    map.insert(0x34, |cpu| swap(cpu, RegisterName::H));
-    map.insert(0x35, |cpu| swap(cpu, RegisterName::L));
-    map.insert(0x36, |cpu| swap(cpu, RegisterName::HL));
-    map.insert(0x37, |cpu| swap(cpu, RegisterName::A));
+    table[0x30] = cb("SWAP B", 8, "Z000", |cpu| swap(cpu, RegisterName::B));
+    table[0x31] = cb("SWAP C", 8, "Z000", |cpu| swap(cpu, RegisterName::C));
+    table[0x32] = cb("SWAP D", 8, "Z000", |cpu| swap(cpu, RegisterName::D));
+    table[0x33] = cb("SWAP E", 8, "Z000", |cpu| swap(cpu, RegisterName::E));
+    table[0x34] = cb("SWAP H", 8, "Z000", |cpu| swap(cpu, RegisterName::H));
+    table[0x35] = cb("SWAP L", 8, "Z000", |cpu| swap(cpu, RegisterName::L));
+    table[0x36] = cb("SWAP (HL)", 16, "Z000", |cpu| swap(cpu, RegisterName::HL));
+    table[0x37] = cb("SWAP A", 8, "Z000", |cpu| swap(cpu, RegisterName::A));
+    table[0x38] = cb("SRL B", 8, "Z00C", |cpu| srl(cpu, RegisterName::B));
+    table[0x39] = cb("SRL C", 8, "Z00C", |cpu| srl(cpu, RegisterName::C));
+    table[0x3A] = cb("SRL D", 8, "Z00C", |cpu| srl(cpu, RegisterName::D));
+    table[0x3B] = cb("SRL E", 8, "Z00C", |cpu| srl(cpu, RegisterName::E));
+    table[0x3C] = cb("SRL H", 8, "Z00C", |cpu| srl(cpu, RegisterName::H));
+    table[0x3D] = cb("SRL L", 8, "Z00C", |cpu| srl(cpu, RegisterName::L));
+    table[0x3E] = cb("SRL (HL)", 16, "Z00C", |cpu| srl(cpu, RegisterName::HL));
+    table[0x3F] = cb("SRL A", 8, "Z00C", |cpu| srl(cpu, RegisterName::A));
 
-    map.insert(0x38, |cpu| srl(cpu, RegisterName::B));
-    map.insert(0x39, |cpu| srl(cpu, RegisterName::C));
-    map.insert(0x3A, |cpu| srl(cpu, RegisterName::D));
-    map.insert(0x3B, |cpu| srl(cpu, RegisterName::E));
-    map.insert(0x3C, |cpu| srl(cpu, RegisterName::H));
-    map.insert(0x3D, |cpu| srl(cpu, RegisterName::L));
-    map.insert(0x3E, |cpu| srl(cpu, RegisterName::HL));
-    map.insert(0x3F, |cpu| srl(cpu, RegisterName::A));
+    table[0x40] = cb("BIT 0,B", 8, "Z01-", |cpu| bit(cpu, 0, RegisterName::B));
+    table[0x41] = cb("BIT 0,C", 8, "Z01-", |cpu| bit(cpu, 0, RegisterName::C));
+    table[0x42] = cb("BIT 0,D", 8, "Z01-", |cpu| bit(cpu, 0, RegisterName::D));
+    table[0x43] = cb("BIT 0,E", 8, "Z01-", |cpu| bit(cpu, 0, RegisterName::E));
+    table[0x44] = cb("BIT 0,H", 8, "Z01-", |cpu| bit(cpu, 0, RegisterName::H));
+    table[0x45] = cb("BIT 0,L", 8, "Z01-", |cpu| bit(cpu, 0, RegisterName::L));
+    table[0x46] = cb("BIT 0,(HL)", 12, "Z01-", |cpu| bit(cpu, 0, RegisterName::HL));
+    table[0x47] = cb("BIT 0,A", 8, "Z01-", |cpu| bit(cpu, 0, RegisterName::A));
+    table[0x48] = cb("BIT 1,B", 8, "Z01-", |cpu| bit(cpu, 1, RegisterName::B));
+    table[0x49] = cb("BIT 1,C", 8, "Z01-", |cpu| bit(cpu, 1, RegisterName::C));
+    table[0x4A] = cb("BIT 1,D", 8, "Z01-", |cpu| bit(cpu, 1, RegisterName::D));
+    table[0x4B] = cb("BIT 1,E", 8, "Z01-", |cpu| bit(cpu, 1, RegisterName::E));
+    table[0x4C] = cb("BIT 1,H", 8, "Z01-", |cpu| bit(cpu, 1, RegisterName::H));
+    table[0x4D] = cb("BIT 1,L", 8, "Z01-", |cpu| bit(cpu, 1, RegisterName::L));
+    table[0x4E] = cb("BIT 1,(HL)", 12, "Z01-", |cpu| bit(cpu, 1, RegisterName::HL));
+    table[0x4F] = cb("BIT 1,A", 8, "Z01-", |cpu| bit(cpu, 1, RegisterName::A));
 
-    map.insert(0x40, |cpu| bit(cpu, 0, RegisterName::B));
-    map.insert(0x41, |cpu| bit(cpu, 0, RegisterName::C));
-    map.insert(0x42, |cpu| bit(cpu, 0, RegisterName::D));
-    map.insert(0x43, |cpu| bit(cpu, 0, RegisterName::E));
-    map.insert(0x44, |cpu| bit(cpu, 0, RegisterName::H));
-    map.insert(0x45, |cpu| bit(cpu, 0, RegisterName::L));
-    map.insert(0x46, |cpu| bit(cpu, 0, RegisterName::HL));
-    map.insert(0x47, |cpu| bit(cpu, 0, RegisterName::A));
+    table[0x50] = cb("BIT 2,B", 8, "Z01-", |cpu| bit(cpu, 2, RegisterName::B));
+    table[0x51] = cb("BIT 2,C", 8, "Z01-", |cpu| bit(cpu, 2, RegisterName::C));
+    table[0x52] = cb("BIT 2,D", 8, "Z01-", |cpu| bit(cpu, 2, RegisterName::D));
+    table[0x53] = cb("BIT 2,E", 8, "Z01-", |cpu| bit(cpu, 2, RegisterName::E));
+    table[0x54] = cb("BIT 2,H", 8, "Z01-", |cpu| bit(cpu, 2, RegisterName::H));
+    table[0x55] = cb("BIT 2,L", 8, "Z01-", |cpu| bit(cpu, 2, RegisterName::L));
+    table[0x56] = cb("BIT 2,(HL)", 12, "Z01-", |cpu| bit(cpu, 2, RegisterName::HL));
+    table[0x57] = cb("BIT 2,A", 8, "Z01-", |cpu| bit(cpu, 2, RegisterName::A));
+    table[0x58] = cb("BIT 3,B", 8, "Z01-", |cpu| bit(cpu, 3, RegisterName::B));
+    table[0x59] = cb("BIT 3,C", 8, "Z01-", |cpu| bit(cpu, 3, RegisterName::C));
+    table[0x5A] = cb("BIT 3,D", 8, "Z01-", |cpu| bit(cpu, 3, RegisterName::D));
+    table[0x5B] = cb("BIT 3,E", 8, "Z01-", |cpu| bit(cpu, 3, RegisterName::E));
+    table[0x5C] = cb("BIT 3,H", 8, "Z01-", |cpu| bit(cpu, 3, RegisterName::H));
+    table[0x5D] = cb("BIT 3,L", 8, "Z01-", |cpu| bit(cpu, 3, RegisterName::L));
+    table[0x5E] = cb("BIT 3,(HL)", 12, "Z01-", |cpu| bit(cpu, 3, RegisterName::HL));
+    table[0x5F] = cb("BIT 3,A", 8, "Z01-", |cpu| bit(cpu, 3, RegisterName::A));
 
-    map.insert(0x48, |cpu| bit(cpu, 1, RegisterName::B));
-    map.insert(0x49, |cpu| bit(cpu, 1, RegisterName::C));
-    map.insert(0x4A, |cpu| bit(cpu, 1, RegisterName::D));
-    map.insert(0x4B, |cpu| bit(cpu, 1, RegisterName::E));
-    map.insert(0x4C, |cpu| bit(cpu, 1, RegisterName::H));
-    map.insert(0x4D, |cpu| bit(cpu, 1, RegisterName::L));
-    map.insert(0x4E, |cpu| bit(cpu, 1, RegisterName::HL));
-    map.insert(0x4F, |cpu| bit(cpu, 1, RegisterName::A));
+    table[0x60] = cb("BIT 4,B", 8, "Z01-", |cpu| bit(cpu, 4, RegisterName::B));
+    table[0x61] = cb("BIT 4,C", 8, "Z01-", |cpu| bit(cpu, 4, RegisterName::C));
+    table[0x62] = cb("BIT 4,D", 8, "Z01-", |cpu| bit(cpu, 4, RegisterName::D));
+    table[0x63] = cb("BIT 4,E", 8, "Z01-", |cpu| bit(cpu, 4, RegisterName::E));
+    table[0x64] = cb("BIT 4,H", 8, "Z01-", |cpu| bit(cpu, 4, RegisterName::H));
+    table[0x65] = cb("BIT 4,L", 8, "Z01-", |cpu| bit(cpu, 4, RegisterName::L));
+    table[0x66] = cb("BIT 4,(HL)", 12, "Z01-", |cpu| bit(cpu, 4, RegisterName::HL));
+    table[0x67] = cb("BIT 4,A", 8, "Z01-", |cpu| bit(cpu, 4, RegisterName::A));
+    table[0x68] = cb("BIT 5,B", 8, "Z01-", |cpu| bit(cpu, 5, RegisterName::B));
+    table[0x69] = cb("BIT 5,C", 8, "Z01-", |cpu| bit(cpu, 5, RegisterName::C));
+    table[0x6A] = cb("BIT 5,D", 8, "Z01-", |cpu| bit(cpu, 5, RegisterName::D));
+    table[0x6B] = cb("BIT 5,E", 8, "Z01-", |cpu| bit(cpu, 5, RegisterName::E));
+    table[0x6C] = cb("BIT 5,H", 8, "Z01-", |cpu| bit(cpu, 5, RegisterName::H));
+    table[0x6D] = cb("BIT 5,L", 8, "Z01-", |cpu| bit(cpu, 5, RegisterName::L));
+    table[0x6E] = cb("BIT 5,(HL)", 12, "Z01-", |cpu| bit(cpu, 5, RegisterName::HL));
+    table[0x6F] = cb("BIT 5,A", 8, "Z01-", |cpu| bit(cpu, 5, RegisterName::A));
 
-    map.insert(0x50, |cpu| bit(cpu, 2, RegisterName::B));
-    map.insert(0x51, |cpu| bit(cpu, 2, RegisterName::C));
-    map.insert(0x52, |cpu| bit(cpu, 2, RegisterName::D));
-    map.insert(0x53, |cpu| bit(cpu, 2, RegisterName::E));
-    map.insert(0x54, |cpu| bit(cpu, 2, RegisterName::H));
-    map.insert(0x55, |cpu| bit(cpu, 2, RegisterName::L));
-    map.insert(0x56, |cpu| bit(cpu, 2, RegisterName::HL));
-    map.insert(0x57, |cpu| bit(cpu, 2, RegisterName::A));
+    table[0x70] = cb("BIT 6,B", 8, "Z01-", |cpu| bit(cpu, 6, RegisterName::B));
+    table[0x71] = cb("BIT 6,C", 8, "Z01-", |cpu| bit(cpu, 6, RegisterName::C));
+    table[0x72] = cb("BIT 6,D", 8, "Z01-", |cpu| bit(cpu, 6, RegisterName::D));
+    table[0x73] = cb("BIT 6,E", 8, "Z01-", |cpu| bit(cpu, 6, RegisterName::E));
+    table[0x74] = cb("BIT 6,H", 8, "Z01-", |cpu| bit(cpu, 6, RegisterName::H));
+    table[0x75] = cb("BIT 6,L", 8, "Z01-", |cpu| bit(cpu, 6, RegisterName::L));
+    table[0x76] = cb("BIT 6,(HL)", 12, "Z01-", |cpu| bit(cpu, 6, RegisterName::HL));
+    table[0x77] = cb("BIT 6,A", 8, "Z01-", |cpu| bit(cpu, 6, RegisterName::A));
+    table[0x78] = cb("BIT 7,B", 8, "Z01-", |cpu| bit(cpu, 7, RegisterName::B));
+    table[0x79] = cb("BIT 7,C", 8, "Z01-", |cpu| bit(cpu, 7, RegisterName::C));
+    table[0x7A] = cb("BIT 7,D", 8, "Z01-", |cpu| bit(cpu, 7, RegisterName::D));
+    table[0x7B] = cb("BIT 7,E", 8, "Z01-", |cpu| bit(cpu, 7, RegisterName::E));
+    table[0x7C] = cb("BIT 7,H", 8, "Z01-", |cpu| bit(cpu, 7, RegisterName::H));
+    table[0x7D] = cb("BIT 7,L", 8, "Z01-", |cpu| bit(cpu, 7, RegisterName::L));
+    table[0x7E] = cb("BIT 7,(HL)", 12, "Z01-", |cpu| bit(cpu, 7, RegisterName::HL));
+    table[0x7F] = cb("BIT 7,A", 8, "Z01-", |cpu| bit(cpu, 7, RegisterName::A));
 
-    map.insert(0x58, |cpu| bit(cpu, 3, RegisterName::B));
-    map.insert(0x59, |cpu| bit(cpu, 3, RegisterName::C));
-    map.insert(0x5A, |cpu| bit(cpu, 3, RegisterName::D));
-    map.insert(0x5B, |cpu| bit(cpu, 3, RegisterName::E));
-    map.insert(0x5C, |cpu| bit(cpu, 3, RegisterName::H));
-    map.insert(0x5D, |cpu| bit(cpu, 3, RegisterName::L));
-    map.insert(0x5E, |cpu| bit(cpu, 3, RegisterName::HL));
-    map.insert(0x5F, |cpu| bit(cpu, 3, RegisterName::A));
+    table[0x80] = cb("RES 0,B", 8, "----", |cpu| res(cpu, 0, RegisterName::B));
+    table[0x81] = cb("RES 0,C", 8, "----", |cpu| res(cpu, 0, RegisterName::C));
+    table[0x82] = cb("RES 0,D", 8, "----", |cpu| res(cpu, 0, RegisterName::D));
+    table[0x83] = cb("RES 0,E", 8, "----", |cpu| res(cpu, 0, RegisterName::E));
+    table[0x84] = cb("RES 0,H", 8, "----", |cpu| res(cpu, 0, RegisterName::H));
+    table[0x85] = cb("RES 0,L", 8, "----", |cpu| res(cpu, 0, RegisterName::L));
+    table[0x86] = cb("RES 0,(HL)", 16, "----", |cpu| res(cpu, 0, RegisterName::HL));
+    table[0x87] = cb("RES 0,A", 8, "----", |cpu| res(cpu, 0, RegisterName::A));
+    table[0x88] = cb("RES 1,B", 8, "----", |cpu| res(cpu, 1, RegisterName::B));
+    table[0x89] = cb("RES 1,C", 8, "----", |cpu| res(cpu, 1, RegisterName::C));
+    table[0x8A] = cb("RES 1,D", 8, "----", |cpu| res(cpu, 1, RegisterName::D));
+    table[0x8B] = cb("RES 1,E", 8, "----", |cpu| res(cpu, 1, RegisterName::E));
+    table[0x8C] = cb("RES 1,H", 8, "----", |cpu| res(cpu, 1, RegisterName::H));
+    table[0x8D] = cb("RES 1,L", 8, "----", |cpu| res(cpu, 1, RegisterName::L));
+    table[0x8E] = cb("RES 1,(HL)", 16, "----", |cpu| res(cpu, 1, RegisterName::HL));
+    table[0x8F] = cb("RES 1,A", 8, "----", |cpu| res(cpu, 1, RegisterName::A));
 
-    map.insert(0x60, |cpu| bit(cpu, 4, RegisterName::B));
-    map.insert(0x61, |cpu| bit(cpu, 4, RegisterName::C));
-    map.insert(0x62, |cpu| bit(cpu, 4, RegisterName::D));
-    map.insert(0x63, |cpu| bit(cpu, 4, RegisterName::E));
-    map.insert(0x64, |cpu| bit(cpu, 4, RegisterName::H));
-    map.insert(0x65, |cpu| bit(cpu, 4, RegisterName::L));
-    map.insert(0x66, |cpu| bit(cpu, 4, RegisterName::HL));
-    map.insert(0x67, |cpu| bit(cpu, 4, RegisterName::A));
+    table[0x90] = cb("RES 2,B", 8, "----", |cpu| res(cpu, 2, RegisterName::B));
+    table[0x91] = cb("RES 2,C", 8, "----", |cpu| res(cpu, 2, RegisterName::C));
+    table[0x92] = cb("RES 2,D", 8, "----", |cpu| res(cpu, 2, RegisterName::D));
+    table[0x93] = cb("RES 2,E", 8, "----", |cpu| res(cpu, 2, RegisterName::E));
+    table[0x94] = cb("RES 2,H", 8, "----", |cpu| res(cpu, 2, RegisterName::H));
+    table[0x95] = cb("RES 2,L", 8, "----", |cpu| res(cpu, 2, RegisterName::L));
+    table[0x96] = cb("RES 2,(HL)", 16, "----", |cpu| res(cpu, 2, RegisterName::HL));
+    table[0x97] = cb("RES 2,A", 8, "----", |cpu| res(cpu, 2, RegisterName::A));
+    table[0x98] = cb("RES 3,B", 8, "----", |cpu| res(cpu, 3, RegisterName::B));
+    table[0x99] = cb("RES 3,C", 8, "----", |cpu| res(cpu, 3, RegisterName::C));
+    table[0x9A] = cb("RES 3,D", 8, "----", |cpu| res(cpu, 3, RegisterName::D));
+    table[0x9B] = cb("RES 3,E", 8, "----", |cpu| res(cpu, 3, RegisterName::E));
+    table[0x9C] = cb("RES 3,H", 8, "----", |cpu| res(cpu, 3, RegisterName::H));
+    table[0x9D] = cb("RES 3,L", 8, "----", |cpu| res(cpu, 3, RegisterName::L));
+    table[0x9E] = cb("RES 3,(HL)", 16, "----", |cpu| res(cpu, 3, RegisterName::HL));
+    table[0x9F] = cb("RES 3,A", 8, "----", |cpu| res(cpu, 3, RegisterName::A));
 
-    map.insert(0x68, |cpu| bit(cpu, 5, RegisterName::B));
-    map.insert(0x69, |cpu| bit(cpu, 5, RegisterName::C));
-    map.insert(0x6A, |cpu| bit(cpu, 5, RegisterName::D));
-    map.insert(0x6B, |cpu| bit(cpu, 5, RegisterName::E));
-    map.insert(0x6C, |cpu| bit(cpu, 5, RegisterName::H));
-    map.insert(0x6D, |cpu| bit(cpu, 5, RegisterName::L));
-    map.insert(0x6E, |cpu| bit(cpu, 5, RegisterName::HL));
-    map.insert(0x6F, |cpu| bit(cpu, 5, RegisterName::A));
+    table[0xA0] = cb("RES 4,B", 8, "----", |cpu| res(cpu, 4, RegisterName::B));
+    table[0xA1] = cb("RES 4,C", 8, "----", |cpu| res(cpu, 4, RegisterName::C));
+    table[0xA2] = cb("RES 4,D", 8, "----", |cpu| res(cpu, 4, RegisterName::D));
+    table[0xA3] = cb("RES 4,E", 8, "----", |cpu| res(cpu, 4, RegisterName::E));
+    table[0xA4] = cb("RES 4,H", 8, "----", |cpu| res(cpu, 4, RegisterName::H));
+    table[0xA5] = cb("RES 4,L", 8, "----", |cpu| res(cpu, 4, RegisterName::L));
+    table[0xA6] = cb("RES 4,(HL)", 16, "----", |cpu| res(cpu, 4, RegisterName::HL));
+    table[0xA7] = cb("RES 4,A", 8, "----", |cpu| res(cpu, 4, RegisterName::A));
+    table[0xA8] = cb("RES 5,B", 8, "----", |cpu| res(cpu, 5, RegisterName::B));
+    table[0xA9] = cb("RES 5,C", 8, "----", |cpu| res(cpu, 5, RegisterName::C));
+    table[0xAA] = cb("RES 5,D", 8, "----", |cpu| res(cpu, 5, RegisterName::D));
+    table[0xAB] = cb("RES 5,E", 8, "----", |cpu| res(cpu, 5, RegisterName::E));
+    table[0xAC] = cb("RES 5,H", 8, "----", |cpu| res(cpu, 5, RegisterName::H));
+    table[0xAD] = cb("RES 5,L", 8, "----", |cpu| res(cpu, 5, RegisterName::L));
+    table[0xAE] = cb("RES 5,(HL)", 16, "----", |cpu| res(cpu, 5, RegisterName::HL));
+    table[0xAF] = cb("RES 5,A", 8, "----", |cpu| res(cpu, 5, RegisterName::A));
 
-    map.insert(0x70, |cpu| bit(cpu, 6, RegisterName::B));
-    map.insert(0x71, |cpu| bit(cpu, 6, RegisterName::C));
-    map.insert(0x72, |cpu| bit(cpu, 6, RegisterName::D));
-    map.insert(0x73, |cpu| bit(cpu, 6, RegisterName::E));
-    map.insert(0x74, |cpu| bit(cpu, 6, RegisterName::H));
-    map.insert(0x75, |cpu| bit(cpu, 6, RegisterName::L));
-    map.insert(0x76, |cpu| bit(cpu, 6, RegisterName::HL));
-    map.insert(0x77, |cpu| bit(cpu, 6, RegisterName::A));
+    table[0xB0] = cb("RES 6,B", 8, "----", |cpu| res(cpu, 6, RegisterName::B));
+    table[0xB1] = cb("RES 6,C", 8, "----", |cpu| res(cpu, 6, RegisterName::C));
+    table[0xB2] = cb("RES 6,D", 8, "----", |cpu| res(cpu, 6, RegisterName::D));
+    table[0xB3] = cb("RES 6,E", 8, "----", |cpu| res(cpu, 6, RegisterName::E));
+    table[0xB4] = cb("RES 6,H", 8, "----", |cpu| res(cpu, 6, RegisterName::H));
+    table[0xB5] = cb("RES 6,L", 8, "----", |cpu| res(cpu, 6, RegisterName::L));
+    table[0xB6] = cb("RES 6,(HL)", 16, "----", |cpu| res(cpu, 6, RegisterName::HL));
+    table[0xB7] = cb("RES 6,A", 8, "----", |cpu| res(cpu, 6, RegisterName::A));
+    table[0xB8] = cb("RES 7,B", 8, "----", |cpu| res(cpu, 7, RegisterName::B));
+    table[0xB9] = cb("RES 7,C", 8, "----", |cpu| res(cpu, 7, RegisterName::C));
+    table[0xBA] = cb("RES 7,D", 8, "----", |cpu| res(cpu, 7, RegisterName::D));
+    table[0xBB] = cb("RES 7,E", 8, "----", |cpu| res(cpu, 7, RegisterName::E));
+    table[0xBC] = cb("RES 7,H", 8, "----", |cpu| res(cpu, 7, RegisterName::H));
+    table[0xBD] = cb("RES 7,L", 8, "----", |cpu| res(cpu, 7, RegisterName::L));
+    table[0xBE] = cb("RES 7,(HL)", 16, "----", |cpu| res(cpu, 7, RegisterName::HL));
+    table[0xBF] = cb("RES 7,A", 8, "----", |cpu| res(cpu, 7, RegisterName::A));
 
-    map.insert(0x78, |cpu| bit(cpu, 7, RegisterName::B));
-    map.insert(0x79, |cpu| bit(cpu, 7, RegisterName::C));
-    map.insert(0x7A, |cpu| bit(cpu, 7, RegisterName::D));
-    map.insert(0x7B, |cpu| bit(cpu, 7, RegisterName::E));
-    map.insert(0x7C, |cpu| bit(cpu, 7, RegisterName::H));
-    map.insert(0x7D, |cpu| bit(cpu, 7, RegisterName::L));
-    map.insert(0x7E, |cpu| bit(cpu, 7, RegisterName::HL));
-    map.insert(0x7F, |cpu| bit(cpu, 7, RegisterName::A));
+    table[0xC0] = cb("SET 0,B", 8, "----", |cpu| set(cpu, 0, RegisterName::B));
+    table[0xC1] = cb("SET 0,C", 8, "----", |cpu| set(cpu, 0, RegisterName::C));
+    table[0xC2] = cb("SET 0,D", 8, "----", |cpu| set(cpu, 0, RegisterName::D));
+    table[0xC3] = cb("SET 0,E", 8, "----", |cpu| set(cpu, 0, RegisterName::E));
+    table[0xC4] = cb("SET 0,H", 8, "----", |cpu| set(cpu, 0, RegisterName::H));
+    table[0xC5] = cb("SET 0,L", 8, "----", |cpu| set(cpu, 0, RegisterName::L));
+    table[0xC6] = cb("SET 0,(HL)", 16, "----", |cpu| set(cpu, 0, RegisterName::HL));
+    table[0xC7] = cb("SET 0,A", 8, "----", |cpu| set(cpu, 0, RegisterName::A));
+    table[0xC8] = cb("SET 1,B", 8, "----", |cpu| set(cpu, 1, RegisterName::B));
+    table[0xC9] = cb("SET 1,C", 8, "----", |cpu| set(cpu, 1, RegisterName::C));
+    table[0xCA] = cb("SET 1,D", 8, "----", |cpu| set(cpu, 1, RegisterName::D));
+    table[0xCB] = cb("SET 1,E", 8, "----", |cpu| set(cpu, 1, RegisterName::E));
+    table[0xCC] = cb("SET 1,H", 8, "----", |cpu| set(cpu, 1, RegisterName::H));
+    table[0xCD] = cb("SET 1,L", 8, "----", |cpu| set(cpu, 1, RegisterName::L));
+    table[0xCE] = cb("SET 1,(HL)", 16, "----", |cpu| set(cpu, 1, RegisterName::HL));
+    table[0xCF] = cb("SET 1,A", 8, "----", |cpu| set(cpu, 1, RegisterName::A));
 
-    map.insert(0x80, |cpu| res(cpu, 0, RegisterName::B));
-    map.insert(0x81, |cpu| res(cpu, 0, RegisterName::C));
-    map.insert(0x82, |cpu| res(cpu, 0, RegisterName::D));
-    map.insert(0x83, |cpu| res(cpu, 0, RegisterName::E));
-    map.insert(0x84, |cpu| res(cpu, 0, RegisterName::H));
-    map.insert(0x85, |cpu| res(cpu, 0, RegisterName::L));
-    map.insert(0x86, |cpu| res(cpu, 0, RegisterName::HL));
-    map.insert(0x87, |cpu| res(cpu, 0, RegisterName::A));
-    map.insert(0x88, |cpu| res(cpu, 1, RegisterName::B));
-    map.insert(0x89, |cpu| res(cpu, 1, RegisterName::C));
-    map.insert(0x8A, |cpu| res(cpu, 1, RegisterName::D));
-    map.insert(0x8B, |cpu| res(cpu, 1, RegisterName::E));
-    map.insert(0x8C, |cpu| res(cpu, 1, RegisterName::H));
-    map.insert(0x8D, |cpu| res(cpu, 1, RegisterName::L));
-    map.insert(0x8E, |cpu| res(cpu, 1, RegisterName::HL));
-    map.insert(0x8F, |cpu| res(cpu, 1, RegisterName::A));
-    map.insert(0x90, |cpu| res(cpu, 2, RegisterName::B));
-    map.insert(0x91, |cpu| res(cpu, 2, RegisterName::C));
-    map.insert(0x92, |cpu| res(cpu, 2, RegisterName::D));
-    map.insert(0x93, |cpu| res(cpu, 2, RegisterName::E));
-    map.insert(0x94, |cpu| res(cpu, 2, RegisterName::H));
-    map.insert(0x95, |cpu| res(cpu, 2, RegisterName::L));
-    map.insert(0x96, |cpu| res(cpu, 2, RegisterName::HL));
-    map.insert(0x97, |cpu| res(cpu, 2, RegisterName::A));
-    map.insert(0x98, |cpu| res(cpu, 3, RegisterName::B));
-    map.insert(0x99, |cpu| res(cpu, 3, RegisterName::C));
-    map.insert(0x9A, |cpu| res(cpu, 3, RegisterName::D));
-    map.insert(0x9B, |cpu| res(cpu, 3, RegisterName::E));
-    map.insert(0x9C, |cpu| res(cpu, 3, RegisterName::H));
-    map.insert(0x9D, |cpu| res(cpu, 3, RegisterName::L));
-    map.insert(0x9E, |cpu| res(cpu, 3, RegisterName::HL));
-    map.insert(0x9F, |cpu| res(cpu, 3, RegisterName::A));
-    map.insert(0xA0, |cpu| res(cpu, 4, RegisterName::B));
-    map.insert(0xA1, |cpu| res(cpu, 4, RegisterName::C));
-    map.insert(0xA2, |cpu| res(cpu, 4, RegisterName::D));
-    map.insert(0xA3, |cpu| res(cpu, 4, RegisterName::E));
-    map.insert(0xA4, |cpu| res(cpu, 4, RegisterName::H));
-    map.insert(0xA5, |cpu| res(cpu, 4, RegisterName::L));
-    map.insert(0xA6, |cpu| res(cpu, 4, RegisterName::HL));
-    map.insert(0xA7, |cpu| res(cpu, 4, RegisterName::A));
-    map.insert(0xA8, |cpu| res(cpu, 5, RegisterName::B));
-    map.insert(0xA9, |cpu| res(cpu, 5, RegisterName::C));
-    map.insert(0xAA, |cpu| res(cpu, 5, RegisterName::D));
-    map.insert(0xAB, |cpu| res(cpu, 5, RegisterName::E));
-    map.insert(0xAC, |cpu| res(cpu, 5, RegisterName::H));
-    map.insert(0xAD, |cpu| res(cpu, 5, RegisterName::L));
-    map.insert(0xAE, |cpu| res(cpu, 5, RegisterName::HL));
-    map.insert(0xAF, |cpu| res(cpu, 5, RegisterName::A));
-    map.insert(0xB0, |cpu| res(cpu, 6, RegisterName::B));
-    map.insert(0xB1, |cpu| res(cpu, 6, RegisterName::C));
-    map.insert(0xB2, |cpu| res(cpu, 6, RegisterName::D));
-    map.insert(0xB3, |cpu| res(cpu, 6, RegisterName::E));
-    map.insert(0xB4, |cpu| res(cpu, 6, RegisterName::H));
-    map.insert(0xB5, |cpu| res(cpu, 6, RegisterName::L));
-    map.insert(0xB6, |cpu| res(cpu, 6, RegisterName::HL));
-    map.insert(0xB7, |cpu| res(cpu, 6, RegisterName::A));
-    map.insert(0xB8, |cpu| res(cpu, 7, RegisterName::B));
-    map.insert(0xB9, |cpu| res(cpu, 7, RegisterName::C));
-    map.insert(0xBA, |cpu| res(cpu, 7, RegisterName::D));
-    map.insert(0xBB, |cpu| res(cpu, 7, RegisterName::E));
-    map.insert(0xBC, |cpu| res(cpu, 7, RegisterName::H));
-    map.insert(0xBD, |cpu| res(cpu, 7, RegisterName::L));
-    map.insert(0xBE, |cpu| res(cpu, 7, RegisterName::HL));
-    map.insert(0xBF, |cpu| res(cpu, 7, RegisterName::A));
+    table[0xD0] = cb("SET 2,B", 8, "----", |cpu| set(cpu, 2, RegisterName::B));
+    table[0xD1] = cb("SET 2,C", 8, "----", |cpu| set(cpu, 2, RegisterName::C));
+    table[0xD2] = cb("SET 2,D", 8, "----", |cpu| set(cpu, 2, RegisterName::D));
+    table[0xD3] = cb("SET 2,E", 8, "----", |cpu| set(cpu, 2, RegisterName::E));
+    table[0xD4] = cb("SET 2,H", 8, "----", |cpu| set(cpu, 2, RegisterName::H));
+    table[0xD5] = cb("SET 2,L", 8, "----", |cpu| set(cpu, 2, RegisterName::L));
+    table[0xD6] = cb("SET 2,(HL)", 16, "----", |cpu| set(cpu, 2, RegisterName::HL));
+    table[0xD7] = cb("SET 2,A", 8, "----", |cpu| set(cpu, 2, RegisterName::A));
+    table[0xD8] = cb("SET 3,B", 8, "----", |cpu| set(cpu, 3, RegisterName::B));
+    table[0xD9] = cb("SET 3,C", 8, "----", |cpu| set(cpu, 3, RegisterName::C));
+    table[0xDA] = cb("SET 3,D", 8, "----", |cpu| set(cpu, 3, RegisterName::D));
+    table[0xDB] = cb("SET 3,E", 8, "----", |cpu| set(cpu, 3, RegisterName::E));
+    table[0xDC] = cb("SET 3,H", 8, "----", |cpu| set(cpu, 3, RegisterName::H));
+    table[0xDD] = cb("SET 3,L", 8, "----", |cpu| set(cpu, 3, RegisterName::L));
+    table[0xDE] = cb("SET 3,(HL)", 16, "----", |cpu| set(cpu, 3, RegisterName::HL));
+    table[0xDF] = cb("SET 3,A", 8, "----", |cpu| set(cpu, 3, RegisterName::A));
 
-    map.insert(0xC0, |cpu| set(cpu, 0, RegisterName::B));
-    map.insert(0xC1, |cpu| set(cpu, 0, RegisterName::C));
-    map.insert(0xC2, |cpu| set(cpu, 0, RegisterName::D));
-    map.insert(0xC3, |cpu| set(cpu, 0, RegisterName::E));
-    map.insert(0xC4, |cpu| set(cpu, 0, RegisterName::H));
-    map.insert(0xC5, |cpu| set(cpu, 0, RegisterName::L));
-    map.insert(0xC6, |cpu| set(cpu, 0, RegisterName::HL));
-    map.insert(0xC7, |cpu| set(cpu, 0, RegisterName::A));
-    map.insert(0xC8, |cpu| set(cpu, 1, RegisterName::B));
-    map.insert(0xC9, |cpu| set(cpu, 1, RegisterName::C));
-    map.insert(0xCA, |cpu| set(cpu, 1, RegisterName::D));
-    map.insert(0xCB, |cpu| set(cpu, 1, RegisterName::E));
-    map.insert(0xCC, |cpu| set(cpu, 1, RegisterName::H));
-    map.insert(0xCD, |cpu| set(cpu, 1, RegisterName::L));
-    map.insert(0xCE, |cpu| set(cpu, 1, RegisterName::HL));
-    map.insert(0xCF, |cpu| set(cpu, 1, RegisterName::A));
-    map.insert(0xD0, |cpu| set(cpu, 2, RegisterName::B));
-    map.insert(0xD1, |cpu| set(cpu, 2, RegisterName::C));
-    map.insert(0xD2, |cpu| set(cpu, 2, RegisterName::D));
-    map.insert(0xD3, |cpu| set(cpu, 2, RegisterName::E));
-    map.insert(0xD4, |cpu| set(cpu, 2, RegisterName::H));
-    map.insert(0xD5, |cpu| set(cpu, 2, RegisterName::L));
-    map.insert(0xD6, |cpu| set(cpu, 2, RegisterName::HL));
-    map.insert(0xD7, |cpu| set(cpu, 2, RegisterName::A));
-    map.insert(0xD8, |cpu| set(cpu, 3, RegisterName::B));
-    map.insert(0xD9, |cpu| set(cpu, 3, RegisterName::C));
-    map.insert(0xDA, |cpu| set(cpu, 3, RegisterName::D));
-    map.insert(0xDB, |cpu| set(cpu, 3, RegisterName::E));
-    map.insert(0xDC, |cpu| set(cpu, 3, RegisterName::H));
-    map.insert(0xDD, |cpu| set(cpu, 3, RegisterName::L));
-    map.insert(0xDE, |cpu| set(cpu, 3, RegisterName::HL));
-    map.insert(0xDF, |cpu| set(cpu, 3, RegisterName::A));
-    map.insert(0xE0, |cpu| set(cpu, 4, RegisterName::B));
-    map.insert(0xE1, |cpu| set(cpu, 4, RegisterName::C));
-    map.insert(0xE2, |cpu| set(cpu, 4, RegisterName::D));
-    map.insert(0xE3, |cpu| set(cpu, 4, RegisterName::E));
-    map.insert(0xE4, |cpu| set(cpu, 4, RegisterName::H));
-    map.insert(0xE5, |cpu| set(cpu, 4, RegisterName::L));
-    map.insert(0xE6, |cpu| set(cpu, 4, RegisterName::HL));
-    map.insert(0xE7, |cpu| set(cpu, 4, RegisterName::A));
-    map.insert(0xE8, |cpu| set(cpu, 5, RegisterName::B));
-    map.insert(0xE9, |cpu| set(cpu, 5, RegisterName::C));
-    map.insert(0xEA, |cpu| set(cpu, 5, RegisterName::D));
-    map.insert(0xEB, |cpu| set(cpu, 5, RegisterName::E));
-    map.insert(0xEC, |cpu| set(cpu, 5, RegisterName::H));
-    map.insert(0xED, |cpu| set(cpu, 5, RegisterName::L));
-    map.insert(0xEE, |cpu| set(cpu, 5, RegisterName::HL));
-    map.insert(0xEF, |cpu| set(cpu, 5, RegisterName::A));
-    map.insert(0xF0, |cpu| set(cpu, 6, RegisterName::B));
-    map.insert(0xF1, |cpu| set(cpu, 6, RegisterName::C));
-    map.insert(0xF2, |cpu| set(cpu, 6, RegisterName::D));
-    map.insert(0xF3, |cpu| set(cpu, 6, RegisterName::E));
-    map.insert(0xF4, |cpu| set(cpu, 6, RegisterName::H));
-    map.insert(0xF5, |cpu| set(cpu, 6, RegisterName::L));
-    map.insert(0xF6, |cpu| set(cpu, 6, RegisterName::HL));
-    map.insert(0xF7, |cpu| set(cpu, 6, RegisterName::A));
-    map.insert(0xF8, |cpu| set(cpu, 7, RegisterName::B));
-    map.insert(0xF9, |cpu| set(cpu, 7, RegisterName::C));
-    map.insert(0xFA, |cpu| set(cpu, 7, RegisterName::D));
-    map.insert(0xFB, |cpu| set(cpu, 7, RegisterName::E));
-    map.insert(0xFC, |cpu| set(cpu, 7, RegisterName::H));
-    map.insert(0xFD, |cpu| set(cpu, 7, RegisterName::L));
-    map.insert(0xFE, |cpu| set(cpu, 7, RegisterName::HL));
-    map.insert(0xFF, |cpu| set(cpu, 7, RegisterName::A));
+    table[0xE0] = cb("SET 4,B", 8, "----", |cpu| set(cpu, 4, RegisterName::B));
+    table[0xE1] = cb("SET 4,C", 8, "----", |cpu| set(cpu, 4, RegisterName::C));
+    table[0xE2] = cb("SET 4,D", 8, "----", |cpu| set(cpu, 4, RegisterName::D));
+    table[0xE3] = cb("SET 4,E", 8, "----", |cpu| set(cpu, 4, RegisterName::E));
+    table[0xE4] = cb("SET 4,H", 8, "----", |cpu| set(cpu, 4, RegisterName::H));
+    table[0xE5] = cb("SET 4,L", 8, "----", |cpu| set(cpu, 4, RegisterName::L));
+    table[0xE6] = cb("SET 4,(HL)", 16, "----", |cpu| set(cpu, 4, RegisterName::HL));
+    table[0xE7] = cb("SET 4,A", 8, "----", |cpu| set(cpu, 4, RegisterName::A));
+    table[0xE8] = cb("SET 5,B", 8, "----", |cpu| set(cpu, 5, RegisterName::B));
+    table[0xE9] = cb("SET 5,C", 8, "----", |cpu| set(cpu, 5, RegisterName::C));
+    table[0xEA] = cb("SET 5,D", 8, "----", |cpu| set(cpu, 5, RegisterName::D));
+    table[0xEB] = cb("SET 5,E", 8, "----", |cpu| set(cpu, 5, RegisterName::E));
+    table[0xEC] = cb("SET 5,H", 8, "----", |cpu| set(cpu, 5, RegisterName::H));
+    table[0xED] = cb("SET 5,L", 8, "----", |cpu| set(cpu, 5, RegisterName::L));
+    table[0xEE] = cb("SET 5,(HL)", 16, "----", |cpu| set(cpu, 5, RegisterName::HL));
+    table[0xEF] = cb("SET 5,A", 8, "----", |cpu| set(cpu, 5, RegisterName::A));
 
-    map
+    table[0xF0] = cb("SET 6,B", 8, "----", |cpu| set(cpu, 6, RegisterName::B));
+    table[0xF1] = cb("SET 6,C", 8, "----", |cpu| set(cpu, 6, RegisterName::C));
+    table[0xF2] = cb("SET 6,D", 8, "----", |cpu| set(cpu, 6, RegisterName::D));
+    table[0xF3] = cb("SET 6,E", 8, "----", |cpu| set(cpu, 6, RegisterName::E));
+    table[0xF4] = cb("SET 6,H", 8, "----", |cpu| set(cpu, 6, RegisterName::H));
+    table[0xF5] = cb("SET 6,L", 8, "----", |cpu| set(cpu, 6, RegisterName::L));
+    table[0xF6] = cb("SET 6,(HL)", 16, "----", |cpu| set(cpu, 6, RegisterName::HL));
+    table[0xF7] = cb("SET 6,A", 8, "----", |cpu| set(cpu, 6, RegisterName::A));
+    table[0xF8] = cb("SET 7,B", 8, "----", |cpu| set(cpu, 7, RegisterName::B));
+    table[0xF9] = cb("SET 7,C", 8, "----", |cpu| set(cpu, 7, RegisterName::C));
+    table[0xFA] = cb("SET 7,D", 8, "----", |cpu| set(cpu, 7, RegisterName::D));
+    table[0xFB] = cb("SET 7,E", 8, "----", |cpu| set(cpu, 7, RegisterName::E));
+    table[0xFC] = cb("SET 7,H", 8, "----", |cpu| set(cpu, 7, RegisterName::H));
+    table[0xFD] = cb("SET 7,L", 8, "----", |cpu| set(cpu, 7, RegisterName::L));
+    table[0xFE] = cb("SET 7,(HL)", 16, "----", |cpu| set(cpu, 7, RegisterName::HL));
+    table[0xFF] = cb("SET 7,A", 8, "----", |cpu| set(cpu, 7, RegisterName::A));
+    table
 }
-
-
@@ -8,22 +8,23 @@ pub enum Direction {
     None,
 }
 
-pub fn ld_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn ld_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
 
     if destination.is_u16_registers() && source.is_u16_registers() {
+        // 16 bit copies take an extra M-cycle
+        cpu.tick();
         let value = cpu.registers.value_of_u16(&source);
         cpu.registers.write_in_u16(destination, value);
-        return cycles;
+        return;
     }
     let value = cpu.registers.value_of(&&source);
     cpu.registers.write_in(destination, value);
-    cycles
 }
 
-pub fn ld_mr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, direction: Direction, cycles: u8) -> u8 {
+pub fn ld_mr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, direction: Direction) {
     let address = cpu.registers.value_of_u16(&destination);
     let value = cpu.registers.value_of(&source);
-    cpu.bus.bus_write(address, value);
+    cpu.write(address, value);
 
     match direction {
         Direction::Increment => {
@@ -36,13 +37,12 @@ pub fn ld_mr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, dir
         }
         Direction::None => {}
     }
-    cycles
 }
 
-pub fn ld_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, direction: Direction, cycles: u8) -> u8 {
+pub fn ld_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, direction: Direction) {
     let address = cpu.registers.value_of_u16(&source);
 
-    let value_from_memory = cpu.bus.bus_read(address);
+    let value_from_memory = cpu.read(address);
 
 
     cpu.registers.write_in(destination, value_from_memory);
@@ -58,36 +58,34 @@ pub fn ld_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, dir
         }
         Direction::None => {}
     }
-    cycles
 }
 
-pub fn ldi_a_mhl(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn ldi_a_mhl(cpu: &mut CPU) {
     let hl = cpu.registers.get_hl();
 
-    let a = cpu.bus.bus_read(hl);
+    let a = cpu.read(hl);
 
     cpu.registers.a = a;
     cpu.registers.set_hl(hl.wrapping_add(1));
-    cycles
 }
 
 
-pub fn ld_ru16(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
+pub fn ld_ru16(cpu: &mut CPU, destination: RegisterName) {
     let value = cpu.fetch_word();
     cpu.registers.write_in_u16(destination, value);
-    cycles
 }
 
-pub fn ld_ru8(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
+pub fn ld_ru8(cpu: &mut CPU, destination: RegisterName) {
     let value = cpu.fetch_byte();
     cpu.registers.write_in(destination, value);
-    cycles
 }
 
 
 /*ADD*/
-pub fn add_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn add_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     if source.is_u16_registers() {
+        // The ALU is 8 bits wide, the high byte is added during an extra M-cycle
+        cpu.tick();
         let value = cpu.registers.value_of_u16(&source);
         let (new_value, did_overflow) =
             cpu.registers.value_of_u16(&destination)
@@ -111,12 +109,11 @@ pub fn add_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cy
         cpu.registers.update_flag(Flag::C, did_overflow);
         cpu.registers.write_in(destination, new_value);
     }
-    cycles
 }
 
-pub fn add_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn add_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let address = cpu.registers.value_of_u16(&source);
-    let value_from_memory = cpu.bus.bus_read(address);
+    let value_from_memory = cpu.read(address);
 
     let (new_value, did_overflow) =
         cpu.registers.value_of(&&destination).overflowing_add(value_from_memory);
@@ -127,11 +124,9 @@ pub fn add_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cy
     cpu.registers.update_flag(Flag::H, half_carry);
     cpu.registers.update_flag(Flag::C, did_overflow);
     cpu.registers.write_in(destination, new_value);
-
-    cycles
 }
 
-pub fn add_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
+pub fn add_rv(cpu: &mut CPU, destination: RegisterName) {
     let value_from_memory = cpu.fetch_byte();
     let (new_value, did_overflow) =
         cpu.registers.value_of(&&destination).overflowing_add(value_from_memory);
@@ -142,10 +137,9 @@ pub fn add_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::H, half_carry);
     cpu.registers.update_flag(Flag::C, did_overflow);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 /*ADC*/
-pub fn adc_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn adc_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let value = cpu.registers.value_of(&&source);
     let carry = if cpu.registers.check_flag(Flag::C) { 1 } else { 0 };
     let (intermediate_value, overflow1) = cpu.registers.value_of(&&destination).overflowing_add(value);
@@ -158,12 +152,11 @@ pub fn adc_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cy
     cpu.registers.update_flag(Flag::H, half_carry);
     cpu.registers.update_flag(Flag::C, overflow1 || overflow2);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
-pub fn adc_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn adc_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let address = cpu.registers.value_of_u16(&source);
-    let value = cpu.bus.bus_read(address);
+    let value = cpu.read(address);
     let carry = if cpu.registers.check_flag(Flag::C) { 1 } else { 0 };
     let (intermediate_value, overflow1) = cpu.registers.value_of(&&destination).overflowing_add(value);
     let (new_value, overflow2) = intermediate_value.overflowing_add(carry);
@@ -175,10 +168,9 @@ pub fn adc_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cy
     cpu.registers.update_flag(Flag::H, half_carry);
     cpu.registers.update_flag(Flag::C, overflow1 || overflow2);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
-pub fn adc_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
+pub fn adc_rv(cpu: &mut CPU, destination: RegisterName) {
     let value_from_memory = cpu.fetch_byte();
     let carry = if cpu.registers.check_flag(Flag::C) { 1 } else { 0 };
     let (intermediate_result, overflow1) = cpu.registers.value_of(&destination).overflowing_add(value_from_memory);
@@ -191,12 +183,11 @@ pub fn adc_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::H, half_carry);
     cpu.registers.update_flag(Flag::C, overflow1 || overflow2);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
 
 /*SUB*/
-pub fn sub_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn sub_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     if source.is_u16_registers() {
         let value = cpu.registers.value_of_u16(&source);
         let (new_value, did_underflow) =
@@ -222,12 +213,11 @@ pub fn sub_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cy
 
         cpu.registers.write_in(destination, new_value);
     }
-    cycles
 }
 
-pub fn sub_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn sub_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let address = cpu.registers.value_of_u16(&source);
-    let value_from_memory = cpu.bus.bus_read(address);
+    let value_from_memory = cpu.read(address);
 
     let (new_value, did_overflow) =
         cpu.registers.value_of(&destination).overflowing_sub(value_from_memory);
@@ -238,10 +228,9 @@ pub fn sub_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cy
     cpu.registers.update_flag(Flag::H, half_carry);
     cpu.registers.update_flag(Flag::C, did_overflow);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
-pub fn sub_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
+pub fn sub_rv(cpu: &mut CPU, destination: RegisterName) {
     let value_from_memory = cpu.fetch_byte();
 
     let (new_value, did_underflow) =
@@ -254,12 +243,11 @@ pub fn sub_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::H, half_borrow);
     cpu.registers.update_flag(Flag::C, did_underflow);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
 
 /*SBC*/
-pub fn sbc_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn sbc_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let value = cpu.registers.value_of(&source);
     let borrow = if cpu.registers.check_flag(Flag::C) { 1 } else { 0 };
     let (intermediate_value, underflow1) = cpu.registers.value_of(&destination).overflowing_sub(value);
@@ -272,13 +260,11 @@ pub fn sbc_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cy
     cpu.registers.update_flag(Flag::H, half_carry);
     cpu.registers.update_flag(Flag::C, underflow1 || underflow2);
     cpu.registers.write_in(destination, new_value);
-
-    cycles
 }
 
-pub fn sbc_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8{
+pub fn sbc_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let address = cpu.registers.value_of_u16(&source);
-    let value_from_memory = cpu.bus.bus_read(address);
+    let value_from_memory = cpu.read(address);
     let borrow = if cpu.registers.check_flag(Flag::C) { 1 } else { 0 };
     let (intermediate_value, underflow1) = cpu.registers.value_of(&destination).overflowing_sub(value_from_memory);
     let (new_value, underflow2) = intermediate_value.overflowing_sub(borrow);
@@ -290,10 +276,9 @@ pub fn sbc_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cy
     cpu.registers.update_flag(Flag::H, half_carry);
     cpu.registers.update_flag(Flag::C, underflow1 || underflow2);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
-pub fn sbc_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
+pub fn sbc_rv(cpu: &mut CPU, destination: RegisterName) {
     let value_from_memory = cpu.fetch_byte();
     let carry = if cpu.registers.check_flag(Flag::C) { 1 } else { 0 };
     let (intermediate_result, overflow1) = cpu.registers.value_of(&destination).overflowing_sub(value_from_memory);
@@ -306,12 +291,11 @@ pub fn sbc_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::H, half_carry);
     cpu.registers.update_flag(Flag::C, overflow1 || overflow2);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
 
 /*AND*/
-pub fn and_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn and_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let value = cpu.registers.value_of(&source);
     let new_value = cpu.registers.value_of(&destination) & value;
 
@@ -320,12 +304,11 @@ pub fn and_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cy
     cpu.registers.update_flag(Flag::H, true);
     cpu.registers.update_flag(Flag::C, false);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
-pub fn and_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn and_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let address = cpu.registers.value_of_u16(&source);
-    let value_from_memory = cpu.bus.bus_read(address);
+    let value_from_memory = cpu.read(address);
     let new_value = cpu.registers.value_of(&destination) & value_from_memory;
 
     cpu.registers.update_flag(Flag::Z, new_value == 0);
@@ -333,10 +316,9 @@ pub fn and_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cy
     cpu.registers.update_flag(Flag::H, true);
     cpu.registers.update_flag(Flag::C, false);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
-pub fn and_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
+pub fn and_rv(cpu: &mut CPU, destination: RegisterName) {
     let value_from_memory = cpu.fetch_byte();
     let new_value = cpu.registers.value_of(&destination) & value_from_memory;
 
@@ -345,12 +327,11 @@ pub fn and_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::H, true);
     cpu.registers.update_flag(Flag::C, false);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
 
 /*XOR*/
-pub fn xor_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn xor_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let value = cpu.registers.value_of(&source);
     let new_value = cpu.registers.value_of(&destination) ^ value;
 
@@ -359,12 +340,11 @@ pub fn xor_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cy
     cpu.registers.update_flag(Flag::H, false);
     cpu.registers.update_flag(Flag::C, false);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
-pub fn xor_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn xor_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let address = cpu.registers.value_of_u16(&source);
-    let value_from_memory = cpu.bus.bus_read(address);
+    let value_from_memory = cpu.read(address);
     let new_value = cpu.registers.value_of(&destination) ^ value_from_memory;
 
     cpu.registers.update_flag(Flag::Z, new_value == 0);
@@ -372,10 +352,9 @@ pub fn xor_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cy
     cpu.registers.update_flag(Flag::H, false);
     cpu.registers.update_flag(Flag::C, false);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
-pub fn xor_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
+pub fn xor_rv(cpu: &mut CPU, destination: RegisterName) {
     let value_from_memory = cpu.fetch_byte();
     let new_value = cpu.registers.value_of(&destination) ^ value_from_memory;
 
@@ -384,11 +363,10 @@ pub fn xor_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::H, false);
     cpu.registers.update_flag(Flag::C, false);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
 /*OR*/
-pub fn or_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn or_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let value = cpu.registers.value_of(&source);
     let new_value = cpu.registers.value_of(&destination) | value;
 
@@ -397,12 +375,11 @@ pub fn or_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cyc
     cpu.registers.update_flag(Flag::H, false);
     cpu.registers.update_flag(Flag::C, false);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
-pub fn or_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn or_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let address = cpu.registers.value_of_u16(&source);
-    let value_from_memory = cpu.bus.bus_read(address);
+    let value_from_memory = cpu.read(address);
     let new_value = cpu.registers.value_of(&destination) | value_from_memory;
 
     cpu.registers.update_flag(Flag::Z, new_value == 0);
@@ -410,10 +387,9 @@ pub fn or_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cyc
     cpu.registers.update_flag(Flag::H, false);
     cpu.registers.update_flag(Flag::C, false);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
-pub fn or_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
+pub fn or_rv(cpu: &mut CPU, destination: RegisterName) {
     let value_from_memory = cpu.fetch_byte();
     let new_value = cpu.registers.value_of(&destination) | value_from_memory;
 
@@ -422,11 +398,10 @@ pub fn or_rv(cpu: &mut CPU, destination: RegisterName, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::H, false);
     cpu.registers.update_flag(Flag::C, false);
     cpu.registers.write_in(destination, new_value);
-    cycles
 }
 
 /*CP*/
-pub fn cp_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn cp_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let value = cpu.registers.value_of(&source);
     let (result, did_underflow) = cpu.registers.value_of(&destination).overflowing_sub(value);
     let half_carry = (cpu.registers.value_of(&destination) & 0xF) < (value & 0xF);
@@ -435,12 +410,11 @@ pub fn cp_rr(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cyc
     cpu.registers.update_flag(Flag::N, true);
     cpu.registers.update_flag(Flag::H, half_carry);
     cpu.registers.update_flag(Flag::C, did_underflow);
-    cycles
 }
 
-pub fn cp_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cycles: u8) -> u8 {
+pub fn cp_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName) {
     let address = cpu.registers.value_of_u16(&source);
-    let value_from_memory = cpu.bus.bus_read(address);
+    let value_from_memory = cpu.read(address);
     let (result, did_underflow) = cpu.registers.value_of(&destination).overflowing_sub(value_from_memory);
     let half_carry = (cpu.registers.value_of(&destination) & 0xF) < (value_from_memory & 0xF);
 
@@ -448,10 +422,9 @@ pub fn cp_rm(cpu: &mut CPU, destination: RegisterName, source: RegisterName, cyc
     cpu.registers.update_flag(Flag::N, true);
     cpu.registers.update_flag(Flag::H, half_carry);
     cpu.registers.update_flag(Flag::C, did_underflow);
-    cycles
 }
 
-pub fn cp_rv(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
+pub fn cp_rv(cpu: &mut CPU, register: RegisterName) {
     let value_from_memory = cpu.fetch_byte();
     let register_value = cpu.registers.value_of(&register);
 
@@ -462,50 +435,53 @@ pub fn cp_rv(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::N, true);
     cpu.registers.update_flag(Flag::H, half_carry);
     cpu.registers.update_flag(Flag::C, did_overflow);
-    cycles
 }
 
-pub fn ld_m_u8(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
+pub fn ld_m_u8(cpu: &mut CPU, register: RegisterName) {
     let value = cpu.fetch_byte();
 
     let address = cpu.registers.value_of_u16(&register);
 
-    cpu.bus.bus_write(address, value);
-
-    cycles
+    cpu.write(address, value);
 }
 
-pub fn ret_if(cpu: &mut CPU, flag: Flag, condition: bool, cycles: u8) -> u8 {
+pub fn ret_if(cpu: &mut CPU, flag: Flag, condition: bool) -> bool {
+    // Checking the condition takes an M-cycle of its own
+    cpu.tick();
     if cpu.registers.check_flag(flag) == condition {
         cpu.registers.pc = cpu.pop_stack_word();
+        cpu.tick();
+        return true;
     }
-    cycles
+    false
 }
 
-pub fn ret(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn ret(cpu: &mut CPU) {
     cpu.registers.pc = cpu.pop_stack_word();
-    cycles
+    // Loading PC takes an M-cycle after the pops
+    cpu.tick();
 }
 
-pub fn jr_if(cpu: &mut CPU, flag: Flag, expected_state: bool, cycles: u8) -> u8 {
-    let relative_jump: i8 = cpu.bus.bus_read(cpu.registers.pc) as i8;
-    cpu.registers.pc = cpu.registers.pc.wrapping_add(1);
+pub fn jr_if(cpu: &mut CPU, flag: Flag, expected_state: bool) -> bool {
+    let relative_jump: i8 = cpu.fetch_byte() as i8;
 
     if cpu.registers.check_flag(flag) == expected_state {
+        cpu.tick();
         cpu.registers.pc = cpu.registers.pc.wrapping_add(relative_jump as u16);
+        return true;
     }
-    cycles
+    false
 }
 
-pub fn jr(cpu: &mut CPU, cycles: u8) -> u8 {
-    let relative_jump: i8 = cpu.bus.bus_read(cpu.registers.pc) as i8;
-    cpu.registers.pc = cpu.registers.pc.wrapping_add(1);
+pub fn jr(cpu: &mut CPU) {
+    let relative_jump: i8 = cpu.fetch_byte() as i8;
 
+    // Adding the offset to PC takes an M-cycle
+    cpu.tick();
     cpu.registers.pc = cpu.registers.pc.wrapping_add(relative_jump as u16);
-    cycles
 }
 
-pub fn rlca(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn rlca(cpu: &mut CPU) {
     let a = cpu.registers.a;
 
     let msb_set = a & 0x80 != 0;
@@ -517,10 +493,9 @@ pub fn rlca(cpu: &mut CPU, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::Z, false);
     cpu.registers.update_flag(Flag::N, false);
     cpu.registers.update_flag(Flag::H, false);
-    cycles
 }
 
-pub fn rla(cpu: &mut CPU, cycles: u8) -> u8{
+pub fn rla(cpu: &mut CPU) {
     let a = cpu.registers.a;
     let carry_set = cpu.registers.check_flag(Flag::C);
 
@@ -531,10 +506,9 @@ pub fn rla(cpu: &mut CPU, cycles: u8) -> u8{
     cpu.registers.update_flag(Flag::Z, false);
     cpu.registers.update_flag(Flag::N, false);
     cpu.registers.update_flag(Flag::H, false);
-    cycles
 }
 
-pub fn rra(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn rra(cpu: &mut CPU) {
     let a = cpu.registers.a;
     let carry_set = cpu.registers.check_flag(Flag::C);
 
@@ -545,10 +519,9 @@ pub fn rra(cpu: &mut CPU, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::Z, false);
     cpu.registers.update_flag(Flag::N, false);
     cpu.registers.update_flag(Flag::H, false);
-    cycles
 }
 
-pub fn rrca(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn rrca(cpu: &mut CPU) {
     let a = cpu.registers.a;
 
     let lsb_set = a & 0x01 != 0;
@@ -560,10 +533,9 @@ pub fn rrca(cpu: &mut CPU, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::Z, false);
     cpu.registers.update_flag(Flag::N, false);
     cpu.registers.update_flag(Flag::H, false);
-    cycles
 }
 
-pub fn daa(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn daa(cpu: &mut CPU) {
     let n_flag = cpu.registers.check_flag(Flag::N);
     let mut c_flag = cpu.registers.check_flag(Flag::C);
     let h_flag = cpu.registers.check_flag(Flag::H);
@@ -593,38 +565,35 @@ pub fn daa(cpu: &mut CPU, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::H, false);
 
     cpu.registers.a = a;
-
-    cycles
 }
 
 
 
-pub fn cpl(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn cpl(cpu: &mut CPU) {
     cpu.registers.a = !cpu.registers.a;
     cpu.registers.update_flag(Flag::N, true);
     cpu.registers.update_flag(Flag::H, true);
-    cycles
 }
 
-pub fn scf(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn scf(cpu: &mut CPU) {
     cpu.registers.update_flag(Flag::C, true);
     cpu.registers.update_flag(Flag::N, false);
     cpu.registers.update_flag(Flag::H, false);
-    cycles
 }
 
-pub fn ccf(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn ccf(cpu: &mut CPU) {
     let carry = cpu.registers.check_flag(Flag::C);
     cpu.registers.update_flag(Flag::C, !carry);
     cpu.registers.update_flag(Flag::N, false);
     cpu.registers.update_flag(Flag::H, false);
-    cycles
 }
 
 /*INC*/
-pub fn inc_r(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
+pub fn inc_r(cpu: &mut CPU, register: RegisterName) {
 
     if register.is_u16_registers() {
+        // The 16 bit increment takes an extra M-cycle
+        cpu.tick();
         let value = cpu.registers.value_of_u16(&register);
         let new_value = value.wrapping_add(1);
         cpu.registers.write_in_u16(register, new_value);
@@ -638,28 +607,27 @@ pub fn inc_r(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
         cpu.registers.update_flag(Flag::N, false);
         cpu.registers.write_in(register, new_value);
     }
-    cycles
 }
 
-pub fn inc_m(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
+pub fn inc_m(cpu: &mut CPU, register: RegisterName) {
     if !register.is_u16_registers() {
         panic!("Trying to use a non-16-bit register as a memory address in inc_m!");
     }
 
     let address = cpu.registers.value_of_u16(&register);
-    let value_from_memory = cpu.bus.bus_read(address);
+    let value_from_memory = cpu.read(address);
     let incremented_value = value_from_memory.wrapping_add(1);
 
     cpu.registers.update_flag(Flag::Z, incremented_value == 0);
     cpu.registers.update_flag(Flag::H, (value_from_memory & 0xF) + 1 > 0xF);
     cpu.registers.update_flag(Flag::N, false);
-    cpu.bus.bus_write(address, incremented_value);
-    cycles
+    cpu.write(address, incremented_value);
 }
 
-pub fn dec_r(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
+pub fn dec_r(cpu: &mut CPU, register: RegisterName) {
 
     if register.is_u16_registers() {
+        cpu.tick();
         let value = cpu.registers.value_of_u16(&register);
         let new_value = value.wrapping_sub(1);
         cpu.registers.write_in_u16(register, new_value);
@@ -674,16 +642,15 @@ pub fn dec_r(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
         cpu.registers.update_flag(Flag::N, true);
         cpu.registers.update_flag(Flag::H, (value & 0x0F) < (new_value & 0x0F));
     }
-    cycles
 }
 
-pub fn dec_m(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
+pub fn dec_m(cpu: &mut CPU, register: RegisterName) {
     if !register.is_u16_registers() {
         panic!("Trying to use a non-16-bit register as a memory address in dec_m!");
     }
 
     let address = cpu.registers.value_of_u16(&register);
-    let value_from_memory = cpu.bus.bus_read(address);
+    let value_from_memory = cpu.read(address);
     let decremented_value = value_from_memory.wrapping_sub(1);
 
     cpu.registers.update_flag(Flag::Z, decremented_value == 0);
@@ -691,20 +658,19 @@ pub fn dec_m(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::H, (value_from_memory & 0x0F) == 0);
 
 
-    cpu.bus.bus_write(address, decremented_value);
-    cycles
+    cpu.write(address, decremented_value);
 }
 
 
-pub fn ld_u16_sp(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn ld_u16_sp(cpu: &mut CPU) {
     let address = cpu.fetch_word();
 
-    cpu.bus.bus_write_word(address, cpu.registers.sp);
-
-    cycles
+    let sp = cpu.registers.sp;
+    cpu.write(address, sp as u8);
+    cpu.write(address.wrapping_add(1), (sp >> 8) as u8);
 }
 
-pub fn pop_r(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
+pub fn pop_r(cpu: &mut CPU, register: RegisterName) {
     let popped_value = cpu.pop_stack_word();
     cpu.registers.write_in_u16(register, popped_value);
 
@@ -722,91 +688,86 @@ pub fn pop_r(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
         cpu.registers.update_flag(Flag::H, (masked_f & 0x20) != 0);
         cpu.registers.update_flag(Flag::C, (masked_f & 0x10) != 0);
     }
-
-    cycles
 }
 
 
 
-pub fn push_r(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
+pub fn push_r(cpu: &mut CPU, register: RegisterName) {
     let value = cpu.registers.value_of_u16(&register);
+    cpu.tick();
     cpu.push_stack_word(value);
-    cycles
 }
 
-pub fn jp_if(cpu: &mut CPU, flag: Flag, condition: bool, cycles: u8) -> u8{
+pub fn jp_if(cpu: &mut CPU, flag: Flag, condition: bool) -> bool {
+    // The address is read whether the jump is taken or not
+    let address = cpu.fetch_word();
     if cpu.registers.check_flag(flag) == condition {
-        let address = cpu.bus.bus_read_word(cpu.registers.pc);
+        cpu.tick();
         cpu.registers.pc = address;
-    }else{
-        cpu.registers.pc += 2;
+        return true;
     }
-    cycles
+    false
 }
 
-pub fn jp(cpu: &mut CPU, cycles: u8) -> u8 {
-    let address = cpu.bus.bus_read_word(cpu.registers.pc);
+pub fn jp(cpu: &mut CPU) {
+    let address = cpu.fetch_word();
+    // Loading PC takes an M-cycle after the address is read
+    cpu.tick();
     cpu.registers.pc = address;
-    cycles
 }
 
-pub fn jp_r(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
+pub fn jp_r(cpu: &mut CPU, register: RegisterName) {
     let address = cpu.registers.value_of_u16(&register);
     cpu.registers.pc = address;
-    cycles
 }
 
-pub fn call_if(cpu: &mut CPU, flag: Flag, condition: bool, cycles: u8) -> u8 {
+pub fn call_if(cpu: &mut CPU, flag: Flag, condition: bool) -> bool {
+    let address = cpu.fetch_word();
     if cpu.registers.check_flag(flag) == condition {
-        let return_address = cpu.registers.pc + 2;
-        let address = cpu.bus.bus_read_word(cpu.registers.pc);
-        cpu.push_stack_word(return_address);
+        cpu.tick();
+        cpu.push_stack_word(cpu.registers.pc);
         cpu.registers.pc = address;
-    }else {
-        cpu.registers.pc += 2;
+        return true;
     }
-
-    cycles
+    false
 }
 
-pub fn call(cpu: &mut CPU, cycles: u8) -> u8 {
-    let return_address = cpu.registers.pc + 2;
-    let address = cpu.bus.bus_read_word(cpu.registers.pc);
-    cpu.push_stack_word(return_address);
+pub fn call(cpu: &mut CPU) {
+    let address = cpu.fetch_word();
+    cpu.tick();
+    cpu.push_stack_word(cpu.registers.pc);
     cpu.registers.pc = address;
-    cycles
 }
 
-pub fn rst(cpu: &mut CPU, address: u16, cycles: u8) -> u8 {
+pub fn rst(cpu: &mut CPU, address: u16) {
+    cpu.tick();
     cpu.push_stack_word(cpu.registers.pc);
 
     cpu.registers.pc = address;
-    cycles
 }
 
-pub fn reti(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn reti(cpu: &mut CPU) {
     let return_address = cpu.pop_stack_word();
+    cpu.tick();
 
     cpu.registers.pc = return_address;
 
-    ei(cpu, 4);
-    cycles
+    // Unlike EI, RETI enables interrupts right away
+    cpu.interrupt_enabled = true;
 }
 
-pub fn ld_ff00_u8_a(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn ld_ff00_u8_a(cpu: &mut CPU) {
     let offset = cpu.fetch_byte();
 
     let address = 0xFF00u16.wrapping_add(offset as u16);
 
     let value = cpu.registers.value_of(&RegisterName::A);
 
-    cpu.bus.bus_write(address, value);
-
-    cycles
+    cpu.write(address, value);
 }
 
 
-pub fn ld_a_ff00_plus_u8(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn ld_a_ff00_plus_u8(cpu: &mut CPU) {
     // Fetch the immediate byte from the next memory location.
     let offset = cpu.fetch_byte();
 
@@ -815,53 +776,47 @@ pub fn ld_a_ff00_plus_u8(cpu: &mut CPU, cycles: u8) -> u8 {
 
 
     // Read the byte from the calculated memory address.
-    let value = cpu.bus.bus_read(address);
+    let value = cpu.read(address);
 
 
     // Store the read byte value into the A register.
     cpu.registers.write_in(RegisterName::A, value);
-    cycles
 }
 
-pub fn ld_ff00_c_a(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn ld_ff00_c_a(cpu: &mut CPU) {
     let c_value = cpu.registers.value_of(&RegisterName::C);
 
     let address = 0xFF00 + c_value as u16;
 
     let value = cpu.registers.value_of(&RegisterName::A);
 
-    cpu.bus.bus_write(address, value);
-
-    cycles
+    cpu.write(address, value);
 }
 
-pub fn ld_a_ff00_c(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn ld_a_ff00_c(cpu: &mut CPU) {
     let c_value = cpu.registers.value_of(&RegisterName::C);
 
     let address = 0xFF00 + c_value as u16;
 
-    let value = cpu.bus.bus_read(address);
+    let value = cpu.read(address);
 
     cpu.registers.write_in(RegisterName::A, value);
-
-    cycles
 }
 
-pub fn ld_u16_a(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn ld_u16_a(cpu: &mut CPU) {
     let a_value = cpu.registers.value_of(&RegisterName::A);
 
-    let address = cpu.bus.bus_read_word(cpu.registers.pc);
-
-    cpu.bus.bus_write(address, a_value);
-
-    cpu.registers.pc += 2;
+    let address = cpu.fetch_word();
 
-    cycles
+    cpu.write(address, a_value);
 }
 
-pub fn add_sp_i8(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn add_sp_i8(cpu: &mut CPU) {
     let i8_value = cpu.fetch_byte() as i8;
     let i16_value = i8_value as i16 as u16;
+    // One M-cycle for each byte of SP
+    cpu.tick();
+    cpu.tick();
 
     let result = cpu.registers.sp as u32 + i16_value as u32;
 
@@ -875,40 +830,33 @@ pub fn add_sp_i8(cpu: &mut CPU, cycles: u8) -> u8 {
     cpu.registers.update_flag(Flag::N, false);
     cpu.registers.update_flag(Flag::H, half_carry);
     cpu.registers.update_flag(Flag::C, full_carry);
-
-    cycles
 }
 
 
-pub fn ld_mvr(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
-    let address = cpu.bus.bus_read_word(cpu.registers.pc);
+pub fn ld_mvr(cpu: &mut CPU, register: RegisterName) {
+    let address = cpu.fetch_word();
 
     let value_a = cpu.registers.value_of(&register);
 
-    cpu.bus.bus_write(address, value_a);
-
-    cpu.registers.pc += 2;
-
-    cycles
+    cpu.write(address, value_a);
 }
 
-pub fn di(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn di(cpu: &mut CPU) {
     cpu.interrupt_enabled = false;
-
-    cycles
+    cpu.enable_interrupts_next = false;
 }
 
-pub fn ei(cpu: &mut CPU, cycles: u8) -> u8 {
-    cpu.interrupt_enabled = true;
-
-    cycles
+pub fn ei(cpu: &mut CPU) {
+    // IME only gets set after the next instruction
+    cpu.enable_interrupts_next = true;
 }
 
-pub fn ld_hl_spi8(cpu: &mut CPU, cycles: u8) -> u8 {
+pub fn ld_hl_spi8(cpu: &mut CPU) {
     cpu.registers.clear_all_flags();
 
     let sp = cpu.registers.sp as u16;
     let value = cpu.fetch_byte() as i8;
+    cpu.tick();
     let extended_value = value as u16; // If the Rust environment correctly sign-extends, otherwise use manual sign extension.
     let result = sp.wrapping_add(extended_value);
 
@@ -925,23 +873,44 @@ pub fn ld_hl_spi8(cpu: &mut CPU, cycles: u8) -> u8 {
     }
 
     cpu.registers.set_hl(result);
-    cycles
 }
 
 
 
 
-pub fn ld_rmv(cpu: &mut CPU, register: RegisterName, cycles: u8) -> u8 {
-    let address = cpu.bus.bus_read_word(cpu.registers.pc);
+pub fn ld_rmv(cpu: &mut CPU, register: RegisterName) {
+    let address = cpu.fetch_word();
 
-    let value_from_address = cpu.bus.bus_read(address);
+    let value_from_address = cpu.read(address);
     cpu.registers.a = value_from_address;
+}
+
+pub fn stop(cpu: &mut CPU) {
+    let button_held = cpu.bus.io.joypad.any_line_low();
+    let interrupt_pending = cpu.bus.has_interrupt();
 
-    cpu.registers.pc += 2;
-    cycles
+    // STOP is followed by a byte that gets skipped, unless an interrupt is pending
+    if !interrupt_pending {
+        cpu.registers.pc = cpu.registers.pc.wrapping_add(1);
+    }
+    if button_held {
+        // Nothing could wake the CPU up, so it doesn't stop. Without a pending
+        // interrupt it halts instead.
+        if !interrupt_pending {
+            cpu.is_halted = true;
+        }
+    } else {
+        cpu.bus.bus_write(0xFF04, 0); // Resets DIV
+        cpu.is_stopped = true;
+    }
 }
 
-pub fn halt(cpu: &mut CPU, cycles: u8) -> u8 {
-    cpu.is_halted = true;
-    cycles
+pub fn halt(cpu: &mut CPU) {
+    if !cpu.interrupt_enabled && cpu.bus.has_interrupt() {
+        // HALT bug: with an interrupt already pending and IME off the CPU doesn't halt,
+        // and PC isn't incremented when the next byte is fetched
+        cpu.halt_bug = true;
+    } else {
+        cpu.is_halted = true;
+    }
 }
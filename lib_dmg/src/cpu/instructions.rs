@@ -1,334 +1,352 @@
-use std::collections::HashMap;
-use std::iter::Cycle;
-use crate::bus::Bus;
 use crate::cpu::CPU;
 use crate::cpu::function::*;
-
-
+use crate::cpu::cb_instructions::CB_INSTRUCTIONS;
 use crate::cpu::registers::{Flag, RegisterName};
 
-pub type OpCodeHandler = fn(&mut CPU) -> u8;
-
-pub fn init_opcode_handlers() -> HashMap<u8, OpCodeHandler> {
-    let mut map: HashMap<u8, OpCodeHandler> = HashMap::new();
-
-    map.insert(0x00, |_| 4);
-    map.insert(0x0E, |cpu| ld_ru8(cpu, RegisterName::C, 8));
-    map.insert(0x11, |cpu| ld_ru16(cpu, RegisterName::DE, 12));
-    map.insert(0x21, |cpu| ld_ru16(cpu, RegisterName::HL, 12));
-    map.insert(0x47, |cpu| ld_rr(cpu, RegisterName::B, RegisterName::A, 4));
-    map.insert(0xC3, |cpu| jp(cpu, 16));
-
-    map.insert(0x01, |cpu| ld_ru16(cpu, RegisterName::BC, 12));
-    map.insert(0x02, |cpu| ld_mr(cpu, RegisterName::BC, RegisterName::A, Direction::None, 8));
-    map.insert(0x03, |cpu| inc_r(cpu, RegisterName::BC, 8));
-    map.insert(0x04, |cpu| inc_r(cpu, RegisterName::B, 4));
-    map.insert(0x05, |cpu| dec_r(cpu, RegisterName::B, 4));
-    map.insert(0x06, |cpu| ld_ru8(cpu, RegisterName::B, 8));
-    map.insert(0x07, |cpu| rlca(cpu, 4));
-    map.insert(0x08, |cpu| ld_u16_sp(cpu, 20));
-    map.insert(0x09, |cpu| add_rr(cpu, RegisterName::HL, RegisterName::BC, 8));
-    map.insert(0x0A, |cpu| ld_rm(cpu, RegisterName::A, RegisterName::BC, Direction::None, 8));
-    /*C*/
-    map.insert(0x0B, |cpu| dec_r(cpu, RegisterName::BC, 8));
-    map.insert(0x0C, |cpu| inc_r(cpu, RegisterName::C, 4));
-    map.insert(0x0D, |cpu| dec_r(cpu, RegisterName::C, 4));
-    map.insert(0x0F, |cpu| rrca(cpu, 4));
-
-    //Stop
-    map.insert(0x10, |cpu| panic!("STOP"));
-    /*DE*/
-    /*D*/
-    map.insert(0x12, |cpu| ld_mr(cpu, RegisterName::DE, RegisterName::A, Direction::None, 8));
-    map.insert(0x13, |cpu| inc_r(cpu, RegisterName::DE, 8));
-    map.insert(0x14, |cpu| inc_r(cpu, RegisterName::D, 4));
-    map.insert(0x15, |cpu| dec_r(cpu, RegisterName::D, 4));
-    map.insert(0x16, |cpu| ld_ru8(cpu, RegisterName::D, 8));
-    map.insert(0x17, |cpu| rla(cpu, 4));
-    map.insert(0x18, |cpu| jr(cpu, 12));
-    map.insert(0x19, |cpu| add_rr(cpu, RegisterName::HL, RegisterName::DE, 8));
-    map.insert(0x1A, |cpu| ld_rm(cpu, RegisterName::A, RegisterName::DE, Direction::None, 8));
-    /*E*/
-    map.insert(0x1B, |cpu| dec_r(cpu, RegisterName::DE, 8));
-    map.insert(0x1C, |cpu| inc_r(cpu, RegisterName::E, 4));
-    map.insert(0x1D, |cpu| dec_r(cpu, RegisterName::E, 4));
-    map.insert(0x1E, |cpu| ld_ru8(cpu, RegisterName::E, 8));
-    map.insert(0x1F, |cpu| rra(cpu, 4));
-
-    map.insert(0x20, |cpu| jr_if(cpu, Flag::Z, false, 8));
-    /*HL*/
-    /*H*/
-    map.insert(0x21, |cpu| ld_ru16(cpu, RegisterName::HL, 12));
-    map.insert(0x22, |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::A, Direction::Increment, 8));
-    map.insert(0x23, |cpu| inc_r(cpu, RegisterName::HL, 8));
-    map.insert(0x24, |cpu| inc_r(cpu, RegisterName::H, 4));
-    map.insert(0x25, |cpu| dec_r(cpu, RegisterName::H, 4));
-    map.insert(0x26, |cpu| ld_ru8(cpu, RegisterName::H, 8));
-    map.insert(0x27, |cpu| daa(cpu, 4));
-    map.insert(0x28, |cpu| jr_if(cpu, Flag::Z, true, 8));
-    map.insert(0x29, |cpu| add_rr(cpu, RegisterName::HL, RegisterName::HL, 8));
-    map.insert(0x2A, |cpu| ld_rm(cpu, RegisterName::A, RegisterName::HL, Direction::Increment, 8));
-    /*L*/
-    map.insert(0x2B, |cpu| dec_r(cpu, RegisterName::HL, 8));
-    map.insert(0x2C, |cpu| inc_r(cpu, RegisterName::L, 4));
-    map.insert(0x2D, |cpu| dec_r(cpu, RegisterName::L, 4));
-    map.insert(0x2E, |cpu| ld_ru8(cpu, RegisterName::L, 8));
-    map.insert(0x2F, |cpu| cpl(cpu, 4));
-
-
-    map.insert(0x30, |cpu| jr_if(cpu, Flag::C, false, 8));
-    /*HL*/
-    /*H*/
-    map.insert(0x31, |cpu| ld_ru16(cpu, RegisterName::SP, 12));
-    map.insert(0x32, |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::A, Direction::Decrement, 8));
-    map.insert(0x33, |cpu| inc_r(cpu, RegisterName::SP, 8));
-    map.insert(0x34, |cpu| inc_m(cpu, RegisterName::HL, 12));
-    map.insert(0x35, |cpu| dec_m(cpu, RegisterName::HL, 12));
-    map.insert(0x36, |cpu| ld_m_u8(cpu, RegisterName::HL, 12));
-    map.insert(0x37, |cpu| scf(cpu, 4));
-    map.insert(0x38, |cpu| jr_if(cpu, Flag::C, true, 8));
-    map.insert(0x39, |cpu| add_rr(cpu, RegisterName::HL, RegisterName::SP, 8));
-    map.insert(0x3A, |cpu| ld_rm(cpu, RegisterName::A, RegisterName::HL, Direction::Decrement, 8));
-    /*E*/
-    map.insert(0x3B, |cpu| dec_r(cpu, RegisterName::SP, 8));
-    map.insert(0x3C, |cpu| inc_r(cpu, RegisterName::A, 4));
-    map.insert(0x3D, |cpu| dec_r(cpu, RegisterName::A, 4));
-    map.insert(0x3E, |cpu| ld_ru8(cpu, RegisterName::A, 8));
-    map.insert(0x3F, |cpu| ccf(cpu, 4));
-
-    //LD B
-    map.insert(0x40, |cpu| ld_rr(cpu, RegisterName::B, RegisterName::B, 4));
-    map.insert(0x41, |cpu| ld_rr(cpu, RegisterName::B, RegisterName::C, 4));
-    map.insert(0x42, |cpu| ld_rr(cpu, RegisterName::B, RegisterName::D, 4));
-    map.insert(0x43, |cpu| ld_rr(cpu, RegisterName::B, RegisterName::E, 4));
-    map.insert(0x44, |cpu| ld_rr(cpu, RegisterName::B, RegisterName::H, 4));
-    map.insert(0x45, |cpu| ld_rr(cpu, RegisterName::B, RegisterName::L, 4));
-    map.insert(0x46, |cpu| ld_rm(cpu, RegisterName::B, RegisterName::HL, Direction::None, 8));
-    map.insert(0x47, |cpu| ld_rr(cpu, RegisterName::B, RegisterName::A, 4));
-
-    //LD C
-    map.insert(0x48, |cpu| ld_rr(cpu, RegisterName::C, RegisterName::B, 4));
-    map.insert(0x49, |cpu| ld_rr(cpu, RegisterName::C, RegisterName::C, 4));
-    map.insert(0x4A, |cpu| ld_rr(cpu, RegisterName::C, RegisterName::D, 4));
-    map.insert(0x4B, |cpu| ld_rr(cpu, RegisterName::C, RegisterName::E, 4));
-    map.insert(0x4C, |cpu| ld_rr(cpu, RegisterName::C, RegisterName::H, 4));
-    map.insert(0x4D, |cpu| ld_rr(cpu, RegisterName::C, RegisterName::L, 4));
-    map.insert(0x4E, |cpu| ld_rm(cpu, RegisterName::C, RegisterName::HL, Direction::None, 8));
-    map.insert(0x4F, |cpu| ld_rr(cpu, RegisterName::C, RegisterName::A, 4));
-
-    //LD D
-    map.insert(0x50, |cpu| ld_rr(cpu, RegisterName::D, RegisterName::B, 4));
-    map.insert(0x51, |cpu| ld_rr(cpu, RegisterName::D, RegisterName::C, 4));
-    map.insert(0x52, |cpu| ld_rr(cpu, RegisterName::D, RegisterName::D, 4));
-    map.insert(0x53, |cpu| ld_rr(cpu, RegisterName::D, RegisterName::E, 4));
-    map.insert(0x54, |cpu| ld_rr(cpu, RegisterName::D, RegisterName::H, 4));
-    map.insert(0x55, |cpu| ld_rr(cpu, RegisterName::D, RegisterName::L, 4));
-    map.insert(0x56, |cpu| ld_rm(cpu, RegisterName::D, RegisterName::HL, Direction::None, 8));
-    map.insert(0x57, |cpu| ld_rr(cpu, RegisterName::D, RegisterName::A, 4));
-
-    //LD E
-    map.insert(0x58, |cpu| ld_rr(cpu, RegisterName::E, RegisterName::B, 4));
-    map.insert(0x59, |cpu| ld_rr(cpu, RegisterName::E, RegisterName::C, 4));
-    map.insert(0x5A, |cpu| ld_rr(cpu, RegisterName::E, RegisterName::D, 4));
-    map.insert(0x5B, |cpu| ld_rr(cpu, RegisterName::E, RegisterName::E, 4));
-    map.insert(0x5C, |cpu| ld_rr(cpu, RegisterName::E, RegisterName::H, 4));
-    map.insert(0x5D, |cpu| ld_rr(cpu, RegisterName::E, RegisterName::L, 4));
-    map.insert(0x5E, |cpu| ld_rm(cpu, RegisterName::E, RegisterName::HL, Direction::None, 8));
-    map.insert(0x5F, |cpu| ld_rr(cpu, RegisterName::E, RegisterName::A, 4));
-
-    //LD H
-    map.insert(0x60, |cpu| ld_rr(cpu, RegisterName::H, RegisterName::B, 4));
-    map.insert(0x61, |cpu| ld_rr(cpu, RegisterName::H, RegisterName::C, 4));
-    map.insert(0x62, |cpu| ld_rr(cpu, RegisterName::H, RegisterName::D, 4));
-    map.insert(0x63, |cpu| ld_rr(cpu, RegisterName::H, RegisterName::E, 4));
-    map.insert(0x64, |cpu| ld_rr(cpu, RegisterName::H, RegisterName::H, 4));
-    map.insert(0x65, |cpu| ld_rr(cpu, RegisterName::H, RegisterName::L, 4));
-    map.insert(0x66, |cpu| ld_rm(cpu, RegisterName::H, RegisterName::HL, Direction::None, 8));
-    map.insert(0x67, |cpu| ld_rr(cpu, RegisterName::H, RegisterName::A, 4));
-
-    //LD L
-    map.insert(0x68, |cpu| ld_rr(cpu, RegisterName::L, RegisterName::B, 4));
-    map.insert(0x69, |cpu| ld_rr(cpu, RegisterName::L, RegisterName::C, 4));
-    map.insert(0x6A, |cpu| ld_rr(cpu, RegisterName::L, RegisterName::D, 4));
-    map.insert(0x6B, |cpu| ld_rr(cpu, RegisterName::L, RegisterName::E, 4));
-    map.insert(0x6C, |cpu| ld_rr(cpu, RegisterName::L, RegisterName::H, 4));
-    map.insert(0x6D, |cpu| ld_rr(cpu, RegisterName::L, RegisterName::L, 4));
-    map.insert(0x6E, |cpu| ld_rm(cpu, RegisterName::L, RegisterName::HL, Direction::None, 8));
-    map.insert(0x6F, |cpu| ld_rr(cpu, RegisterName::L, RegisterName::A, 4));
-
-    //LD (HL)
-    map.insert(0x70, |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::B, Direction::None, 8));
-    map.insert(0x71, |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::C, Direction::None, 8));
-    map.insert(0x72, |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::D, Direction::None, 8));
-    map.insert(0x73, |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::E, Direction::None, 8));
-    map.insert(0x74, |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::H, Direction::None, 8));
-    map.insert(0x75, |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::L, Direction::None, 8));
-    map.insert(0x76, |cpu| halt(cpu, 4));
-    map.insert(0x77, |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::A, Direction::None, 8));
-
-    //LD A
-    map.insert(0x78, |cpu| ld_rr(cpu, RegisterName::A, RegisterName::B, 4));
-    map.insert(0x79, |cpu| ld_rr(cpu, RegisterName::A, RegisterName::C, 4));
-    map.insert(0x7A, |cpu| ld_rr(cpu, RegisterName::A, RegisterName::D, 4));
-    map.insert(0x7B, |cpu| ld_rr(cpu, RegisterName::A, RegisterName::E, 4));
-    map.insert(0x7C, |cpu| ld_rr(cpu, RegisterName::A, RegisterName::H, 4));
-    map.insert(0x7D, |cpu| ld_rr(cpu, RegisterName::A, RegisterName::L, 4));
-    map.insert(0x7E, |cpu| ld_rm(cpu, RegisterName::A, RegisterName::HL, Direction::None, 8));
-    map.insert(0x7F, |cpu| ld_rr(cpu, RegisterName::A, RegisterName::A, 4));
-
-    //ADD
-    map.insert(0x80, |cpu| add_rr(cpu, RegisterName::A, RegisterName::B , 4));
-    map.insert(0x81, |cpu| add_rr(cpu, RegisterName::A, RegisterName::C , 4));
-    map.insert(0x82, |cpu| add_rr(cpu, RegisterName::A, RegisterName::D , 4));
-    map.insert(0x83, |cpu| add_rr(cpu, RegisterName::A, RegisterName::E , 4));
-    map.insert(0x84, |cpu| add_rr(cpu, RegisterName::A, RegisterName::H , 4));
-    map.insert(0x85, |cpu| add_rr(cpu, RegisterName::A, RegisterName::L , 4));
-    map.insert(0x86, |cpu| add_rm(cpu, RegisterName::A, RegisterName::HL , 8));
-    map.insert(0x87, |cpu| add_rr(cpu, RegisterName::A, RegisterName::A , 4));
-
-    //ADC
-    map.insert(0x88, |cpu| adc_rr(cpu, RegisterName::A, RegisterName::B , 4));
-    map.insert(0x89, |cpu| adc_rr(cpu, RegisterName::A, RegisterName::C , 4));
-    map.insert(0x8A, |cpu| adc_rr(cpu, RegisterName::A, RegisterName::D , 4));
-    map.insert(0x8B, |cpu| adc_rr(cpu, RegisterName::A, RegisterName::E , 4));
-    map.insert(0x8C, |cpu| adc_rr(cpu, RegisterName::A, RegisterName::H , 4));
-    map.insert(0x8D, |cpu| adc_rr(cpu, RegisterName::A, RegisterName::L , 4));
-    map.insert(0x8E, |cpu| adc_rm(cpu, RegisterName::A, RegisterName::HL , 8));
-    map.insert(0x8F, |cpu| adc_rr(cpu, RegisterName::A, RegisterName::A , 4));
-
-    //SUB
-    map.insert(0x90, |cpu| sub_rr(cpu, RegisterName::A, RegisterName::B , 4));
-    map.insert(0x91, |cpu| sub_rr(cpu, RegisterName::A, RegisterName::C , 4));
-    map.insert(0x92, |cpu| sub_rr(cpu, RegisterName::A, RegisterName::D , 4));
-    map.insert(0x93, |cpu| sub_rr(cpu, RegisterName::A, RegisterName::E , 4));
-    map.insert(0x94, |cpu| sub_rr(cpu, RegisterName::A, RegisterName::H , 4));
-    map.insert(0x95, |cpu| sub_rr(cpu, RegisterName::A, RegisterName::L , 4));
-    map.insert(0x96, |cpu| sub_rm(cpu, RegisterName::A, RegisterName::HL , 8));
-    map.insert(0x97, |cpu| sub_rr(cpu, RegisterName::A, RegisterName::A , 4));
-
-    //SBC
-    map.insert(0x98, |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::B , 4));
-    map.insert(0x99, |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::C , 4));
-    map.insert(0x9A, |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::D , 4));
-    map.insert(0x9B, |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::E , 4));
-    map.insert(0x9C, |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::H , 4));
-    map.insert(0x9D, |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::L , 4));
-    map.insert(0x9E, |cpu| sbc_rm(cpu, RegisterName::A, RegisterName::HL , 8));
-    map.insert(0x9F, |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::A , 4));
-
-    //AND
-    map.insert(0xA0, |cpu| and_rr(cpu, RegisterName::A, RegisterName::B , 4));
-    map.insert(0xA1, |cpu| and_rr(cpu, RegisterName::A, RegisterName::C , 4));
-    map.insert(0xA2, |cpu| and_rr(cpu, RegisterName::A, RegisterName::D , 4));
-    map.insert(0xA3, |cpu| and_rr(cpu, RegisterName::A, RegisterName::E , 4));
-    map.insert(0xA4, |cpu| and_rr(cpu, RegisterName::A, RegisterName::H , 4));
-    map.insert(0xA5, |cpu| and_rr(cpu, RegisterName::A, RegisterName::L , 4));
-    map.insert(0xA6, |cpu| and_rm(cpu, RegisterName::A, RegisterName::HL , 8));
-    map.insert(0xA7, |cpu| and_rr(cpu, RegisterName::A, RegisterName::A , 4));
-
-    //XOR
-    map.insert(0xA8, |cpu| xor_rr(cpu, RegisterName::A, RegisterName::B , 4));
-    map.insert(0xA9, |cpu| xor_rr(cpu, RegisterName::A, RegisterName::C , 4));
-    map.insert(0xAA, |cpu| xor_rr(cpu, RegisterName::A, RegisterName::D , 4));
-    map.insert(0xAB, |cpu| xor_rr(cpu, RegisterName::A, RegisterName::E , 4));
-    map.insert(0xAC, |cpu| xor_rr(cpu, RegisterName::A, RegisterName::H , 4));
-    map.insert(0xAD, |cpu| xor_rr(cpu, RegisterName::A, RegisterName::L , 4));
-    map.insert(0xAE, |cpu| xor_rm(cpu, RegisterName::A, RegisterName::HL , 8));
-    map.insert(0xAF, |cpu| xor_rr(cpu, RegisterName::A, RegisterName::A , 4));
-
-
-    //OR
-    map.insert(0xB0, |cpu| or_rr(cpu, RegisterName::A, RegisterName::B , 4));
-    map.insert(0xB1, |cpu| or_rr(cpu, RegisterName::A, RegisterName::C , 4));
-    map.insert(0xB2, |cpu| or_rr(cpu, RegisterName::A, RegisterName::D , 4));
-    map.insert(0xB3, |cpu| or_rr(cpu, RegisterName::A, RegisterName::E , 4));
-    map.insert(0xB4, |cpu| or_rr(cpu, RegisterName::A, RegisterName::H , 4));
-    map.insert(0xB5, |cpu| or_rr(cpu, RegisterName::A, RegisterName::L , 4));
-    map.insert(0xB6, |cpu| or_rm(cpu, RegisterName::A, RegisterName::HL , 8));
-    map.insert(0xB7, |cpu| or_rr(cpu, RegisterName::A, RegisterName::A , 4));
-
-    //CP
-    map.insert(0xB8, |cpu| cp_rr(cpu, RegisterName::A, RegisterName::B , 4));
-    map.insert(0xB9, |cpu| cp_rr(cpu, RegisterName::A, RegisterName::C , 4));
-    map.insert(0xBA, |cpu| cp_rr(cpu, RegisterName::A, RegisterName::D , 4));
-    map.insert(0xBB, |cpu| cp_rr(cpu, RegisterName::A, RegisterName::E , 4));
-    map.insert(0xBC, |cpu| cp_rr(cpu, RegisterName::A, RegisterName::H , 4));
-    map.insert(0xBD, |cpu| cp_rr(cpu, RegisterName::A, RegisterName::L , 4));
-    map.insert(0xBE, |cpu| cp_rm(cpu, RegisterName::A, RegisterName::HL , 8));
-    map.insert(0xBF, |cpu| cp_rr(cpu, RegisterName::A, RegisterName::A , 4));
-
-    //0xC0
-    map.insert(0xC0, |cpu| ret_if(cpu, Flag::Z, false, 8));
-    map.insert(0xC1, |cpu| pop_r(cpu, RegisterName::BC, 12));
-    map.insert(0xC2, |cpu| jp_if(cpu, Flag::Z, false, 12));
-
-
-    map.insert(0xC4, |cpu| call_if(cpu, Flag::Z, false, 12));
-    map.insert(0xC5, |cpu| push_r(cpu, RegisterName::BC, 16));
-    map.insert(0xC6, |cpu| add_rv(cpu, RegisterName::A, 8));
-    map.insert(0xC7, |cpu| rst(cpu, 0x0000, 16));
-
-    //0xC8
-    map.insert(0xC8, |cpu| ret_if(cpu, Flag::Z, true, 8));
-    map.insert(0xC9, |cpu| ret(cpu, 16));
-    map.insert(0xCA, |cpu| jp_if(cpu, Flag::Z, true, 12));
-    //Prefix CB map.insert(0xCB, |cpu| cp_rr(cpu, RegisterName::A, RegisterName::E));
-    map.insert(0xCC, |cpu| call_if(cpu, Flag::Z, true, 12));
-    map.insert(0xCD, |cpu| call(cpu, 24));
-    map.insert(0xCE, |cpu| adc_rv(cpu, RegisterName::A, 8));
-    map.insert(0xCF, |cpu| rst(cpu, 0x0008, 16));
-
-    //0xD0
-    map.insert(0xD0, |cpu| ret_if(cpu, Flag::C, false,8));
-    map.insert(0xD1, |cpu| pop_r(cpu, RegisterName::DE,12));
-    map.insert(0xD2, |cpu| jp_if(cpu, Flag::C, false,12));
-    map.insert(0xD4, |cpu| call_if(cpu, Flag::C, false,12));
-    map.insert(0xD5, |cpu| push_r(cpu, RegisterName::DE,16));
-    map.insert(0xD6, |cpu| sub_rv(cpu, RegisterName::A,8));
-    map.insert(0xD7, |cpu| rst(cpu, 0x0010,16));
-
-    //0xD8
-    map.insert(0xD8, |cpu| ret_if(cpu, Flag::C, true,8));
-    map.insert(0xD9, |cpu| reti(cpu, 16));
-    map.insert(0xDA, |cpu| jp_if(cpu, Flag::C, true, 12));
-    map.insert(0xDC, |cpu| call_if(cpu, Flag::C, true, 12));
-    map.insert(0xDE, |cpu| sbc_rv(cpu, RegisterName::A, 8));
-    map.insert(0xDF, |cpu| rst(cpu, 0x0018, 16));
-
-    //0xE0
-    map.insert(0xE0, |cpu| ld_ff00_u8_a(cpu, 12));
-    map.insert(0xE1, |cpu| pop_r(cpu, RegisterName::HL, 12));
-    map.insert(0xE2, |cpu| ld_ff00_c_a(cpu, 8));
-    map.insert(0xE5, |cpu| push_r(cpu, RegisterName::HL, 16));
-    map.insert(0xE6, |cpu| and_rv(cpu, RegisterName::A,8));
-    map.insert(0xE7, |cpu| rst(cpu, 0x0020, 16));
-
-    //0xE8
-    map.insert(0xE8, |cpu| add_sp_i8(cpu, 16));
-    map.insert(0xE9, |cpu| jp_r(cpu, RegisterName::HL, 4));
-    map.insert(0xEA, |cpu| ld_mvr(cpu, RegisterName::A, 16));
-    map.insert(0xEE, |cpu| xor_rv(cpu, RegisterName::A, 8));
-    map.insert(0xEF, |cpu| rst(cpu, 0x0028, 16));
-
-
-    //0xF0
-    map.insert(0xF0, |cpu| ld_a_ff00_plus_u8(cpu, 12));
-    map.insert(0xF1, |cpu| pop_r(cpu, RegisterName::AF, 12));
-    map.insert(0xF2, |cpu| ld_a_ff00_c(cpu, 8));
-    map.insert(0xF3, |cpu| di(cpu, 4));
-    map.insert(0xF5, |cpu| push_r(cpu, RegisterName::AF, 16));
-    map.insert(0xF6, |cpu| or_rv(cpu, RegisterName::A, 8));
-    map.insert(0xF7, |cpu| rst(cpu, 0x0030, 16));
-
-    //0xF8
-    map.insert(0xF8, |cpu| ld_hl_spi8(cpu, 12));
-    map.insert(0xF9, |cpu| ld_rr(cpu, RegisterName::SP, RegisterName::HL, 8));
-    map.insert(0xFA, |cpu| ld_rmv(cpu, RegisterName::A, 16));
-    map.insert(0xFB, |cpu| ei(cpu, 4));
-
-    map.insert(0xFE, |cpu| cp_rv(cpu, RegisterName::A, 8));
-    map.insert(0xFF, |cpu| rst(cpu, 0x0038, 16));
-
-
-    map
-}
\ No newline at end of file
+// The cycles come from the table, conditional jumps, calls and returns only tell
+// whether the branch was taken
+#[derive(Copy, Clone)]
+pub enum OpCodeHandler {
+    Op(fn(&mut CPU)),
+    Branch(fn(&mut CPU) -> bool),
+}
+
+// Everything about an opcode in one place: the decoder runs the handler, the
+// disassembler and tracer use the rest. Illegal opcodes have no handler.
+#[derive(Copy, Clone)]
+pub struct Instruction<H> {
+    pub mnemonic: &'static str, // u8, i8 and u16 stand for the operand bytes
+    pub length: u8,
+    pub cycles: u8,
+    pub branch_cycles: u8,      // when a conditional jump, call or return is taken
+    pub flags: &'static str,    // Z N H C, - untouched, 0 or 1 forced, letter if it depends on the result
+    pub handler: Option<H>,
+}
+
+const ILLEGAL: Instruction<OpCodeHandler> = Instruction {
+    mnemonic: "ILLEGAL",
+    length: 1,
+    cycles: 4,
+    branch_cycles: 4,
+    flags: "----",
+    handler: None,
+};
+
+const fn op(mnemonic: &'static str, length: u8, cycles: u8, flags: &'static str, handler: fn(&mut CPU)) -> Instruction<OpCodeHandler> {
+    Instruction { mnemonic, length, cycles, branch_cycles: cycles, flags, handler: Some(OpCodeHandler::Op(handler)) }
+}
+
+const fn branch(mnemonic: &'static str, length: u8, cycles: u8, branch_cycles: u8, handler: fn(&mut CPU) -> bool) -> Instruction<OpCodeHandler> {
+    Instruction { mnemonic, length, cycles, branch_cycles, flags: "----", handler: Some(OpCodeHandler::Branch(handler)) }
+}
+
+pub static INSTRUCTIONS: [Instruction<OpCodeHandler>; 256] = init_instructions();
+
+const fn init_instructions() -> [Instruction<OpCodeHandler>; 256] {
+    let mut table = [ILLEGAL; 256];
+
+    table[0x00] = op("NOP", 1, 4, "----", |_| {});
+    table[0x01] = op("LD BC,u16", 3, 12, "----", |cpu| ld_ru16(cpu, RegisterName::BC));
+    table[0x02] = op("LD (BC),A", 1, 8, "----", |cpu| ld_mr(cpu, RegisterName::BC, RegisterName::A, Direction::None));
+    table[0x03] = op("INC BC", 1, 8, "----", |cpu| inc_r(cpu, RegisterName::BC));
+    table[0x04] = op("INC B", 1, 4, "Z0H-", |cpu| inc_r(cpu, RegisterName::B));
+    table[0x05] = op("DEC B", 1, 4, "Z1H-", |cpu| dec_r(cpu, RegisterName::B));
+    table[0x06] = op("LD B,u8", 2, 8, "----", |cpu| ld_ru8(cpu, RegisterName::B));
+    table[0x07] = op("RLCA", 1, 4, "000C", rlca);
+    table[0x08] = op("LD (u16),SP", 3, 20, "----", ld_u16_sp);
+    table[0x09] = op("ADD HL,BC", 1, 8, "-0HC", |cpu| add_rr(cpu, RegisterName::HL, RegisterName::BC));
+    table[0x0A] = op("LD A,(BC)", 1, 8, "----", |cpu| ld_rm(cpu, RegisterName::A, RegisterName::BC, Direction::None));
+    table[0x0B] = op("DEC BC", 1, 8, "----", |cpu| dec_r(cpu, RegisterName::BC));
+    table[0x0C] = op("INC C", 1, 4, "Z0H-", |cpu| inc_r(cpu, RegisterName::C));
+    table[0x0D] = op("DEC C", 1, 4, "Z1H-", |cpu| dec_r(cpu, RegisterName::C));
+    table[0x0E] = op("LD C,u8", 2, 8, "----", |cpu| ld_ru8(cpu, RegisterName::C));
+    table[0x0F] = op("RRCA", 1, 4, "000C", rrca);
+
+    table[0x10] = op("STOP", 2, 4, "----", stop);
+    table[0x11] = op("LD DE,u16", 3, 12, "----", |cpu| ld_ru16(cpu, RegisterName::DE));
+    table[0x12] = op("LD (DE),A", 1, 8, "----", |cpu| ld_mr(cpu, RegisterName::DE, RegisterName::A, Direction::None));
+    table[0x13] = op("INC DE", 1, 8, "----", |cpu| inc_r(cpu, RegisterName::DE));
+    table[0x14] = op("INC D", 1, 4, "Z0H-", |cpu| inc_r(cpu, RegisterName::D));
+    table[0x15] = op("DEC D", 1, 4, "Z1H-", |cpu| dec_r(cpu, RegisterName::D));
+    table[0x16] = op("LD D,u8", 2, 8, "----", |cpu| ld_ru8(cpu, RegisterName::D));
+    table[0x17] = op("RLA", 1, 4, "000C", rla);
+    table[0x18] = op("JR i8", 2, 12, "----", jr);
+    table[0x19] = op("ADD HL,DE", 1, 8, "-0HC", |cpu| add_rr(cpu, RegisterName::HL, RegisterName::DE));
+    table[0x1A] = op("LD A,(DE)", 1, 8, "----", |cpu| ld_rm(cpu, RegisterName::A, RegisterName::DE, Direction::None));
+    table[0x1B] = op("DEC DE", 1, 8, "----", |cpu| dec_r(cpu, RegisterName::DE));
+    table[0x1C] = op("INC E", 1, 4, "Z0H-", |cpu| inc_r(cpu, RegisterName::E));
+    table[0x1D] = op("DEC E", 1, 4, "Z1H-", |cpu| dec_r(cpu, RegisterName::E));
+    table[0x1E] = op("LD E,u8", 2, 8, "----", |cpu| ld_ru8(cpu, RegisterName::E));
+    table[0x1F] = op("RRA", 1, 4, "000C", rra);
+
+    table[0x20] = branch("JR NZ,i8", 2, 8, 12, |cpu| jr_if(cpu, Flag::Z, false));
+    table[0x21] = op("LD HL,u16", 3, 12, "----", |cpu| ld_ru16(cpu, RegisterName::HL));
+    table[0x22] = op("LD (HL+),A", 1, 8, "----", |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::A, Direction::Increment));
+    table[0x23] = op("INC HL", 1, 8, "----", |cpu| inc_r(cpu, RegisterName::HL));
+    table[0x24] = op("INC H", 1, 4, "Z0H-", |cpu| inc_r(cpu, RegisterName::H));
+    table[0x25] = op("DEC H", 1, 4, "Z1H-", |cpu| dec_r(cpu, RegisterName::H));
+    table[0x26] = op("LD H,u8", 2, 8, "----", |cpu| ld_ru8(cpu, RegisterName::H));
+    table[0x27] = op("DAA", 1, 4, "Z-0C", daa);
+    table[0x28] = branch("JR Z,i8", 2, 8, 12, |cpu| jr_if(cpu, Flag::Z, true));
+    table[0x29] = op("ADD HL,HL", 1, 8, "-0HC", |cpu| add_rr(cpu, RegisterName::HL, RegisterName::HL));
+    table[0x2A] = op("LD A,(HL+)", 1, 8, "----", |cpu| ld_rm(cpu, RegisterName::A, RegisterName::HL, Direction::Increment));
+    table[0x2B] = op("DEC HL", 1, 8, "----", |cpu| dec_r(cpu, RegisterName::HL));
+    table[0x2C] = op("INC L", 1, 4, "Z0H-", |cpu| inc_r(cpu, RegisterName::L));
+    table[0x2D] = op("DEC L", 1, 4, "Z1H-", |cpu| dec_r(cpu, RegisterName::L));
+    table[0x2E] = op("LD L,u8", 2, 8, "----", |cpu| ld_ru8(cpu, RegisterName::L));
+    table[0x2F] = op("CPL", 1, 4, "-11-", cpl);
+
+    table[0x30] = branch("JR NC,i8", 2, 8, 12, |cpu| jr_if(cpu, Flag::C, false));
+    table[0x31] = op("LD SP,u16", 3, 12, "----", |cpu| ld_ru16(cpu, RegisterName::SP));
+    table[0x32] = op("LD (HL-),A", 1, 8, "----", |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::A, Direction::Decrement));
+    table[0x33] = op("INC SP", 1, 8, "----", |cpu| inc_r(cpu, RegisterName::SP));
+    table[0x34] = op("INC (HL)", 1, 12, "Z0H-", |cpu| inc_m(cpu, RegisterName::HL));
+    table[0x35] = op("DEC (HL)", 1, 12, "Z1H-", |cpu| dec_m(cpu, RegisterName::HL));
+    table[0x36] = op("LD (HL),u8", 2, 12, "----", |cpu| ld_m_u8(cpu, RegisterName::HL));
+    table[0x37] = op("SCF", 1, 4, "-001", scf);
+    table[0x38] = branch("JR C,i8", 2, 8, 12, |cpu| jr_if(cpu, Flag::C, true));
+    table[0x39] = op("ADD HL,SP", 1, 8, "-0HC", |cpu| add_rr(cpu, RegisterName::HL, RegisterName::SP));
+    table[0x3A] = op("LD A,(HL-)", 1, 8, "----", |cpu| ld_rm(cpu, RegisterName::A, RegisterName::HL, Direction::Decrement));
+    table[0x3B] = op("DEC SP", 1, 8, "----", |cpu| dec_r(cpu, RegisterName::SP));
+    table[0x3C] = op("INC A", 1, 4, "Z0H-", |cpu| inc_r(cpu, RegisterName::A));
+    table[0x3D] = op("DEC A", 1, 4, "Z1H-", |cpu| dec_r(cpu, RegisterName::A));
+    table[0x3E] = op("LD A,u8", 2, 8, "----", |cpu| ld_ru8(cpu, RegisterName::A));
+    table[0x3F] = op("CCF", 1, 4, "-00C", ccf);
+
+    table[0x40] = op("LD B,B", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::B, RegisterName::B));
+    table[0x41] = op("LD B,C", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::B, RegisterName::C));
+    table[0x42] = op("LD B,D", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::B, RegisterName::D));
+    table[0x43] = op("LD B,E", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::B, RegisterName::E));
+    table[0x44] = op("LD B,H", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::B, RegisterName::H));
+    table[0x45] = op("LD B,L", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::B, RegisterName::L));
+    table[0x46] = op("LD B,(HL)", 1, 8, "----", |cpu| ld_rm(cpu, RegisterName::B, RegisterName::HL, Direction::None));
+    table[0x47] = op("LD B,A", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::B, RegisterName::A));
+    table[0x48] = op("LD C,B", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::C, RegisterName::B));
+    table[0x49] = op("LD C,C", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::C, RegisterName::C));
+    table[0x4A] = op("LD C,D", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::C, RegisterName::D));
+    table[0x4B] = op("LD C,E", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::C, RegisterName::E));
+    table[0x4C] = op("LD C,H", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::C, RegisterName::H));
+    table[0x4D] = op("LD C,L", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::C, RegisterName::L));
+    table[0x4E] = op("LD C,(HL)", 1, 8, "----", |cpu| ld_rm(cpu, RegisterName::C, RegisterName::HL, Direction::None));
+    table[0x4F] = op("LD C,A", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::C, RegisterName::A));
+
+    table[0x50] = op("LD D,B", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::D, RegisterName::B));
+    table[0x51] = op("LD D,C", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::D, RegisterName::C));
+    table[0x52] = op("LD D,D", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::D, RegisterName::D));
+    table[0x53] = op("LD D,E", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::D, RegisterName::E));
+    table[0x54] = op("LD D,H", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::D, RegisterName::H));
+    table[0x55] = op("LD D,L", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::D, RegisterName::L));
+    table[0x56] = op("LD D,(HL)", 1, 8, "----", |cpu| ld_rm(cpu, RegisterName::D, RegisterName::HL, Direction::None));
+    table[0x57] = op("LD D,A", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::D, RegisterName::A));
+    table[0x58] = op("LD E,B", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::E, RegisterName::B));
+    table[0x59] = op("LD E,C", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::E, RegisterName::C));
+    table[0x5A] = op("LD E,D", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::E, RegisterName::D));
+    table[0x5B] = op("LD E,E", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::E, RegisterName::E));
+    table[0x5C] = op("LD E,H", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::E, RegisterName::H));
+    table[0x5D] = op("LD E,L", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::E, RegisterName::L));
+    table[0x5E] = op("LD E,(HL)", 1, 8, "----", |cpu| ld_rm(cpu, RegisterName::E, RegisterName::HL, Direction::None));
+    table[0x5F] = op("LD E,A", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::E, RegisterName::A));
+
+    table[0x60] = op("LD H,B", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::H, RegisterName::B));
+    table[0x61] = op("LD H,C", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::H, RegisterName::C));
+    table[0x62] = op("LD H,D", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::H, RegisterName::D));
+    table[0x63] = op("LD H,E", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::H, RegisterName::E));
+    table[0x64] = op("LD H,H", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::H, RegisterName::H));
+    table[0x65] = op("LD H,L", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::H, RegisterName::L));
+    table[0x66] = op("LD H,(HL)", 1, 8, "----", |cpu| ld_rm(cpu, RegisterName::H, RegisterName::HL, Direction::None));
+    table[0x67] = op("LD H,A", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::H, RegisterName::A));
+    table[0x68] = op("LD L,B", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::L, RegisterName::B));
+    table[0x69] = op("LD L,C", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::L, RegisterName::C));
+    table[0x6A] = op("LD L,D", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::L, RegisterName::D));
+    table[0x6B] = op("LD L,E", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::L, RegisterName::E));
+    table[0x6C] = op("LD L,H", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::L, RegisterName::H));
+    table[0x6D] = op("LD L,L", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::L, RegisterName::L));
+    table[0x6E] = op("LD L,(HL)", 1, 8, "----", |cpu| ld_rm(cpu, RegisterName::L, RegisterName::HL, Direction::None));
+    table[0x6F] = op("LD L,A", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::L, RegisterName::A));
+
+    table[0x70] = op("LD (HL),B", 1, 8, "----", |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::B, Direction::None));
+    table[0x71] = op("LD (HL),C", 1, 8, "----", |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::C, Direction::None));
+    table[0x72] = op("LD (HL),D", 1, 8, "----", |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::D, Direction::None));
+    table[0x73] = op("LD (HL),E", 1, 8, "----", |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::E, Direction::None));
+    table[0x74] = op("LD (HL),H", 1, 8, "----", |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::H, Direction::None));
+    table[0x75] = op("LD (HL),L", 1, 8, "----", |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::L, Direction::None));
+    table[0x76] = op("HALT", 1, 4, "----", halt);
+    table[0x77] = op("LD (HL),A", 1, 8, "----", |cpu| ld_mr(cpu, RegisterName::HL, RegisterName::A, Direction::None));
+    table[0x78] = op("LD A,B", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::A, RegisterName::B));
+    table[0x79] = op("LD A,C", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::A, RegisterName::C));
+    table[0x7A] = op("LD A,D", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::A, RegisterName::D));
+    table[0x7B] = op("LD A,E", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::A, RegisterName::E));
+    table[0x7C] = op("LD A,H", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::A, RegisterName::H));
+    table[0x7D] = op("LD A,L", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::A, RegisterName::L));
+    table[0x7E] = op("LD A,(HL)", 1, 8, "----", |cpu| ld_rm(cpu, RegisterName::A, RegisterName::HL, Direction::None));
+    table[0x7F] = op("LD A,A", 1, 4, "----", |cpu| ld_rr(cpu, RegisterName::A, RegisterName::A));
+
+    table[0x80] = op("ADD A,B", 1, 4, "Z0HC", |cpu| add_rr(cpu, RegisterName::A, RegisterName::B ));
+    table[0x81] = op("ADD A,C", 1, 4, "Z0HC", |cpu| add_rr(cpu, RegisterName::A, RegisterName::C ));
+    table[0x82] = op("ADD A,D", 1, 4, "Z0HC", |cpu| add_rr(cpu, RegisterName::A, RegisterName::D ));
+    table[0x83] = op("ADD A,E", 1, 4, "Z0HC", |cpu| add_rr(cpu, RegisterName::A, RegisterName::E ));
+    table[0x84] = op("ADD A,H", 1, 4, "Z0HC", |cpu| add_rr(cpu, RegisterName::A, RegisterName::H ));
+    table[0x85] = op("ADD A,L", 1, 4, "Z0HC", |cpu| add_rr(cpu, RegisterName::A, RegisterName::L ));
+    table[0x86] = op("ADD A,(HL)", 1, 8, "Z0HC", |cpu| add_rm(cpu, RegisterName::A, RegisterName::HL ));
+    table[0x87] = op("ADD A,A", 1, 4, "Z0HC", |cpu| add_rr(cpu, RegisterName::A, RegisterName::A ));
+    table[0x88] = op("ADC A,B", 1, 4, "Z0HC", |cpu| adc_rr(cpu, RegisterName::A, RegisterName::B ));
+    table[0x89] = op("ADC A,C", 1, 4, "Z0HC", |cpu| adc_rr(cpu, RegisterName::A, RegisterName::C ));
+    table[0x8A] = op("ADC A,D", 1, 4, "Z0HC", |cpu| adc_rr(cpu, RegisterName::A, RegisterName::D ));
+    table[0x8B] = op("ADC A,E", 1, 4, "Z0HC", |cpu| adc_rr(cpu, RegisterName::A, RegisterName::E ));
+    table[0x8C] = op("ADC A,H", 1, 4, "Z0HC", |cpu| adc_rr(cpu, RegisterName::A, RegisterName::H ));
+    table[0x8D] = op("ADC A,L", 1, 4, "Z0HC", |cpu| adc_rr(cpu, RegisterName::A, RegisterName::L ));
+    table[0x8E] = op("ADC A,(HL)", 1, 8, "Z0HC", |cpu| adc_rm(cpu, RegisterName::A, RegisterName::HL ));
+    table[0x8F] = op("ADC A,A", 1, 4, "Z0HC", |cpu| adc_rr(cpu, RegisterName::A, RegisterName::A ));
+
+    table[0x90] = op("SUB A,B", 1, 4, "Z1HC", |cpu| sub_rr(cpu, RegisterName::A, RegisterName::B ));
+    table[0x91] = op("SUB A,C", 1, 4, "Z1HC", |cpu| sub_rr(cpu, RegisterName::A, RegisterName::C ));
+    table[0x92] = op("SUB A,D", 1, 4, "Z1HC", |cpu| sub_rr(cpu, RegisterName::A, RegisterName::D ));
+    table[0x93] = op("SUB A,E", 1, 4, "Z1HC", |cpu| sub_rr(cpu, RegisterName::A, RegisterName::E ));
+    table[0x94] = op("SUB A,H", 1, 4, "Z1HC", |cpu| sub_rr(cpu, RegisterName::A, RegisterName::H ));
+    table[0x95] = op("SUB A,L", 1, 4, "Z1HC", |cpu| sub_rr(cpu, RegisterName::A, RegisterName::L ));
+    table[0x96] = op("SUB A,(HL)", 1, 8, "Z1HC", |cpu| sub_rm(cpu, RegisterName::A, RegisterName::HL ));
+    table[0x97] = op("SUB A,A", 1, 4, "Z1HC", |cpu| sub_rr(cpu, RegisterName::A, RegisterName::A ));
+    table[0x98] = op("SBC A,B", 1, 4, "Z1HC", |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::B ));
+    table[0x99] = op("SBC A,C", 1, 4, "Z1HC", |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::C ));
+    table[0x9A] = op("SBC A,D", 1, 4, "Z1HC", |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::D ));
+    table[0x9B] = op("SBC A,E", 1, 4, "Z1HC", |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::E ));
+    table[0x9C] = op("SBC A,H", 1, 4, "Z1HC", |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::H ));
+    table[0x9D] = op("SBC A,L", 1, 4, "Z1HC", |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::L ));
+    table[0x9E] = op("SBC A,(HL)", 1, 8, "Z1HC", |cpu| sbc_rm(cpu, RegisterName::A, RegisterName::HL ));
+    table[0x9F] = op("SBC A,A", 1, 4, "Z1HC", |cpu| sbc_rr(cpu, RegisterName::A, RegisterName::A ));
+
+    table[0xA0] = op("AND A,B", 1, 4, "Z010", |cpu| and_rr(cpu, RegisterName::A, RegisterName::B ));
+    table[0xA1] = op("AND A,C", 1, 4, "Z010", |cpu| and_rr(cpu, RegisterName::A, RegisterName::C ));
+    table[0xA2] = op("AND A,D", 1, 4, "Z010", |cpu| and_rr(cpu, RegisterName::A, RegisterName::D ));
+    table[0xA3] = op("AND A,E", 1, 4, "Z010", |cpu| and_rr(cpu, RegisterName::A, RegisterName::E ));
+    table[0xA4] = op("AND A,H", 1, 4, "Z010", |cpu| and_rr(cpu, RegisterName::A, RegisterName::H ));
+    table[0xA5] = op("AND A,L", 1, 4, "Z010", |cpu| and_rr(cpu, RegisterName::A, RegisterName::L ));
+    table[0xA6] = op("AND A,(HL)", 1, 8, "Z010", |cpu| and_rm(cpu, RegisterName::A, RegisterName::HL ));
+    table[0xA7] = op("AND A,A", 1, 4, "Z010", |cpu| and_rr(cpu, RegisterName::A, RegisterName::A ));
+    table[0xA8] = op("XOR A,B", 1, 4, "Z000", |cpu| xor_rr(cpu, RegisterName::A, RegisterName::B ));
+    table[0xA9] = op("XOR A,C", 1, 4, "Z000", |cpu| xor_rr(cpu, RegisterName::A, RegisterName::C ));
+    table[0xAA] = op("XOR A,D", 1, 4, "Z000", |cpu| xor_rr(cpu, RegisterName::A, RegisterName::D ));
+    table[0xAB] = op("XOR A,E", 1, 4, "Z000", |cpu| xor_rr(cpu, RegisterName::A, RegisterName::E ));
+    table[0xAC] = op("XOR A,H", 1, 4, "Z000", |cpu| xor_rr(cpu, RegisterName::A, RegisterName::H ));
+    table[0xAD] = op("XOR A,L", 1, 4, "Z000", |cpu| xor_rr(cpu, RegisterName::A, RegisterName::L ));
+    table[0xAE] = op("XOR A,(HL)", 1, 8, "Z000", |cpu| xor_rm(cpu, RegisterName::A, RegisterName::HL ));
+    table[0xAF] = op("XOR A,A", 1, 4, "Z000", |cpu| xor_rr(cpu, RegisterName::A, RegisterName::A ));
+
+    table[0xB0] = op("OR A,B", 1, 4, "Z000", |cpu| or_rr(cpu, RegisterName::A, RegisterName::B ));
+    table[0xB1] = op("OR A,C", 1, 4, "Z000", |cpu| or_rr(cpu, RegisterName::A, RegisterName::C ));
+    table[0xB2] = op("OR A,D", 1, 4, "Z000", |cpu| or_rr(cpu, RegisterName::A, RegisterName::D ));
+    table[0xB3] = op("OR A,E", 1, 4, "Z000", |cpu| or_rr(cpu, RegisterName::A, RegisterName::E ));
+    table[0xB4] = op("OR A,H", 1, 4, "Z000", |cpu| or_rr(cpu, RegisterName::A, RegisterName::H ));
+    table[0xB5] = op("OR A,L", 1, 4, "Z000", |cpu| or_rr(cpu, RegisterName::A, RegisterName::L ));
+    table[0xB6] = op("OR A,(HL)", 1, 8, "Z000", |cpu| or_rm(cpu, RegisterName::A, RegisterName::HL ));
+    table[0xB7] = op("OR A,A", 1, 4, "Z000", |cpu| or_rr(cpu, RegisterName::A, RegisterName::A ));
+    table[0xB8] = op("CP A,B", 1, 4, "Z1HC", |cpu| cp_rr(cpu, RegisterName::A, RegisterName::B ));
+    table[0xB9] = op("CP A,C", 1, 4, "Z1HC", |cpu| cp_rr(cpu, RegisterName::A, RegisterName::C ));
+    table[0xBA] = op("CP A,D", 1, 4, "Z1HC", |cpu| cp_rr(cpu, RegisterName::A, RegisterName::D ));
+    table[0xBB] = op("CP A,E", 1, 4, "Z1HC", |cpu| cp_rr(cpu, RegisterName::A, RegisterName::E ));
+    table[0xBC] = op("CP A,H", 1, 4, "Z1HC", |cpu| cp_rr(cpu, RegisterName::A, RegisterName::H ));
+    table[0xBD] = op("CP A,L", 1, 4, "Z1HC", |cpu| cp_rr(cpu, RegisterName::A, RegisterName::L ));
+    table[0xBE] = op("CP A,(HL)", 1, 8, "Z1HC", |cpu| cp_rm(cpu, RegisterName::A, RegisterName::HL ));
+    table[0xBF] = op("CP A,A", 1, 4, "Z1HC", |cpu| cp_rr(cpu, RegisterName::A, RegisterName::A ));
+
+    table[0xC0] = branch("RET NZ", 1, 8, 20, |cpu| ret_if(cpu, Flag::Z, false));
+    table[0xC1] = op("POP BC", 1, 12, "----", |cpu| pop_r(cpu, RegisterName::BC));
+    table[0xC2] = branch("JP NZ,u16", 3, 12, 16, |cpu| jp_if(cpu, Flag::Z, false));
+    table[0xC3] = op("JP u16", 3, 16, "----", jp);
+    table[0xC4] = branch("CALL NZ,u16", 3, 12, 24, |cpu| call_if(cpu, Flag::Z, false));
+    table[0xC5] = op("PUSH BC", 1, 16, "----", |cpu| push_r(cpu, RegisterName::BC));
+    table[0xC6] = op("ADD A,u8", 2, 8, "Z0HC", |cpu| add_rv(cpu, RegisterName::A));
+    table[0xC7] = op("RST 00H", 1, 16, "----", |cpu| rst(cpu, 0x0000));
+    table[0xC8] = branch("RET Z", 1, 8, 20, |cpu| ret_if(cpu, Flag::Z, true));
+    table[0xC9] = op("RET", 1, 16, "----", ret);
+    table[0xCA] = branch("JP Z,u16", 3, 12, 16, |cpu| jp_if(cpu, Flag::Z, true));
+    table[0xCC] = branch("CALL Z,u16", 3, 12, 24, |cpu| call_if(cpu, Flag::Z, true));
+    table[0xCD] = op("CALL u16", 3, 24, "----", call);
+    table[0xCE] = op("ADC A,u8", 2, 8, "Z0HC", |cpu| adc_rv(cpu, RegisterName::A));
+    table[0xCF] = op("RST 08H", 1, 16, "----", |cpu| rst(cpu, 0x0008));
+
+    table[0xD0] = branch("RET NC", 1, 8, 20, |cpu| ret_if(cpu, Flag::C, false));
+    table[0xD1] = op("POP DE", 1, 12, "----", |cpu| pop_r(cpu, RegisterName::DE));
+    table[0xD2] = branch("JP NC,u16", 3, 12, 16, |cpu| jp_if(cpu, Flag::C, false));
+    table[0xD4] = branch("CALL NC,u16", 3, 12, 24, |cpu| call_if(cpu, Flag::C, false));
+    table[0xD5] = op("PUSH DE", 1, 16, "----", |cpu| push_r(cpu, RegisterName::DE));
+    table[0xD6] = op("SUB A,u8", 2, 8, "Z1HC", |cpu| sub_rv(cpu, RegisterName::A));
+    table[0xD7] = op("RST 10H", 1, 16, "----", |cpu| rst(cpu, 0x0010));
+    table[0xD8] = branch("RET C", 1, 8, 20, |cpu| ret_if(cpu, Flag::C, true));
+    table[0xD9] = op("RETI", 1, 16, "----", reti);
+    table[0xDA] = branch("JP C,u16", 3, 12, 16, |cpu| jp_if(cpu, Flag::C, true));
+    table[0xDC] = branch("CALL C,u16", 3, 12, 24, |cpu| call_if(cpu, Flag::C, true));
+    table[0xDE] = op("SBC A,u8", 2, 8, "Z1HC", |cpu| sbc_rv(cpu, RegisterName::A));
+    table[0xDF] = op("RST 18H", 1, 16, "----", |cpu| rst(cpu, 0x0018));
+
+    table[0xE0] = op("LD (FF00+u8),A", 2, 12, "----", ld_ff00_u8_a);
+    table[0xE1] = op("POP HL", 1, 12, "----", |cpu| pop_r(cpu, RegisterName::HL));
+    table[0xE2] = op("LD (FF00+C),A", 1, 8, "----", ld_ff00_c_a);
+    table[0xE5] = op("PUSH HL", 1, 16, "----", |cpu| push_r(cpu, RegisterName::HL));
+    table[0xE6] = op("AND A,u8", 2, 8, "Z010", |cpu| and_rv(cpu, RegisterName::A));
+    table[0xE7] = op("RST 20H", 1, 16, "----", |cpu| rst(cpu, 0x0020));
+    table[0xE8] = op("ADD SP,i8", 2, 16, "00HC", add_sp_i8);
+    table[0xE9] = op("JP HL", 1, 4, "----", |cpu| jp_r(cpu, RegisterName::HL));
+    table[0xEA] = op("LD (u16),A", 3, 16, "----", |cpu| ld_mvr(cpu, RegisterName::A));
+    table[0xEE] = op("XOR A,u8", 2, 8, "Z000", |cpu| xor_rv(cpu, RegisterName::A));
+    table[0xEF] = op("RST 28H", 1, 16, "----", |cpu| rst(cpu, 0x0028));
+
+    table[0xF0] = op("LD A,(FF00+u8)", 2, 12, "----", ld_a_ff00_plus_u8);
+    table[0xF1] = op("POP AF", 1, 12, "ZNHC", |cpu| pop_r(cpu, RegisterName::AF));
+    table[0xF2] = op("LD A,(FF00+C)", 1, 8, "----", ld_a_ff00_c);
+    table[0xF3] = op("DI", 1, 4, "----", di);
+    table[0xF5] = op("PUSH AF", 1, 16, "----", |cpu| push_r(cpu, RegisterName::AF));
+    table[0xF6] = op("OR A,u8", 2, 8, "Z000", |cpu| or_rv(cpu, RegisterName::A));
+    table[0xF7] = op("RST 30H", 1, 16, "----", |cpu| rst(cpu, 0x0030));
+    table[0xF8] = op("LD HL,SP+i8", 2, 12, "00HC", ld_hl_spi8);
+    table[0xF9] = op("LD SP,HL", 1, 8, "----", |cpu| ld_rr(cpu, RegisterName::SP, RegisterName::HL));
+    table[0xFA] = op("LD A,(u16)", 3, 16, "----", |cpu| ld_rmv(cpu, RegisterName::A));
+    table[0xFB] = op("EI", 1, 4, "----", ei);
+    table[0xFE] = op("CP A,u8", 2, 8, "Z1HC", |cpu| cp_rv(cpu, RegisterName::A));
+    table[0xFF] = op("RST 38H", 1, 16, "----", |cpu| rst(cpu, 0x0038));
+
+    // 0xCB is the prefix, execute decodes the next byte with CB_INSTRUCTIONS
+    table[0xCB] = Instruction { mnemonic: "PREFIX CB", ..ILLEGAL };
+    table
+}
+
+// Formats the instruction starting with bytes[0], returns it with its length
+pub fn disassemble(bytes: &[u8]) -> (String, u8) {
+    let byte = |index: usize| bytes.get(index).copied().unwrap_or(0);
+    if byte(0) == 0xCB {
+        return (CB_INSTRUCTIONS[byte(1) as usize].mnemonic.to_string(), 2);
+    }
+    let instruction = &INSTRUCTIONS[byte(0) as usize];
+    let word = (byte(2) as u16) << 8 | byte(1) as u16;
+    let text = instruction.mnemonic
+        .replace("u16", &format!("${:04X}", word))
+        .replace("u8", &format!("${:02X}", byte(1)))
+        .replace("i8", &format!("{}", byte(1) as i8));
+    (text, instruction.length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cpu::ILLEGAL_OPCODES;
+
+    #[test]
+    fn test_only_illegal_opcodes_are_missing() {
+        for (opcode, instruction) in INSTRUCTIONS.iter().enumerate() {
+            let missing = opcode == 0xCB || ILLEGAL_OPCODES.contains(&(opcode as u8));
+            assert_eq!(instruction.handler.is_none(), missing, "{:02X}", opcode);
+        }
+        assert!(CB_INSTRUCTIONS.iter().all(|instruction| instruction.handler.is_some()));
+    }
+
+    #[test]
+    fn test_disassemble() {
+        assert_eq!(disassemble(&[0x00]), ("NOP".to_string(), 1));
+        assert_eq!(disassemble(&[0x3E, 0x12]), ("LD A,$12".to_string(), 2));
+        assert_eq!(disassemble(&[0xC3, 0x50, 0x01]), ("JP $0150".to_string(), 3));
+        assert_eq!(disassemble(&[0x20, 0xFE]), ("JR NZ,-2".to_string(), 2));
+        assert_eq!(disassemble(&[0xE0, 0x40]), ("LD (FF00+$40),A".to_string(), 2));
+        assert_eq!(disassemble(&[0xCB, 0x7C]), ("BIT 7,H".to_string(), 2));
+        assert_eq!(disassemble(&[0xD3]), ("ILLEGAL".to_string(), 1));
+    }
+}
@@ -1,10 +1,10 @@
-use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::io::Write;
-use crate::bus::{Bus, LCDSTAT_VECTOR, TIMER_VECTOR, VBLANK_VECTOR};
-use crate::cpu::cb_instructions::CBOpCodeHandler;
-use crate::cpu::instructions::OpCodeHandler;
+use crate::bus::Bus;
+use crate::cart::header::CartridgeError;
+use crate::error::EmulatorError;
 use crate::cpu::registers::Registers;
+use crate::gpu::Renderer;
 
 mod registers;
 mod function;
@@ -12,37 +12,95 @@ mod cb_function;
 mod instructions;
 mod cb_instructions;
 
+pub use self::cb_instructions::CB_INSTRUCTIONS;
+pub use self::instructions::{disassemble, Instruction, OpCodeHandler, INSTRUCTIONS};
+
+// Opcodes that don't exist on the DMG, executing one freezes the CPU for good
+const ILLEGAL_OPCODES: [u8; 11] = [0xD3, 0xDB, 0xDD, 0xE3, 0xE4, 0xEB, 0xEC, 0xED, 0xF4, 0xFC, 0xFD];
+
+// With instruction timing the rest of the machine catches up once the whole
+// instruction ran, which is fast but means the timer, PPU and DMA only see its memory
+// accesses afterwards. M-cycle timing advances them on every read, write and internal
+// delay as it happens, for code that depends on exact access timing.
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum Timing {
+    #[default]
+    Instruction,
+    MCycle,
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct CPU {
     pub registers: Registers,
     pub bus: Bus,
     pub is_halted: bool,
-    cb_opcode_handler: HashMap<u8, CBOpCodeHandler>,
-    opcode_handler: HashMap<u8, OpCodeHandler>,
+    pub is_stopped: bool,
+    pub is_locked_up: bool,
+    halt_bug: bool,
     interrupt_enabled: bool,
+    enable_interrupts_next: bool, // set by EI
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    pub timing: Timing,
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    ticked: u8, // cycles the bus already ran during the current step
+    #[cfg_attr(feature = "serialize", serde(skip))]
     log_buffer: Vec<String>,
+    #[cfg_attr(feature = "serialize", serde(skip))]
     log_index: usize,
 }
 
 impl CPU {
-    pub fn new(data: &[u8]) -> CPU {
+    pub fn new(data: &[u8]) -> Result<CPU, CartridgeError> {
+        CPU::with_renderer(data, Renderer::Scanline)
+    }
+
+    pub fn with_renderer(data: &[u8], renderer: Renderer) -> Result<CPU, CartridgeError> {
         let registers: Registers = Registers::new();
-        let bus: Bus = Bus::new(data);
+        let bus: Bus = Bus::with_renderer(data, renderer)?;
         init_log();
-        CPU {
+        Ok(CPU {
             registers: registers,
             bus: bus,
             log_buffer: Vec::new(),
             is_halted: false,
-            cb_opcode_handler: cb_instructions::init_cb_opcode_handlers(),
-            opcode_handler: instructions::init_opcode_handlers(),
+            is_stopped: false,
+            is_locked_up: false,
+            halt_bug: false,
             interrupt_enabled: true,
+            enable_interrupts_next: false,
+            timing: Timing::Instruction,
+            ticked: 0,
             log_index: 0,
+        })
+    }
+
+    // Runs the rest of the machine for one M-cycle right away with M-cycle timing,
+    // otherwise step does it once the instruction is done
+    fn tick(&mut self) {
+        if self.timing == Timing::MCycle {
+            self.bus.step(4);
+            self.ticked += 4;
         }
     }
 
+    pub fn read(&mut self, address: u16) -> u8 {
+        self.tick();
+        self.bus.bus_read(address)
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        self.tick();
+        self.bus.bus_write(address, value);
+    }
+
     pub fn fetch_byte(&mut self) -> u8 {
-        let value = self.bus.bus_read(self.registers.pc);
-        self.registers.pc += 1;
+        let value = self.read(self.registers.pc);
+        if self.halt_bug {
+            // The byte after HALT gets read twice
+            self.halt_bug = false;
+        } else {
+            self.registers.pc += 1;
+        }
         value
     }
 
@@ -52,80 +110,130 @@ impl CPU {
         (msb << 8) | lsb
     }
 
-    pub fn step(&mut self) -> u8 {
+    pub fn step(&mut self) -> Result<u8, EmulatorError> {
         /*self.log_to_buffer();
         self.log_index += 1;
         if self.log_buffer.len() > self.flush_frequency_based_on_index() {
             self.flush_log_buffer();
         }*/
-        let mut instruction_byte = self.fetch_byte();
-
-        let mut cycles: u8 = 0;
+        if self.is_locked_up {
+            // Not even an interrupt gets the CPU out of this, the rest of the machine
+            // keeps running though
+            self.bus.step(4);
+            return Ok(4);
+        }
 
-        if instruction_byte == 0xCB {
-            instruction_byte = self.fetch_byte();
-            if let Some(handler) = self.cb_opcode_handler.get(&instruction_byte) {
-                handler(self);
-                cycles = 8;
-            } else {
-                panic!("Prefixed opcode not implemented: {:02X}", instruction_byte);
-            }
-        } else {
-            if let Some(handler) = self.opcode_handler.get(&instruction_byte) {
-                cycles = handler(self);
-            } else {
-                panic!("Opcode not implemented: {:02X}", instruction_byte);
+        if self.is_stopped {
+            // The CPU, LCD and timers are all frozen until a button is pressed
+            if self.bus.io.joypad.any_line_low() {
+                self.is_stopped = false;
             }
+            return Ok(4);
         }
 
-        self.bus.step(cycles);
+        // EI takes effect once the instruction after it is done, which is the one
+        // about to run
+        if self.enable_interrupts_next {
+            self.enable_interrupts_next = false;
+            self.interrupt_enabled = true;
+        }
+
+        self.ticked = 0;
+        let mut cycles = if self.is_halted {
+            // Nothing is fetched while halted but time keeps going
+            4
+        } else {
+            self.execute()?
+        };
+
+        // Whatever the instruction didn't already run, everything with instruction
+        // timing, only the halted M-cycle with M-cycle timing
+        self.bus.step(cycles.saturating_sub(self.ticked));
+        cycles = cycles.max(self.ticked);
 
-        if self.bus.has_interrupt() {
+        // Any pending interrupt ends HALT, even with interrupts disabled
+        if self.is_halted && self.bus.has_interrupt() {
             self.is_halted = false;
         }
-        if !self.is_halted {
 
+        // Only one interrupt is taken, the next one waits for the handler's first
+        // instruction
+        if self.interrupt_enabled && self.bus.has_interrupt() {
+            cycles += self.interrupt();
         }
 
-        let mut interrupted = false;
-        if self.interrupt_enabled {
-            if self.bus.io.interrupt_enable.vblank && self.bus.io.interrupt_flag.vblank {
-                interrupted = true;
-                self.bus.io.interrupt_flag.vblank = false;
-                self.interrupt(VBLANK_VECTOR)
-            }
-            if self.bus.io.interrupt_enable.lcdstat && self.bus.io.interrupt_flag.lcdstat {
-                interrupted = true;
-                self.bus.io.interrupt_flag.lcdstat = false;
-                self.interrupt(LCDSTAT_VECTOR)
+        Ok(cycles)
+    }
+
+    // Fetches and runs one instruction, returns the cycles it took
+    fn execute(&mut self) -> Result<u8, EmulatorError> {
+        let address = self.registers.pc;
+        let mut instruction_byte = self.fetch_byte();
+
+        let cycles;
+
+        if instruction_byte == 0xCB {
+            instruction_byte = self.fetch_byte();
+            let instruction = &CB_INSTRUCTIONS[instruction_byte as usize];
+            if let Some(handler) = instruction.handler {
+                handler(self);
+                cycles = instruction.cycles;
+            } else {
+                return Err(EmulatorError::UnimplementedPrefixedOpcode { opcode: instruction_byte, address });
             }
-            if self.bus.io.interrupt_enable.timer && self.bus.io.interrupt_flag.timer {
-                interrupted = true;
-                self.bus.io.interrupt_flag.timer = false;
-                self.interrupt(TIMER_VECTOR)
+        } else if ILLEGAL_OPCODES.contains(&instruction_byte) {
+            self.is_locked_up = true;
+            cycles = 4;
+        } else {
+            let instruction = &INSTRUCTIONS[instruction_byte as usize];
+            match instruction.handler {
+                Some(OpCodeHandler::Op(handler)) => {
+                    handler(self);
+                    cycles = instruction.cycles;
+                }
+                Some(OpCodeHandler::Branch(handler)) => {
+                    cycles = if handler(self) { instruction.branch_cycles } else { instruction.cycles };
+                }
+                None => return Err(EmulatorError::UnimplementedOpcode { opcode: instruction_byte, address }),
             }
         }
-        if interrupted {
-            cycles += 12;
-        }
-
-        cycles
+        Ok(cycles)
     }
 
-    fn interrupt(&mut self, location: u16) {
+    // Dispatches the highest priority pending interrupt, takes 5 M-cycles: 2 waiting,
+    // 2 pushing PC and 1 jumping
+    fn interrupt(&mut self) -> u8 {
         self.interrupt_enabled = false;
-        self.push_stack_word(self.registers.pc);
-        self.registers.pc = location;
-        self.bus.step(12);
+        self.ticked = 0;
+        self.tick();
+        self.tick();
+
+        let pc = self.registers.pc;
+        self.push_stack((pc >> 8) as u8);
+        // The vector is only picked after the high byte is pushed. If that push
+        // overwrote IE (SP was 0x0000) and nothing is left pending, PC ends up at 0x0000.
+        let vector = self.bus.pending_interrupt();
+        self.push_stack(pc as u8);
+
+        match vector {
+            Some(vector) => {
+                self.bus.acknowledge_interrupt(vector);
+                self.registers.pc = vector;
+            }
+            None => self.registers.pc = 0x0000,
+        }
+        self.tick();
+        self.bus.step(20 - self.ticked);
+        20
     }
 
     pub fn push_stack(&mut self, value: u8) {
-        self.registers.sp -= 1;  // Decrement Stack Pointer
-        self.bus.bus_write(self.registers.sp, value);
+        self.registers.sp = self.registers.sp.wrapping_sub(1);  // Decrement Stack Pointer
+        self.write(self.registers.sp, value);
     }
 
     pub fn pop_stack(&mut self) -> u8 {
-        let value = self.bus.bus_read(self.registers.sp);
+        let value = self.read(self.registers.sp);
         self.registers.sp += 1;  // Increment Stack Pointer
         value
     }
@@ -190,4 +298,326 @@ impl CPU {
 
 pub fn init_log() {
     File::create("log.txt").expect("Failed to create or clear log.txt");
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bus::{JOYPAD_VECTOR, SERIAL_VECTOR, VBLANK_VECTOR};
+    use crate::cart::header::test_rom;
+
+    // Runs the program from WRAM
+    fn cpu_with_program(program: &[u8]) -> CPU {
+        let mut cpu = CPU::new(&test_rom(0x00, 0x00)).unwrap();
+        for (offset, byte) in program.iter().enumerate() {
+            cpu.bus.bus_write(0xC000 + offset as u16, *byte);
+        }
+        cpu.registers.pc = 0xC000;
+        cpu
+    }
+
+    #[test]
+    fn test_illegal_opcode_locks_up() {
+        let mut cpu = cpu_with_program(&[0x00, 0xDD, 0x00]);
+        cpu.step().unwrap();
+        assert_eq!(cpu.step(), Ok(4));
+        assert!(cpu.is_locked_up);
+
+        // Interrupts don't wake it up
+        cpu.bus.io.interrupt_enable.vblank = true;
+        cpu.bus.io.interrupt_flag.vblank = true;
+        for _ in 0..10 {
+            assert_eq!(cpu.step(), Ok(4));
+        }
+        assert_eq!(cpu.registers.pc, 0xC002);
+    }
+
+    #[test]
+    fn test_halt_waits_for_an_interrupt() {
+        // HALT, INC A
+        let mut cpu = cpu_with_program(&[0x76, 0x3C]);
+        cpu.registers.a = 0;
+        for _ in 0..10 {
+            cpu.step().unwrap();
+        }
+        assert!(cpu.is_halted);
+        assert_eq!(cpu.registers.pc, 0xC001);
+
+        // Wakes up even with IME off, without jumping to the handler
+        cpu.interrupt_enabled = false;
+        cpu.bus.io.interrupt_enable.timer = true;
+        cpu.bus.io.interrupt_flag.timer = true;
+        cpu.step().unwrap();
+        assert!(!cpu.is_halted);
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.a, 1);
+        assert_eq!(cpu.registers.pc, 0xC002);
+    }
+
+    #[test]
+    fn test_halt_services_interrupt_with_ime() {
+        let mut cpu = cpu_with_program(&[0x76, 0x00]);
+        cpu.interrupt_enabled = true;
+        cpu.step().unwrap();
+        cpu.bus.io.interrupt_enable.vblank = true;
+        cpu.bus.io.interrupt_flag.vblank = true;
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, VBLANK_VECTOR);
+        assert_eq!(cpu.pop_stack_word(), 0xC001);
+    }
+
+    #[test]
+    fn test_halt_bug() {
+        // HALT, INC A, NOP: INC A runs twice
+        let mut cpu = cpu_with_program(&[0x76, 0x3C, 0x00]);
+        cpu.registers.a = 0;
+        cpu.interrupt_enabled = false;
+        cpu.bus.io.interrupt_enable.timer = true;
+        cpu.bus.io.interrupt_flag.timer = true;
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+        assert!(!cpu.is_halted);
+        assert_eq!(cpu.registers.a, 2);
+        assert_eq!(cpu.registers.pc, 0xC002);
+    }
+
+    #[test]
+    fn test_stop_waits_for_a_button() {
+        // STOP, (skipped), INC A
+        let mut cpu = cpu_with_program(&[0x10, 0x3C, 0x3C]);
+        cpu.registers.a = 0;
+        cpu.bus.step(200);
+        cpu.step().unwrap();
+        assert!(cpu.is_stopped);
+        assert_eq!(cpu.registers.pc, 0xC002);
+        assert_eq!(cpu.bus.bus_read(0xFF04), 0);
+        let counter = cpu.bus.io.timer.system_counter();
+        for _ in 0..100 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.bus.io.timer.system_counter(), counter);
+        assert_eq!(cpu.registers.pc, 0xC002);
+
+        cpu.bus.io.joypad.right = true;
+        cpu.step().unwrap();
+        assert!(!cpu.is_stopped);
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.a, 1);
+    }
+
+    #[test]
+    fn test_stop_with_button_held() {
+        let mut cpu = cpu_with_program(&[0x10, 0x00, 0x00]);
+        cpu.bus.io.joypad.right = true;
+        cpu.bus.step(200);
+        let counter = cpu.bus.io.timer.system_counter();
+        cpu.step().unwrap();
+        // Halts instead, DIV keeps going
+        assert!(!cpu.is_stopped);
+        assert!(cpu.is_halted);
+        assert_eq!(cpu.registers.pc, 0xC002);
+        assert!(cpu.bus.io.timer.system_counter() > counter);
+    }
+
+    #[test]
+    fn test_stop_with_interrupt_pending() {
+        let mut cpu = cpu_with_program(&[0x10, 0x00, 0x00]);
+        cpu.interrupt_enabled = false;
+        cpu.bus.io.interrupt_enable.timer = true;
+        cpu.bus.io.interrupt_flag.timer = true;
+        cpu.step().unwrap();
+        // Only one byte long this time
+        assert!(cpu.is_stopped);
+        assert_eq!(cpu.registers.pc, 0xC001);
+    }
+
+    #[test]
+    fn test_one_interrupt_per_dispatch() {
+        let mut cpu = cpu_with_program(&[0x00]);
+        cpu.registers.sp = 0xD000;
+        cpu.bus.bus_write(0xFFFF, 0x1F);
+        // VBlank, timer and joypad all pending
+        cpu.bus.bus_write(0xFF0F, 0x15);
+        assert_eq!(cpu.step(), Ok(24));
+        assert_eq!(cpu.registers.pc, VBLANK_VECTOR);
+        assert_eq!(cpu.registers.sp, 0xCFFE);
+        assert_eq!(cpu.bus.bus_read(0xFF0F) & 0x14, 0x14);
+    }
+
+    #[test]
+    fn test_serial_and_joypad_vectors() {
+        let mut cpu = cpu_with_program(&[0x00]);
+        cpu.registers.sp = 0xD000;
+        cpu.bus.bus_write(0xFFFF, 0x18);
+        cpu.bus.bus_write(0xFF0F, 0x18);
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, SERIAL_VECTOR);
+
+        cpu.interrupt_enabled = true;
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, JOYPAD_VECTOR);
+        assert_eq!(cpu.bus.bus_read(0xFF0F) & 0x1F, 0x00);
+    }
+
+    #[test]
+    fn test_ei_delay() {
+        // EI, INC A, INC A
+        let mut cpu = cpu_with_program(&[0xFB, 0x3C, 0x3C]);
+        cpu.registers.sp = 0xD000;
+        cpu.registers.a = 0;
+        cpu.interrupt_enabled = false;
+        cpu.bus.io.interrupt_enable.vblank = true;
+        cpu.bus.io.interrupt_flag.vblank = true;
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0xC001);
+        // The instruction after EI still runs before the interrupt
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.a, 1);
+        assert_eq!(cpu.registers.pc, VBLANK_VECTOR);
+        assert_eq!(cpu.pop_stack_word(), 0xC002);
+    }
+
+    #[test]
+    fn test_di_right_after_ei() {
+        // EI, DI, NOP
+        let mut cpu = cpu_with_program(&[0xFB, 0xF3, 0x00]);
+        cpu.interrupt_enabled = false;
+        cpu.bus.io.interrupt_enable.vblank = true;
+        cpu.bus.io.interrupt_flag.vblank = true;
+        for _ in 0..3 {
+            cpu.step().unwrap();
+        }
+        assert_eq!(cpu.registers.pc, 0xC003);
+    }
+
+    #[test]
+    fn test_ie_push_cancels_interrupt() {
+        let mut cpu = cpu_with_program(&[0x00]);
+        // Pushing PC's high byte (0xC0) writes IE and disables VBlank
+        cpu.registers.sp = 0x0000;
+        cpu.bus.bus_write(0xFFFF, 0x01);
+        cpu.bus.bus_write(0xFF0F, 0x01);
+        cpu.step().unwrap();
+        assert_eq!(cpu.registers.pc, 0x0000);
+        assert_eq!(cpu.registers.sp, 0xFFFE);
+        assert!(cpu.bus.io.interrupt_flag.vblank);
+    }
+
+    #[test]
+    fn test_m_cycle_timing() {
+        // LD A,(0xFF05): TIMA is read on the 4th M-cycle
+        for (timing, tima) in [(Timing::Instruction, 0), (Timing::MCycle, 1)] {
+            let mut cpu = cpu_with_program(&[0xFA, 0x05, 0xFF]);
+            cpu.timing = timing;
+            // TIMA goes up every 16 cycles
+            cpu.bus.bus_write(0xFF07, 0x05);
+            cpu.bus.bus_write(0xFF04, 0x00);
+            cpu.bus.bus_write(0xFF05, 0x00);
+            assert_eq!(cpu.step(), Ok(16));
+            assert_eq!(cpu.registers.a, tima);
+            assert_eq!(cpu.bus.io.timer.system_counter(), 16);
+        }
+    }
+
+    #[test]
+    fn test_m_cycle_timing_runs_every_cycle_once() {
+        // CALL, PUSH BC, RET, POP BC
+        let program = [0xCD, 0x05, 0xC0, 0x00, 0x00, 0xC5, 0xC9, 0xC1];
+        let mut instruction = cpu_with_program(&program);
+        let mut m_cycle = cpu_with_program(&program);
+        m_cycle.timing = Timing::MCycle;
+        for cpu in [&mut instruction, &mut m_cycle] {
+            cpu.registers.sp = 0xD000;
+            cpu.registers.set_bc(0x1234);
+        }
+        for _ in 0..4 {
+            assert_eq!(instruction.step(), m_cycle.step());
+            assert_eq!(instruction.registers.pc, m_cycle.registers.pc);
+            assert_eq!(instruction.bus.io.timer.system_counter(), m_cycle.bus.io.timer.system_counter());
+        }
+    }
+
+    // Reference timings in M-cycles, 0 for illegal opcodes and the CB prefix
+    const CYCLES: [u8; 256] = [
+        1, 3, 2, 2, 1, 1, 2, 1, 5, 2, 2, 2, 1, 1, 2, 1,
+        1, 3, 2, 2, 1, 1, 2, 1, 3, 2, 2, 2, 1, 1, 2, 1,
+        2, 3, 2, 2, 1, 1, 2, 1, 2, 2, 2, 2, 1, 1, 2, 1,
+        2, 3, 2, 2, 3, 3, 3, 1, 2, 2, 2, 2, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        2, 2, 2, 2, 2, 2, 1, 2, 1, 1, 1, 1, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        2, 3, 3, 4, 3, 4, 2, 4, 2, 4, 3, 0, 3, 6, 2, 4,
+        2, 3, 3, 0, 3, 4, 2, 4, 2, 4, 3, 0, 3, 0, 2, 4,
+        3, 3, 2, 0, 0, 4, 2, 4, 4, 1, 4, 0, 0, 0, 2, 4,
+        3, 3, 2, 1, 0, 4, 2, 4, 3, 2, 4, 1, 0, 0, 2, 4,
+    ];
+
+    // Same when the condition holds, only differs for conditional branches
+    fn taken_cycles(opcode: u8) -> u8 {
+        match opcode {
+            0x20 | 0x28 | 0x30 | 0x38 => 3,
+            0xC2 | 0xCA | 0xD2 | 0xDA => 4,
+            0xC4 | 0xCC | 0xD4 | 0xDC => 6,
+            0xC0 | 0xC8 | 0xD0 | 0xD8 => 5,
+            _ => CYCLES[opcode as usize],
+        }
+    }
+
+    fn cb_cycles(opcode: u8) -> u8 {
+        match (opcode >> 6, opcode & 0x07) {
+            (_, 0x06) if opcode >> 6 == 1 => 3, // BIT n,(HL)
+            (_, 0x06) => 4,
+            _ => 2,
+        }
+    }
+
+    // Runs one instruction with its operands pointing at WRAM, returns the cycles it took
+    fn run_instruction(program: &[u8], flags: u8, timing: Timing) -> u8 {
+        let mut cpu = cpu_with_program(program);
+        cpu.timing = timing;
+        cpu.registers.sp = 0xD000;
+        cpu.registers.set_bc(0xC900);
+        cpu.registers.set_de(0xCA00);
+        cpu.registers.set_hl(0xC800);
+        cpu.registers.f = flags;
+        let cycles = cpu.step().unwrap();
+        if timing == Timing::MCycle {
+            // Every M-cycle runs where it happens, none is made up for at the end
+            assert_eq!(cpu.ticked, cycles, "{:02X?}", program);
+        }
+        cycles
+    }
+
+    #[test]
+    fn test_cycle_counts() {
+        for timing in [Timing::Instruction, Timing::MCycle] {
+            for opcode in 0..=0xFF {
+                if CYCLES[opcode as usize] == 0 {
+                    continue;
+                }
+                let instruction = &INSTRUCTIONS[opcode as usize];
+                assert_eq!(instruction.cycles, CYCLES[opcode as usize] * 4, "{:02X}", opcode);
+                assert_eq!(instruction.branch_cycles, taken_cycles(opcode) * 4, "{:02X}", opcode);
+
+                // Every condition is taken with one of these and not the other
+                let mut cycles = [
+                    run_instruction(&[opcode, 0x00, 0xC1], 0x00, timing),
+                    run_instruction(&[opcode, 0x00, 0xC1], 0xF0, timing),
+                ];
+                cycles.sort();
+                assert_eq!(cycles, [CYCLES[opcode as usize] * 4, taken_cycles(opcode) * 4], "{:02X} {:?}", opcode, timing);
+            }
+
+            for opcode in 0..=0xFF {
+                assert_eq!(CB_INSTRUCTIONS[opcode as usize].cycles, cb_cycles(opcode) * 4, "CB {:02X}", opcode);
+                assert_eq!(run_instruction(&[0xCB, opcode], 0x00, timing), cb_cycles(opcode) * 4, "CB {:02X} {:?}", opcode, timing);
+            }
+        }
+    }
+}
@@ -35,6 +35,7 @@ impl RegisterName {
     }
 }
 
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Registers {
     pub a: u8,
     pub b: u8,
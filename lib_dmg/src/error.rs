@@ -0,0 +1,30 @@
+use std::fmt;
+use crate::cart::header::CartridgeError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmulatorError {
+    Cartridge(CartridgeError),
+    UnimplementedOpcode { opcode: u8, address: u16 },
+    UnimplementedPrefixedOpcode { opcode: u8, address: u16 },
+}
+
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmulatorError::Cartridge(error) =>
+                write!(f, "invalid cartridge: {}", error),
+            EmulatorError::UnimplementedOpcode { opcode, address } =>
+                write!(f, "opcode {:02X} at {:04X} is not implemented", opcode, address),
+            EmulatorError::UnimplementedPrefixedOpcode { opcode, address } =>
+                write!(f, "opcode CB {:02X} at {:04X} is not implemented", opcode, address),
+        }
+    }
+}
+
+impl std::error::Error for EmulatorError {}
+
+impl From<CartridgeError> for EmulatorError {
+    fn from(error: CartridgeError) -> Self {
+        EmulatorError::Cartridge(error)
+    }
+}
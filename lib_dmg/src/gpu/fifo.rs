@@ -0,0 +1,413 @@
+use std::collections::VecDeque;
+use super::*;
+
+const OAM_SCAN_DOTS: u16 = 80;
+const DOTS_PER_LINE: u16 = 456;
+// The first tile fetched on every line is thrown away
+const LINE_START_DOTS: u8 = 6;
+// Simplified, real hardware also waits for the background fetcher and takes 6 to 11 dots
+const OBJECT_FETCH_DOTS: u8 = 6;
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum FetcherStep {
+    Tile,
+    DataLow,
+    DataHigh,
+    Push,
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct ObjectPixel {
+    value: TilePixelValue,
+    palette: ObjectPalette,
+    priority: bool,
+}
+
+// State of mode 3 when the pixel FIFO renderer is used. The background fetcher reads
+// one tile row every 6 dots and pushes it once the background FIFO is empty, and one
+// pixel is shifted out to the LCD every dot. Registers are read when they are used, so
+// writes made in the middle of a line show up from that point on.
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct PixelFifo {
+    background: VecDeque<TilePixelValue>,
+    objects: VecDeque<ObjectPixel>,
+    step: FetcherStep,
+    step_dots: u8,
+    tile_x: u8,       // column of the next tile to fetch, relative to SCX or to the window
+    tile_number: u8,
+    row: TileRow,
+    x: usize,         // next pixel of the line to be drawn
+    discard: u8,      // pixels dropped from the FIFO for the SCX fine scroll
+    stall: u8,        // dots where nothing is drawn
+    window_active: bool,
+    line_objects: VecDeque<ObjectData>, // not fetched yet, in drawing priority order
+}
+
+impl PixelFifo {
+    pub fn new() -> PixelFifo {
+        PixelFifo {
+            background: VecDeque::with_capacity(8),
+            objects: VecDeque::with_capacity(8),
+            step: FetcherStep::Tile,
+            step_dots: 0,
+            tile_x: 0,
+            tile_number: 0,
+            row: [Default::default(); 8],
+            x: 0,
+            discard: 0,
+            stall: 0,
+            window_active: false,
+            line_objects: VecDeque::new(),
+        }
+    }
+}
+
+impl GPU {
+    pub(super) fn step_pixel_fifo(&mut self, cycles: u8) -> InterruptRequest {
+        let mut request = InterruptRequest::None;
+        if !self.lcd_display_enabled {
+            return request;
+        }
+
+        // Here cycles counts the dots since the start of the line
+        for _ in 0..cycles {
+            self.cycles += 1;
+            match self.mode {
+                Mode::OAMAccess => {
+                    if self.cycles >= OAM_SCAN_DOTS {
+                        self.mode = Mode::VRAMAccess;
+                        self.start_pixel_transfer();
+                    }
+                }
+                Mode::VRAMAccess => {
+                    if self.pixel_transfer_dot() {
+                        if self.pixel_fifo.window_active {
+                            self.window.line += 1;
+                        }
+                        self.mode = Mode::HorizontalBlank;
+                    }
+                }
+                Mode::HorizontalBlank => {
+                    if self.cycles >= DOTS_PER_LINE {
+                        self.cycles = 0;
+                        self.end_horizontal_blank(&mut request);
+                    }
+                }
+                Mode::VerticalBlank => {
+                    self.check_line_153();
+                    if self.cycles >= DOTS_PER_LINE {
+                        self.cycles = 0;
+                        self.end_vertical_blank_line();
+                    }
+                }
+            }
+            if self.update_stat_line() {
+                request.add(InterruptRequest::LCDStat)
+            }
+        }
+        request
+    }
+
+    fn start_pixel_transfer(&mut self) {
+        if self.line == self.window.y {
+            self.window.y_triggered = true;
+        }
+        let line_objects = self.objects_on_line()
+            .into_iter()
+            .filter(|object| object.x > -8 && object.x < SCREEN_WIDTH as i16)
+            .collect();
+
+        let fifo = &mut self.pixel_fifo;
+        fifo.background.clear();
+        fifo.objects.clear();
+        fifo.step = FetcherStep::Tile;
+        fifo.step_dots = 0;
+        fifo.tile_x = 0;
+        fifo.x = 0;
+        fifo.discard = self.viewport_x_offset % 8;
+        fifo.stall = LINE_START_DOTS;
+        fifo.window_active = false;
+        fifo.line_objects = line_objects;
+    }
+
+    // Returns true once the last pixel of the line has been drawn
+    fn pixel_transfer_dot(&mut self) -> bool {
+        if self.pixel_fifo.x >= SCREEN_WIDTH {
+            return true;
+        }
+        if self.pixel_fifo.stall > 0 {
+            self.pixel_fifo.stall -= 1;
+            return false;
+        }
+
+        if self.window_starts_here() {
+            // The window restarts the fetcher from its own tile map, whatever was in the
+            // background FIFO is lost
+            let fifo = &mut self.pixel_fifo;
+            fifo.window_active = true;
+            fifo.background.clear();
+            fifo.step = FetcherStep::Tile;
+            fifo.step_dots = 0;
+            fifo.tile_x = 0;
+            fifo.discard = 7u8.saturating_sub(self.window.x);
+        }
+
+        if self.object_starts_here() {
+            let object = self.pixel_fifo.line_objects.pop_front().unwrap();
+            self.fetch_object(&object);
+            self.pixel_fifo.stall = OBJECT_FETCH_DOTS - 1;
+            return false;
+        }
+
+        self.fetcher_dot();
+
+        let background = match self.pixel_fifo.background.pop_front() {
+            Some(pixel) => pixel,
+            None => return false,
+        };
+        if self.pixel_fifo.discard > 0 {
+            self.pixel_fifo.discard -= 1;
+            return false;
+        }
+        let object = self.pixel_fifo.objects.pop_front();
+        self.draw_fifo_pixel(background, object);
+
+        self.pixel_fifo.x += 1;
+        self.pixel_fifo.x >= SCREEN_WIDTH
+    }
+
+    fn window_starts_here(&self) -> bool {
+        !self.pixel_fifo.window_active
+            && self.window_display_enabled
+            && self.background_display_enabled
+            && self.window.y_triggered
+            && self.window.x <= 166
+            && self.pixel_fifo.x + 7 >= self.window.x as usize
+    }
+
+    // Objects are fetched once the background FIFO has pixels to mix them with.
+    // Ones partly off the left edge are fetched at the first pixel.
+    fn object_starts_here(&self) -> bool {
+        let fifo = &self.pixel_fifo;
+        if !self.object_display_enabled || fifo.background.is_empty() || fifo.discard > 0 {
+            return false;
+        }
+        match fifo.line_objects.front() {
+            Some(object) => object.x.max(0) as usize <= fifo.x,
+            None => false,
+        }
+    }
+
+    fn fetch_object(&mut self, object: &ObjectData) {
+        let object_height = self.object_height();
+        let line = self.line as i16;
+        let first_column = (-object.x).max(0);
+        for column in first_column..8 {
+            let pixel = ObjectPixel {
+                value: self.object_pixel(object, line - object.y, column, object_height),
+                palette: object.palette,
+                priority: object.priority,
+            };
+            // Objects are fetched in priority order, so whatever is already in the FIFO
+            // stays on top unless it is transparent
+            let index = (column - first_column) as usize;
+            match self.pixel_fifo.objects.get_mut(index) {
+                Some(existing) if existing.value == TilePixelValue::Zero => *existing = pixel,
+                Some(_) => {}
+                None => self.pixel_fifo.objects.push_back(pixel),
+            }
+        }
+    }
+
+    fn fetcher_dot(&mut self) {
+        let step = self.pixel_fifo.step;
+        if step == FetcherStep::Push {
+            let fifo = &mut self.pixel_fifo;
+            if fifo.background.is_empty() {
+                fifo.background.extend(fifo.row.iter());
+                fifo.tile_x = fifo.tile_x.wrapping_add(1);
+                fifo.step = FetcherStep::Tile;
+            }
+            return;
+        }
+
+        // Every other step takes two dots
+        self.pixel_fifo.step_dots += 1;
+        if self.pixel_fifo.step_dots < 2 {
+            return;
+        }
+        self.pixel_fifo.step_dots = 0;
+
+        match step {
+            FetcherStep::Tile => {
+                self.pixel_fifo.tile_number = self.vram[self.fetcher_tile_map_address()];
+                self.pixel_fifo.step = FetcherStep::DataLow;
+            }
+            FetcherStep::DataLow => self.pixel_fifo.step = FetcherStep::DataHigh,
+            FetcherStep::DataHigh => {
+                let row = if self.pixel_fifo.window_active {
+                    self.window.line
+                } else {
+                    self.line.wrapping_add(self.viewport_y_offset)
+                } as usize % 8;
+                let tile = self.tile_set_index(self.pixel_fifo.tile_number);
+                self.pixel_fifo.row = self.tile_set[tile][row];
+                self.pixel_fifo.step = FetcherStep::Push;
+            }
+            FetcherStep::Push => unreachable!(),
+        }
+    }
+
+    fn fetcher_tile_map_address(&self) -> usize {
+        let (tile_map, x, y) = if self.pixel_fifo.window_active {
+            (self.window_tile_map, self.pixel_fifo.tile_x, self.window.line)
+        } else {
+            (
+                self.background_tile_map,
+                (self.viewport_x_offset / 8).wrapping_add(self.pixel_fifo.tile_x),
+                self.line.wrapping_add(self.viewport_y_offset),
+            )
+        };
+        let tile_map_begin = if tile_map == TileMap::X9800 {
+            0x1800
+        } else {
+            0x1C00
+        };
+        tile_map_begin + (y as usize / 8) * 32 + (x as usize % 32)
+    }
+
+    fn draw_fifo_pixel(&mut self, background: TilePixelValue, object: Option<ObjectPixel>) {
+        // On DMG, LCDC bit 0 blanks the background and window to colour 0
+        let background = if self.background_display_enabled {
+            background
+        } else {
+            TilePixelValue::Zero
+        };
+        let color = match object {
+            Some(object)
+                if self.object_display_enabled
+                    && object.value != TilePixelValue::Zero
+                    && (object.priority || background == TilePixelValue::Zero) =>
+            {
+                self.tile_value_to_object_color(object.palette, &object.value)
+            }
+            _ => self.tile_value_to_background_color(&background),
+        };
+        self.set_pixel(self.pixel_fifo.x, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tile 1 is all colour 3, the background map is tile 0 apart from column 1
+    fn fifo_test_gpu(renderer: Renderer) -> GPU {
+        let mut gpu = GPU::with_renderer(renderer);
+        for index in 0..16 {
+            gpu.write_vram(0x10 + index, 0xFF);
+        }
+        gpu.write_vram(0x1801, 1);
+        gpu.gpu_write(0xFF47, 0xE4);
+        gpu.gpu_write(0xFF48, 0xE4);
+        gpu.gpu_write(0xFF40, 0x93); // LCD, 0x8000 data, objects, BG
+        gpu
+    }
+
+    fn place_object(gpu: &mut GPU, index: usize, x: i16, y: i16, tile: u8, flags: u8) {
+        gpu.write_oam(index * 4, (y + 16) as u8);
+        gpu.write_oam(index * 4 + 1, (x + 8) as u8);
+        gpu.write_oam(index * 4 + 2, tile);
+        gpu.write_oam(index * 4 + 3, flags);
+    }
+
+    // Runs until the next mode 3 and returns how many dots it lasted
+    fn mode_3_length(gpu: &mut GPU) -> u16 {
+        while gpu.mode != Mode::VRAMAccess {
+            gpu.step(1);
+        }
+        let mut dots = 0;
+        while gpu.mode == Mode::VRAMAccess {
+            gpu.step(1);
+            dots += 1;
+        }
+        dots
+    }
+
+    fn run_frame(gpu: &mut GPU) {
+        while gpu.mode != Mode::VerticalBlank {
+            gpu.step(4);
+        }
+        while gpu.mode == Mode::VerticalBlank {
+            gpu.step(4);
+        }
+        while gpu.mode != Mode::VerticalBlank {
+            gpu.step(4);
+        }
+    }
+
+    #[test]
+    fn test_mode_3_length() {
+        let mut gpu = fifo_test_gpu(Renderer::PixelFifo);
+        assert_eq!(mode_3_length(&mut gpu), 172);
+
+        gpu.gpu_write(0xFF43, 3);
+        assert_eq!(mode_3_length(&mut gpu), 175);
+
+        gpu.gpu_write(0xFF43, 0);
+        let line = gpu.line;
+        place_object(&mut gpu, 0, 40, line as i16 + 1, 1, 0);
+        place_object(&mut gpu, 1, 80, line as i16 + 1, 1, 0);
+        assert_eq!(mode_3_length(&mut gpu), 172 + 2 * OBJECT_FETCH_DOTS as u16);
+    }
+
+    #[test]
+    fn test_window_lengthens_mode_3() {
+        let mut gpu = fifo_test_gpu(Renderer::PixelFifo);
+        gpu.gpu_write(0xFF40, 0xB3);
+        gpu.gpu_write(0xFF4B, 87);
+        // The fetcher starts over from the window tile map
+        assert_eq!(mode_3_length(&mut gpu), 178);
+    }
+
+    #[test]
+    fn test_matches_scanline_renderer() {
+        let mut scanline = fifo_test_gpu(Renderer::Scanline);
+        let mut fifo = fifo_test_gpu(Renderer::PixelFifo);
+        for gpu in [&mut scanline, &mut fifo] {
+            gpu.gpu_write(0xFF40, 0xF3); // window at 0x9C00 as well
+            gpu.gpu_write(0xFF43, 5);
+            gpu.gpu_write(0xFF42, 3);
+            gpu.gpu_write(0xFF4A, 20);
+            gpu.gpu_write(0xFF4B, 60);
+            gpu.write_vram(0x1C02, 1);
+            place_object(gpu, 0, -3, 10, 1, 0x10);
+            place_object(gpu, 1, 30, 30, 1, 0x80);
+            place_object(gpu, 2, 34, 32, 1, 0);
+            run_frame(gpu);
+        }
+        assert!(scanline.canvas_buffer[..] == fifo.canvas_buffer[..]);
+    }
+
+    #[test]
+    fn test_mid_line_palette_change() {
+        let mut gpu = fifo_test_gpu(Renderer::PixelFifo);
+        gpu.gpu_write(0xFF47, 0x00);
+        while gpu.mode != Mode::VRAMAccess {
+            gpu.step(1);
+        }
+        let line = gpu.line as usize;
+        while gpu.pixel_fifo.x < 80 {
+            gpu.step(1);
+        }
+        gpu.gpu_write(0xFF47, 0xFF);
+        while gpu.mode == Mode::VRAMAccess {
+            gpu.step(1);
+        }
+        let pixel = |x: usize| gpu.canvas_buffer[(line * SCREEN_WIDTH + x) * 4];
+        assert_eq!(pixel(79), Color::White as u8);
+        assert_eq!(pixel(80), Color::Black as u8);
+    }
+}
@@ -1,10 +1,15 @@
 use std;
 use crate::utils::bit;
+use self::fifo::PixelFifo;
+
+mod fifo;
 
 
 const NUMBER_OF_OBJECTS: usize = 40;
+const MAX_OBJECTS_PER_LINE: usize = 10;
+const LINE_153_DOTS: u16 = 4;
 
-#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Color {
     White = 255,
@@ -15,17 +20,28 @@ pub enum Color {
 
 impl std::convert::From<u8> for Color {
     fn from(n: u8) -> Self {
-        match n {
+        // Only the two lowest bits are used
+        match n & 0b11 {
             0 => Color::White,
             1 => Color::LightGray,
             2 => Color::DarkGray,
-            3 => Color::Black,
-            _ => panic!("Cannot covert {} to color", n),
+            _ => Color::Black,
         }
     }
 }
 
-#[cfg_attr(feature = "serialize", derive(Serialize))]
+impl std::convert::From<Color> for u8 {
+    fn from(color: Color) -> Self {
+        match color {
+            Color::White => 0,
+            Color::LightGray => 1,
+            Color::DarkGray => 2,
+            Color::Black => 3,
+        }
+    }
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct BackgroundColors(Color, Color, Color, Color);
 
@@ -51,28 +67,34 @@ impl std::convert::From<u8> for BackgroundColors {
     }
 }
 
-#[cfg_attr(feature = "serialize", derive(Serialize))]
+impl std::convert::From<BackgroundColors> for u8 {
+    fn from(colors: BackgroundColors) -> Self {
+        u8::from(colors.3) << 6 | u8::from(colors.2) << 4 | u8::from(colors.1) << 2 | u8::from(colors.0)
+    }
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum TileMap {
     X9800,
     X9C00,
 }
 
-#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum BackgroundAndWindowDataSelect {
     X8000,
     X8800,
 }
 
-#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum ObjectSize {
     OS8X8,
     OS8X16,
 }
 
-#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Mode {
     HorizontalBlank,
@@ -92,6 +114,7 @@ impl std::convert::From<Mode> for u8 {
     }
 }
 
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum TilePixelValue {
     Zero,
@@ -114,6 +137,7 @@ fn empty_tile() -> Tile {
     [[Default::default(); 8]; 8]
 }
 
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct ObjectData {
     x: i16,
@@ -128,8 +152,8 @@ pub struct ObjectData {
 impl Default for ObjectData {
     fn default() -> Self {
         ObjectData {
-            x: -16,
-            y: -8,
+            x: -8,
+            y: -16,
             tile: Default::default(),
             palette: Default::default(),
             xflip: Default::default(),
@@ -139,6 +163,7 @@ impl Default for ObjectData {
     }
 }
 
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, PartialEq)]
 enum ObjectPalette {
     Zero,
@@ -151,6 +176,21 @@ impl Default for ObjectPalette {
     }
 }
 
+// The scanline renderer draws a whole line at once at the end of mode 3, which is fast
+// but ignores register writes made while the line is being drawn. The pixel FIFO one
+// runs the PPU fetchers dot by dot, for games relying on raster effects.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Renderer {
+    Scanline,
+    PixelFifo,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer::Scanline
+    }
+}
+
 #[derive(Eq, PartialEq)]
 pub enum InterruptRequest {
     None,
@@ -174,26 +214,70 @@ impl InterruptRequest {
     }
 }
 
-#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Window {
     pub x: u8,
     pub y: u8,
+    line: u8,               // internal line counter, only moves on lines the window was drawn on
+    y_triggered: bool,      // LY matched WY at some point during this frame
+    covers_next_line: bool, // WX=166 was used on the previous line
+}
+
+impl Window {
+    fn new() -> Window {
+        Window {
+            x: 0,
+            y: 0,
+            line: 0,
+            y_triggered: false,
+            covers_next_line: false,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.line = 0;
+        self.y_triggered = false;
+        self.covers_next_line = false;
+    }
+}
+
+// A VRAM/OAM access the PPU blocked, recorded in strict mode
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct IllegalAccess {
+    pub write: bool,
+    pub address: u16,
+    pub mode: Mode,
+    pub line: u8,
+}
+
+impl std::fmt::Display for IllegalAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Illegal {} {:04X} in mode {} (LY={})",
+            if self.write { "write to" } else { "read from" },
+            self.address,
+            u8::from(self.mode),
+            self.line
+        )
+    }
 }
 
 const SCREEN_WIDTH: usize = 160;
 const SCREEN_HEIGHT: usize = 144;
 
-#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct GPU {
-    #[cfg_attr(feature = "serialize", serde(skip_serializing))]
-    pub canvas_buffer: [u8; SCREEN_WIDTH * SCREEN_HEIGHT * 4],
-    #[cfg_attr(feature = "serialize", serde(skip_serializing))]
-    pub tile_set: [Tile; 384],
-    #[cfg_attr(feature = "serialize", serde(skip_serializing))]
+    #[cfg_attr(feature = "serialize", serde(skip, default = "empty_canvas"))]
+    pub canvas_buffer: Box<[u8; SCREEN_WIDTH * SCREEN_HEIGHT * 4]>,
+    // Decoded views of VRAM and OAM, rebuilt by rebuild_caches after a state is loaded
+    #[cfg_attr(feature = "serialize", serde(skip, default = "empty_tile_set"))]
+    pub tile_set: Box<[Tile; 384]>,
+    #[cfg_attr(feature = "serialize", serde(skip, default = "empty_object_data"))]
     pub object_data: [ObjectData; NUMBER_OF_OBJECTS],
-    #[cfg_attr(feature = "serialize", serde(skip_serializing))]
+    #[cfg_attr(feature = "serialize", serde(with = "crate::utils::byte_array"))]
     pub vram: [u8; 0x2000],
-    #[cfg_attr(feature = "serialize", serde(skip_serializing))]
+    #[cfg_attr(feature = "serialize", serde(with = "crate::utils::byte_array"))]
     pub oam: [u8; 0xA0],
     pub background_colors: BackgroundColors,
     pub viewport_x_offset: u8,
@@ -208,13 +292,17 @@ pub struct GPU {
     pub hblank_interrupt_enabled: bool,
     pub line_check: u8,
     pub line_equals_line_check: bool,
+    stat_line: bool,
     pub window_tile_map: TileMap,
     pub background_tile_map: TileMap,
     pub background_and_window_data_select: BackgroundAndWindowDataSelect,
     pub object_size: ObjectSize,
+    // Colour 0 is transparent for objects, it is only kept to read OBP0/OBP1 back
+    pub obj_0_color_0: Color,
     pub obj_0_color_1: Color,
     pub obj_0_color_2: Color,
     pub obj_0_color_3: Color,
+    pub obj_1_color_0: Color,
     pub obj_1_color_1: Color,
     pub obj_1_color_2: Color,
     pub obj_1_color_3: Color,
@@ -222,14 +310,39 @@ pub struct GPU {
     pub line: u8,
     pub mode: Mode,
     cycles: u16,
+    // Picked when the GPU is created, a loaded state keeps the running one
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    pub renderer: Renderer,
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    pub strict_access: bool,
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    illegal_accesses: Vec<IllegalAccess>,
+    pixel_fifo: PixelFifo,
+}
+
+// Boxed so moving the GPU around (e.g. when a save state is decoded) stays cheap
+fn empty_canvas() -> Box<[u8; SCREEN_WIDTH * SCREEN_HEIGHT * 4]> {
+    Box::new([0; SCREEN_WIDTH * SCREEN_HEIGHT * 4])
+}
+
+fn empty_tile_set() -> Box<[Tile; 384]> {
+    Box::new([empty_tile(); 384])
+}
+
+fn empty_object_data() -> [ObjectData; NUMBER_OF_OBJECTS] {
+    [Default::default(); NUMBER_OF_OBJECTS]
 }
 
 impl GPU {
     pub fn new() -> GPU {
+        GPU::with_renderer(Renderer::Scanline)
+    }
+
+    pub fn with_renderer(renderer: Renderer) -> GPU {
         GPU {
-            canvas_buffer: [0; SCREEN_WIDTH * SCREEN_HEIGHT * 4],
-            tile_set: [empty_tile(); 384],
-            object_data: [Default::default(); NUMBER_OF_OBJECTS],
+            canvas_buffer: empty_canvas(),
+            tile_set: empty_tile_set(),
+            object_data: empty_object_data(),
             vram: [0; 0x2000],
             oam: [0; 0xA0],
             background_colors: BackgroundColors::new(),
@@ -245,33 +358,52 @@ impl GPU {
             hblank_interrupt_enabled: false,
             line_check: 0,
             line_equals_line_check: false,
+            stat_line: false,
             window_tile_map: TileMap::X9800,
             background_tile_map: TileMap::X9800,
             background_and_window_data_select: BackgroundAndWindowDataSelect::X8800,
             object_size: ObjectSize::OS8X8,
+            obj_0_color_0: Color::White,
             obj_0_color_1: Color::LightGray,
             obj_0_color_2: Color::DarkGray,
             obj_0_color_3: Color::Black,
+            obj_1_color_0: Color::White,
             obj_1_color_1: Color::LightGray,
             obj_1_color_2: Color::DarkGray,
             obj_1_color_3: Color::Black,
-            window: Window { x: 0, y: 0 },
+            window: Window::new(),
             line: 0,
             cycles: 0,
             mode: Mode::HorizontalBlank,
+            renderer,
+            strict_access: false,
+            illegal_accesses: Vec::new(),
+            pixel_fifo: PixelFifo::new(),
         }
     }
 
     pub fn gpu_read(&mut self, mut address: u16) -> u8 {
         match address {
             0x8000..=0x9FFF => {
+                if !self.vram_accessible() {
+                    self.illegal_access(false, address);
+                    return 0xFF;
+                }
                 address -= 0x8000;
                 self.vram[address as usize]
             }
             0xFE00..=0xFE9F => {
+                if !self.oam_accessible() {
+                    self.illegal_access(false, address);
+                    return 0xFF;
+                }
                 address -= 0xFE00;
                 self.oam[address as usize]
             },
+            0xFEA0..=0xFEFF => {
+                // Nothing is mapped here, on DMG it reads 0 unless the PPU owns OAM
+                if self.oam_accessible() { 0x00 } else { 0xFF }
+            }
             0xFF40 => {
                 bit(self.lcd_display_enabled) << 7
                     | bit(self.window_tile_map == TileMap::X9C00) << 6
@@ -284,26 +416,76 @@ impl GPU {
                     | bit(self.object_display_enabled) << 1
                     | bit(self.background_display_enabled)
             },
+            0xFF41 => {
+                // The mode reads as 0 while the LCD is off, bit 7 is unused
+                let mode = if self.lcd_display_enabled { u8::from(self.mode) } else { 0 };
+                0x80 | bit(self.line_equals_line_check_interrupt_enabled) << 6
+                    | bit(self.oam_interrupt_enabled) << 5
+                    | bit(self.vblank_interrupt_enabled) << 4
+                    | bit(self.hblank_interrupt_enabled) << 3
+                    | bit(self.line_equals_line_check) << 2
+                    | mode
+            },
             0xFF42 => self.viewport_y_offset,
+            0xFF43 => self.viewport_x_offset,
             0xFF44 => self.line, //0x90 when blargss else self.line
-            _ => panic!("GPU read address not implemented: {:04X}", address)
+            0xFF45 => self.line_check,
+            0xFF47 => self.background_colors.into(),
+            0xFF48 => BackgroundColors(
+                self.obj_0_color_0,
+                self.obj_0_color_1,
+                self.obj_0_color_2,
+                self.obj_0_color_3,
+            ).into(),
+            0xFF49 => BackgroundColors(
+                self.obj_1_color_0,
+                self.obj_1_color_1,
+                self.obj_1_color_2,
+                self.obj_1_color_3,
+            ).into(),
+            0xFF4A => self.window.y,
+            0xFF4B => self.window.x,
+            _ => 0xFF, // Not mapped
         }
     }
 
-    pub fn gpu_write(&mut self, mut address: u16, value: u8) {
+    // Returns true when the write triggers a STAT interrupt
+    pub fn gpu_write(&mut self, mut address: u16, value: u8) -> bool {
+        let mut stat_write_bug = false;
         match address {
             0x8000..=0x9FFF => {
+                if !self.vram_accessible() {
+                    self.illegal_access(true, address);
+                    return false;
+                }
                 address -= 0x8000;
                 self.write_vram(address as usize, value);
             }
             0xFE00..=0xFE9F => {
+                if !self.oam_accessible() {
+                    self.illegal_access(true, address);
+                    return false;
+                }
                 address -= 0xFE00;
                 self.write_oam(address as usize, value);
             },
 
             0xFF40 => {
                 // LCD Control
-                self.lcd_display_enabled = (value >> 7) == 1;
+                let lcd_display_enabled = (value >> 7) == 1;
+                if self.lcd_display_enabled && !lcd_display_enabled {
+                    // LY is held at 0 while the LCD is off
+                    self.line = 0;
+                    self.cycles = 0;
+                    self.mode = Mode::HorizontalBlank;
+                    self.set_equal_lines_check();
+                } else if !self.lcd_display_enabled && lcd_display_enabled {
+                    // Drawing starts over from the top of the screen
+                    self.cycles = 0;
+                    self.mode = Mode::OAMAccess;
+                    self.window.reset();
+                }
+                self.lcd_display_enabled = lcd_display_enabled;
                 self.window_tile_map = if ((value >> 6) & 0b1) == 1 {
                     TileMap::X9C00
                 } else {
@@ -330,6 +512,16 @@ impl GPU {
             }
             0xFF41 => {
                 // LCD Controller Status
+                // DMG bug: for one cycle the write acts as if every source was enabled,
+                // which interrupts in HBlank, VBlank or when LY=LYC
+                stat_write_bug = self.lcd_display_enabled
+                    && (self.line_equals_line_check
+                        || self.mode == Mode::HorizontalBlank
+                        || self.mode == Mode::VerticalBlank)
+                    && !self.stat_line;
+                if stat_write_bug {
+                    self.stat_line = true;
+                }
                 self.line_equals_line_check_interrupt_enabled =
                     (value & 0b1000000) == 0b1000000;
                 self.oam_interrupt_enabled = (value & 0b100000) == 0b100000;
@@ -344,8 +536,12 @@ impl GPU {
                 // Viewport X Offset
                 self.viewport_x_offset = value;
             }
+            0xFF44 => {
+                // LY is read only
+            }
             0xFF45 => {
                 self.line_check = value;
+                self.set_equal_lines_check();
             }
             0xFF47 => {
                 // Background Colors Setting
@@ -355,11 +551,13 @@ impl GPU {
                 self.obj_0_color_3 = (value >> 6).into();
                 self.obj_0_color_2 = ((value >> 4) & 0b11).into();
                 self.obj_0_color_1 = ((value >> 2) & 0b11).into();
+                self.obj_0_color_0 = (value & 0b11).into();
             }
             0xFF49 => {
                 self.obj_1_color_3 = (value >> 6).into();
                 self.obj_1_color_2 = ((value >> 4) & 0b11).into();
                 self.obj_1_color_1 = ((value >> 2) & 0b11).into();
+                self.obj_1_color_0 = (value & 0b11).into();
             }
             0xFF4A => {
                 self.window.y = value;
@@ -369,6 +567,31 @@ impl GPU {
             }
             _ => ()
         }
+        let rising_edge = self.update_stat_line();
+        rising_edge || stat_write_bug
+    }
+
+    // The PPU owns VRAM while it draws, and OAM while it scans it and draws
+    fn vram_accessible(&self) -> bool {
+        !self.lcd_display_enabled || self.mode != Mode::VRAMAccess
+    }
+
+    fn oam_accessible(&self) -> bool {
+        !self.lcd_display_enabled
+            || (self.mode != Mode::OAMAccess && self.mode != Mode::VRAMAccess)
+    }
+
+    // Blocked accesses read 0xFF and drop writes like on hardware, strict mode also
+    // records them to help tracking down timing bugs in homebrew
+    fn illegal_access(&mut self, write: bool, address: u16) {
+        if self.strict_access {
+            self.illegal_accesses.push(IllegalAccess { write, address, mode: self.mode, line: self.line });
+        }
+    }
+
+    // Hands over the accesses recorded since the last call
+    pub fn take_illegal_accesses(&mut self) -> Vec<IllegalAccess> {
+        std::mem::take(&mut self.illegal_accesses)
     }
 
     pub fn write_vram(&mut self, index: usize, value: u8) {
@@ -405,6 +628,15 @@ impl GPU {
         }
     }
 
+    pub fn rebuild_caches(&mut self) {
+        for index in 0..self.vram.len() {
+            self.write_vram(index, self.vram[index]);
+        }
+        for index in 0..self.oam.len() {
+            self.write_oam(index, self.oam[index]);
+        }
+    }
+
     pub fn write_oam(&mut self, index: usize, value: u8) {
         self.oam[index] = value;
         let object_index = index / 4;
@@ -433,6 +665,13 @@ impl GPU {
     }
 
     pub fn step(&mut self, cycles: u8) -> InterruptRequest {
+        match self.renderer {
+            Renderer::Scanline => self.step_scanline(cycles),
+            Renderer::PixelFifo => self.step_pixel_fifo(cycles),
+        }
+    }
+
+    fn step_scanline(&mut self, cycles: u8) -> InterruptRequest {
         let mut request = InterruptRequest::None;
         if !self.lcd_display_enabled {
             return request;
@@ -444,35 +683,14 @@ impl GPU {
             Mode::HorizontalBlank => {
                 if self.cycles >= 200 {
                     self.cycles = self.cycles % 200;
-                    self.line += 1;
-
-                    if self.line >= 144 {
-                        self.mode = Mode::VerticalBlank;
-                        request.add(InterruptRequest::VBlank);
-                        if self.vblank_interrupt_enabled {
-                            request.add(InterruptRequest::LCDStat)
-                        }
-                    } else {
-                        self.mode = Mode::OAMAccess;
-                        if self.oam_interrupt_enabled {
-                            request.add(InterruptRequest::LCDStat)
-                        }
-                    }
-                    self.set_equal_lines_check(&mut request);
+                    self.end_horizontal_blank(&mut request);
                 }
             }
             Mode::VerticalBlank => {
+                self.check_line_153();
                 if self.cycles >= 456 {
                     self.cycles = self.cycles % 456;
-                    self.line += 1;
-                    if self.line == 154 {
-                        self.mode = Mode::OAMAccess;
-                        self.line = 0;
-                        if self.oam_interrupt_enabled {
-                            request.add(InterruptRequest::LCDStat)
-                        }
-                    }
-                    self.set_equal_lines_check(&mut request);
+                    self.end_vertical_blank_line();
                 }
             }
             Mode::OAMAccess => {
@@ -484,30 +702,75 @@ impl GPU {
             Mode::VRAMAccess => {
                 if self.cycles >= 172 {
                     self.cycles = self.cycles % 172;
-                    if self.hblank_interrupt_enabled {
-                        request.add(InterruptRequest::LCDStat)
-                    }
                     self.mode = Mode::HorizontalBlank;
                     self.render_scan_line()
                 }
             }
         }
+        if self.update_stat_line() {
+            request.add(InterruptRequest::LCDStat)
+        }
         request
     }
 
-    fn set_equal_lines_check(&mut self, request: &mut InterruptRequest) {
-        let line_equals_line_check = self.line == self.line_check;
-        if line_equals_line_check && self.line_equals_line_check_interrupt_enabled {
-            request.add(InterruptRequest::LCDStat);
+    fn end_horizontal_blank(&mut self, request: &mut InterruptRequest) {
+        self.line += 1;
+
+        if self.line >= 144 {
+            self.mode = Mode::VerticalBlank;
+            self.window.reset();
+            request.add(InterruptRequest::VBlank);
+        } else {
+            self.mode = Mode::OAMAccess;
         }
-        self.line_equals_line_check = line_equals_line_check;
+        self.set_equal_lines_check();
     }
 
-    pub fn background_as_buffer(&self, outline_tiles: bool, show_viewport: bool) -> Vec<u8> {
-        if self.background_tile_map != TileMap::X9800 {
-            panic!("We only support tilemap at 0x9800 for now");
+    fn end_vertical_blank_line(&mut self) {
+        if self.line == 0 {
+            // End of line 153, LY already went back to 0
+            self.mode = Mode::OAMAccess;
+        } else {
+            self.line += 1;
+        }
+        self.set_equal_lines_check();
+    }
+
+    // LY only reads 153 for the first few dots of the last line, it reads 0 (and is
+    // compared to LYC as 0) for the rest of it
+    fn check_line_153(&mut self) {
+        if self.line == 153 && self.cycles >= LINE_153_DOTS {
+            self.line = 0;
+            self.set_equal_lines_check();
+        }
+    }
+
+    fn set_equal_lines_check(&mut self) {
+        self.line_equals_line_check = self.line == self.line_check;
+    }
+
+    // The STAT interrupt is requested on the rising edge of the OR of every enabled
+    // source, so a source becoming active while another one already holds the line
+    // high doesn't trigger another interrupt
+    fn stat_line_level(&self) -> bool {
+        if !self.lcd_display_enabled {
+            return false;
         }
+        (self.line_equals_line_check_interrupt_enabled && self.line_equals_line_check)
+            || (self.hblank_interrupt_enabled && self.mode == Mode::HorizontalBlank)
+            || (self.vblank_interrupt_enabled && self.mode == Mode::VerticalBlank)
+            || (self.oam_interrupt_enabled && self.mode == Mode::OAMAccess)
+    }
+
+    // Returns true on a rising edge of the STAT interrupt line
+    fn update_stat_line(&mut self) -> bool {
+        let level = self.stat_line_level();
+        let rising_edge = level && !self.stat_line;
+        self.stat_line = level;
+        rising_edge
+    }
 
+    pub fn background_as_buffer(&self, outline_tiles: bool, show_viewport: bool) -> Vec<u8> {
         let width_in_tiles = 32;
         let height_in_tiles = 32;
 
@@ -525,10 +788,14 @@ impl GPU {
             * values_per_pixel;
         let mut data = vec![0; data_length];
 
-        let tiles = self
-            .background_1()
+        let tile_map = if self.background_tile_map == TileMap::X9800 {
+            self.background_1()
+        } else {
+            self.background_2()
+        };
+        let tiles = tile_map
             .iter()
-            .map(|byte| self.tile_set[*byte as usize]);
+            .map(|byte| self.tile_set[self.tile_set_index(*byte)]);
 
         for (tile_index, tile) in tiles.enumerate() {
             let tile_row = tile_index / height_in_tiles;
@@ -669,7 +936,7 @@ impl GPU {
         let index = (tile_y * 32) + tile_x;
         let mut result = [[0u8; 8]; 8];
         let byte = self.background_1().iter().nth(index).unwrap();
-        let tile = self.tile_set[*byte as usize];
+        let tile = self.tile_set[self.tile_set_index(*byte)];
         for (row_index, row) in tile.iter().enumerate() {
             for (pixel_index, pixel) in row.iter().enumerate() {
                 result[row_index][pixel_index] = self.tile_value_to_background_color(pixel) as u8;
@@ -683,6 +950,10 @@ impl GPU {
         &self.vram[0x1800..0x1C00]
     }
 
+    fn background_2(&self) -> &[u8] {
+        &self.vram[0x1C00..0x2000]
+    }
+
     fn render_scan_line(&mut self) {
         let mut scan_line: [TilePixelValue; SCREEN_WIDTH] = [Default::default(); SCREEN_WIDTH];
         if self.background_display_enabled {
@@ -706,86 +977,164 @@ impl GPU {
             let row_y_offset = tile_y_index % 8;
             let mut pixel_x_index = self.viewport_x_offset % 8;
 
-            if self.background_and_window_data_select == BackgroundAndWindowDataSelect::X8800 {
-                panic!("TODO: support 0x8800 background and window data select");
-            }
-
-            let mut canvas_buffer_offset = self.line as usize * SCREEN_WIDTH * 4;
             for line_x in 0..SCREEN_WIDTH {
                 let tile_index = self.vram[tile_map_offset + tile_x_index as usize];
 
-                let tile_value = self.tile_set[tile_index as usize][row_y_offset as usize]
+                let tile_value = self.tile_set[self.tile_set_index(tile_index)][row_y_offset as usize]
                     [pixel_x_index as usize];
                 let color = self.tile_value_to_background_color(&tile_value);
 
-                self.canvas_buffer[canvas_buffer_offset] = color as u8;
-                self.canvas_buffer[canvas_buffer_offset + 1] = color as u8;
-                self.canvas_buffer[canvas_buffer_offset + 2] = color as u8;
-                self.canvas_buffer[canvas_buffer_offset + 3] = 255;
-                canvas_buffer_offset += 4;
+                self.set_pixel(line_x, color);
                 scan_line[line_x] = tile_value;
                 pixel_x_index = (pixel_x_index + 1) % 8;
 
                 if pixel_x_index == 0 {
-                    tile_x_index = tile_x_index + 1;
-                }
-                if self.background_and_window_data_select == BackgroundAndWindowDataSelect::X8800 {
-                    panic!("TODO: support 0x8800 background and window data select");
+                    // The background map is 32 tiles wide and wraps around
+                    tile_x_index = (tile_x_index + 1) % 32;
                 }
             }
         }
 
-        if self.object_display_enabled {
-            let object_height = if self.object_size == ObjectSize::OS8X16 {
-                16
-            } else {
-                8
-            };
-            for object in self.object_data.iter() {
-                let line = self.line as i16;
-                if object.y <= line && object.y + object_height > line {
-                    let pixel_y_offset = line - object.y;
-                    let tile_index = if object_height == 16 && (!object.yflip && pixel_y_offset > 7)
-                        || (object.yflip && pixel_y_offset <= 7)
-                    {
-                        object.tile + 1
-                    } else {
-                        object.tile
-                    };
+        self.render_window(&mut scan_line);
 
-                    let tile = self.tile_set[tile_index as usize];
-                    let tile_row = if object.yflip {
-                        tile[(7 - (pixel_y_offset % 8)) as usize]
-                    } else {
-                        tile[(pixel_y_offset % 8) as usize]
-                    };
+        self.render_objects(&scan_line);
+    }
 
-                    let canvas_y_offset = line as i32 * SCREEN_WIDTH as i32;
-                    let mut canvas_offset = ((canvas_y_offset + object.x as i32) * 4) as usize;
-                    for x in 0..8i16 {
-                        let pixel_x_offset = if object.xflip { (7 - x) } else { x } as usize;
-                        let x_offset = object.x + x;
-                        let pixel = tile_row[pixel_x_offset];
-                        if x_offset >= 0
-                            && x_offset < SCREEN_WIDTH as i16
-                            && pixel != TilePixelValue::Zero
-                            && (object.priority
-                            || scan_line[x_offset as usize] == TilePixelValue::Zero)
-                        {
-                            let color = self.tile_value_to_background_color(&pixel);
-
-                            self.canvas_buffer[canvas_offset + 0] = color as u8;
-                            self.canvas_buffer[canvas_offset + 1] = color as u8;
-                            self.canvas_buffer[canvas_offset + 2] = color as u8;
-                            self.canvas_buffer[canvas_offset + 3] = 255;
-                        }
-                        canvas_offset += 4;
-                    }
+    fn render_window(&mut self, scan_line: &mut [TilePixelValue; SCREEN_WIDTH]) {
+        if self.line == self.window.y {
+            self.window.y_triggered = true;
+        }
+        // On DMG, LCDC bit 0 turns off the window along with the background
+        if !self.window_display_enabled || !self.background_display_enabled || !self.window.y_triggered {
+            return;
+        }
+
+        // WX=166 shows nothing on its own line, instead the window covers the whole next
+        // one. Below 7 the window starts off-screen and its first columns are cut off.
+        let covers_line = std::mem::replace(&mut self.window.covers_next_line, false);
+        if self.window.x == 166 && !covers_line {
+            self.window.covers_next_line = true;
+            return;
+        }
+        let start_x = if covers_line { 0 } else { self.window.x as i16 - 7 };
+        if start_x >= SCREEN_WIDTH as i16 {
+            return;
+        }
+
+        let tile_map_begin = if self.window_tile_map == TileMap::X9800 {
+            0x1800
+        } else {
+            0x1C00
+        };
+        let window_y = self.window.line as usize;
+        let tile_map_offset = tile_map_begin + (window_y / 8) * 32;
+
+        for screen_x in start_x.max(0)..SCREEN_WIDTH as i16 {
+            let window_x = (screen_x - start_x) as usize;
+            let tile_number = self.vram[tile_map_offset + window_x / 8];
+            let tile_value = self.tile_set[self.tile_set_index(tile_number)][window_y % 8][window_x % 8];
+            let color = self.tile_value_to_background_color(&tile_value);
+            self.set_pixel(screen_x as usize, color);
+            scan_line[screen_x as usize] = tile_value;
+        }
+        self.window.line += 1;
+    }
+
+    // Index in tile_set of a tile number read from the background or window tile map.
+    // In 0x8800 mode the number is signed and relative to 0x9000, which maps 0-127 to
+    // tiles 256-383 and 128-255 to the tiles 128-255 shared with objects.
+    fn tile_set_index(&self, tile_number: u8) -> usize {
+        match self.background_and_window_data_select {
+            BackgroundAndWindowDataSelect::X8000 => tile_number as usize,
+            BackgroundAndWindowDataSelect::X8800 => (256 + tile_number as i8 as i16) as usize,
+        }
+    }
+
+    fn set_pixel(&mut self, x: usize, color: Color) {
+        let offset = (self.line as usize * SCREEN_WIDTH + x) * 4;
+        self.canvas_buffer[offset] = color as u8;
+        self.canvas_buffer[offset + 1] = color as u8;
+        self.canvas_buffer[offset + 2] = color as u8;
+        self.canvas_buffer[offset + 3] = 255;
+    }
+
+    // scan_line holds the background and window colour numbers of the line, before
+    // the palette is applied, which is what the BG-over-OBJ priority is checked against.
+    fn render_objects(&mut self, scan_line: &[TilePixelValue; SCREEN_WIDTH]) {
+        if !self.object_display_enabled {
+            return;
+        }
+        let object_height = self.object_height();
+        let line = self.line as i16;
+        let objects = self.objects_on_line();
+
+        for screen_x in 0..SCREEN_WIDTH as i16 {
+            for object in objects.iter() {
+                if screen_x < object.x || screen_x >= object.x + 8 {
+                    continue;
                 }
+                let pixel = self.object_pixel(object, line - object.y, screen_x - object.x, object_height);
+                if pixel == TilePixelValue::Zero {
+                    // Transparent, an object with a lower priority can show through
+                    continue;
+                }
+                // The first opaque object hides the ones below it even when the
+                // background ends up being drawn over it
+                if object.priority || scan_line[screen_x as usize] == TilePixelValue::Zero {
+                    let color = self.tile_value_to_object_color(object.palette, &pixel);
+                    self.set_pixel(screen_x as usize, color);
+                }
+                break;
             }
         }
+    }
+
+    fn object_height(&self) -> i16 {
+        if self.object_size == ObjectSize::OS8X16 {
+            16
+        } else {
+            8
+        }
+    }
+
+    // The OAM scan picks the first 10 objects on the line in OAM order, whether they
+    // are visible horizontally or not. They are returned in drawing priority order: on
+    // DMG the object with the smallest X is drawn on top, ties go to OAM order.
+    fn objects_on_line(&self) -> Vec<ObjectData> {
+        let object_height = self.object_height();
+        let line = self.line as i16;
+        let mut objects: Vec<(usize, ObjectData)> = self.object_data.iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, object)| object.y <= line && line < object.y + object_height)
+            .take(MAX_OBJECTS_PER_LINE)
+            .collect();
+        objects.sort_by_key(|(index, object)| (object.x, *index));
+        objects.into_iter().map(|(_, object)| object).collect()
+    }
+
+    fn object_pixel(&self, object: &ObjectData, y: i16, x: i16, object_height: i16) -> TilePixelValue {
+        let row = if object.yflip { object_height - 1 - y } else { y };
+        let column = if object.xflip { 7 - x } else { x };
+        // 8x16 objects ignore bit 0 of the tile number, the bottom half is the next tile
+        let tile = if object_height == 16 {
+            (object.tile & 0xFE) as usize + (row / 8) as usize
+        } else {
+            object.tile as usize
+        };
+        self.tile_set[tile][(row % 8) as usize][column as usize]
+    }
 
-        if self.window_display_enabled {}
+    fn tile_value_to_object_color(&self, palette: ObjectPalette, tile_value: &TilePixelValue) -> Color {
+        match (palette, tile_value) {
+            (ObjectPalette::Zero, TilePixelValue::One) => self.obj_0_color_1,
+            (ObjectPalette::Zero, TilePixelValue::Two) => self.obj_0_color_2,
+            (ObjectPalette::Zero, TilePixelValue::Three) => self.obj_0_color_3,
+            (ObjectPalette::One, TilePixelValue::One) => self.obj_1_color_1,
+            (ObjectPalette::One, TilePixelValue::Two) => self.obj_1_color_2,
+            (ObjectPalette::One, TilePixelValue::Three) => self.obj_1_color_3,
+            (_, TilePixelValue::Zero) => unreachable!("Colour 0 is transparent for objects"),
+        }
     }
 
     fn tile_value_to_background_color(&self, tile_value: &TilePixelValue) -> Color {
@@ -807,4 +1156,329 @@ mod tests {
         let gpu = GPU::new();
         gpu.tile_set_as_buffer(false);
     }
+
+    // Background map filled with tile 0 (blank), window map at 0x9C00 with tile 1 (black)
+    fn window_test_gpu() -> GPU {
+        let mut gpu = GPU::new();
+        for index in 0..16 {
+            gpu.write_vram(0x10 + index, 0xFF);
+        }
+        for index in 0x1C00..0x2000 {
+            gpu.write_vram(index, 0x01);
+        }
+        gpu.gpu_write(0xFF40, 0xF1); // LCD, window map 0x9C00, window, 0x8000 data, BG
+        gpu.gpu_write(0xFF47, 0xE4);
+        gpu
+    }
+
+    fn pixel(gpu: &GPU, x: usize, y: usize) -> u8 {
+        gpu.canvas_buffer[(y * SCREEN_WIDTH + x) * 4]
+    }
+
+    #[test]
+    fn test_signed_tile_addressing() {
+        let mut gpu = GPU::new();
+        assert_eq!(gpu.background_and_window_data_select, BackgroundAndWindowDataSelect::X8800);
+        assert_eq!(gpu.tile_set_index(0x00), 256);
+        assert_eq!(gpu.tile_set_index(0x7F), 383);
+        assert_eq!(gpu.tile_set_index(0x80), 128);
+        assert_eq!(gpu.tile_set_index(0xFF), 255);
+
+        gpu.gpu_write(0xFF40, 0x10);
+        assert_eq!(gpu.tile_set_index(0x00), 0);
+        assert_eq!(gpu.tile_set_index(0xFF), 255);
+    }
+
+    #[test]
+    fn test_background_in_8800_mode() {
+        let mut gpu = GPU::new();
+        // Tile 0 at 0x9000 is black, the same number in 0x8000 mode is blank
+        for index in 0..16 {
+            gpu.write_vram(0x1000 + index, 0xFF);
+        }
+        gpu.gpu_write(0xFF40, 0x81);
+        gpu.gpu_write(0xFF47, 0xE4);
+        gpu.render_scan_line();
+        assert_eq!(pixel(&gpu, 0, 0), Color::Black as u8);
+
+        gpu.gpu_write(0xFF40, 0x91);
+        gpu.render_scan_line();
+        assert_eq!(pixel(&gpu, 0, 0), Color::White as u8);
+    }
+
+    #[test]
+    fn test_window_position() {
+        let mut gpu = window_test_gpu();
+        gpu.gpu_write(0xFF4A, 2);
+        gpu.gpu_write(0xFF4B, 7 + 80);
+        for line in 0..4 {
+            gpu.line = line;
+            gpu.render_scan_line();
+        }
+        assert_eq!(pixel(&gpu, 100, 1), Color::White as u8);
+        assert_eq!(pixel(&gpu, 79, 2), Color::White as u8);
+        assert_eq!(pixel(&gpu, 80, 2), Color::Black as u8);
+        assert_eq!(gpu.window.line, 2);
+    }
+
+    #[test]
+    fn test_window_line_counter_skips_hidden_lines() {
+        let mut gpu = window_test_gpu();
+        gpu.gpu_write(0xFF4B, 200); // off-screen
+        for line in 0..10 {
+            gpu.line = line;
+            gpu.render_scan_line();
+        }
+        assert_eq!(gpu.window.line, 0);
+
+        gpu.gpu_write(0xFF4B, 0); // WX < 7, the first 7 columns are cut off
+        gpu.line = 10;
+        gpu.render_scan_line();
+        assert_eq!(gpu.window.line, 1);
+        assert_eq!(pixel(&gpu, 0, 10), Color::Black as u8);
+    }
+
+    #[test]
+    fn test_window_wx_166_covers_next_line() {
+        let mut gpu = window_test_gpu();
+        gpu.gpu_write(0xFF4B, 166);
+        gpu.line = 0;
+        gpu.render_scan_line();
+        assert_eq!(pixel(&gpu, 159, 0), Color::White as u8);
+
+        gpu.line = 1;
+        gpu.render_scan_line();
+        assert_eq!(pixel(&gpu, 0, 1), Color::Black as u8);
+    }
+
+    #[test]
+    fn test_register_read_back() {
+        let mut gpu = GPU::new();
+        for (address, value) in [
+            (0xFF40, 0xD3),
+            (0xFF42, 0x12),
+            (0xFF43, 0x34),
+            (0xFF45, 0x56),
+            (0xFF47, 0x1B),
+            (0xFF48, 0xE7),
+            (0xFF49, 0x2D),
+            (0xFF4A, 0x78),
+            (0xFF4B, 0x9A),
+        ] {
+            gpu.gpu_write(address, value);
+            assert_eq!(gpu.gpu_read(address), value, "{:04X}", address);
+        }
+    }
+
+    #[test]
+    fn test_stat_read_back() {
+        let mut gpu = GPU::new();
+        gpu.gpu_write(0xFF41, 0xFF);
+        gpu.gpu_write(0xFF45, 1);
+        // LCD off: unused bit 7 set, mode 0, LY=0 doesn't match LYC=1
+        assert_eq!(gpu.gpu_read(0xFF41), 0xF8);
+
+        gpu.gpu_write(0xFF45, 0);
+        gpu.gpu_write(0xFF40, 0x80);
+        assert_eq!(gpu.gpu_read(0xFF41), 0xFC | u8::from(Mode::OAMAccess));
+        gpu.step(80);
+        assert_eq!(gpu.gpu_read(0xFF41) & 0x03, u8::from(Mode::VRAMAccess));
+    }
+
+    #[test]
+    fn test_ly_reset_when_lcd_disabled() {
+        let mut gpu = GPU::new();
+        gpu.gpu_write(0xFF40, 0x80);
+        for _ in 0..1000 {
+            gpu.step(4);
+        }
+        assert!(gpu.gpu_read(0xFF44) > 0);
+        gpu.gpu_write(0xFF44, 0x40);
+        assert_ne!(gpu.gpu_read(0xFF44), 0x40);
+
+        gpu.gpu_write(0xFF40, 0x00);
+        assert_eq!(gpu.gpu_read(0xFF44), 0);
+        gpu.step(200);
+        assert_eq!(gpu.gpu_read(0xFF44), 0);
+    }
+
+    #[test]
+    fn test_vram_and_oam_lockout() {
+        let mut gpu = GPU::new();
+        gpu.gpu_write(0x8000, 0x12);
+        gpu.gpu_write(0xFE00, 0x34);
+        gpu.gpu_write(0xFF40, 0x80);
+
+        // Mode 2: OAM is blocked, VRAM isn't
+        assert_eq!(gpu.mode, Mode::OAMAccess);
+        assert_eq!(gpu.gpu_read(0x8000), 0x12);
+        assert_eq!(gpu.gpu_read(0xFE00), 0xFF);
+        gpu.gpu_write(0xFE00, 0x56);
+
+        gpu.step(80);
+        assert_eq!(gpu.mode, Mode::VRAMAccess);
+        assert_eq!(gpu.gpu_read(0x8000), 0xFF);
+        assert_eq!(gpu.gpu_read(0xFE00), 0xFF);
+        gpu.gpu_write(0x8000, 0x78);
+
+        gpu.step(172);
+        assert_eq!(gpu.mode, Mode::HorizontalBlank);
+        assert_eq!(gpu.gpu_read(0x8000), 0x12);
+        assert_eq!(gpu.gpu_read(0xFE00), 0x34);
+        assert!(gpu.take_illegal_accesses().is_empty());
+    }
+
+    #[test]
+    fn test_strict_mode_records_illegal_accesses() {
+        let mut gpu = GPU::new();
+        gpu.strict_access = true;
+        gpu.gpu_write(0xFF40, 0x80);
+        gpu.step(80);
+
+        gpu.gpu_read(0x8010);
+        gpu.gpu_write(0xFE04, 0x00);
+        let accesses = gpu.take_illegal_accesses();
+        assert_eq!(accesses, vec![
+            IllegalAccess { write: false, address: 0x8010, mode: Mode::VRAMAccess, line: 0 },
+            IllegalAccess { write: true, address: 0xFE04, mode: Mode::VRAMAccess, line: 0 },
+        ]);
+        assert_eq!(accesses[1].to_string(), "Illegal write to FE04 in mode 3 (LY=0)");
+        assert!(gpu.take_illegal_accesses().is_empty());
+    }
+
+    // Steps 4 cycles at a time until the condition holds, returns the STAT interrupts seen
+    fn stat_interrupts_until(gpu: &mut GPU, condition: impl Fn(&GPU) -> bool) -> usize {
+        let mut interrupts = 0;
+        while !condition(gpu) {
+            match gpu.step(4) {
+                InterruptRequest::LCDStat | InterruptRequest::Both => interrupts += 1,
+                _ => {}
+            }
+        }
+        interrupts
+    }
+
+    #[test]
+    fn test_stat_sources_share_one_line() {
+        let mut gpu = GPU::new();
+        gpu.gpu_write(0xFF41, 0x28); // HBlank and OAM
+        assert!(gpu.gpu_write(0xFF40, 0x80));
+
+        // HBlank is followed straight by OAM on lines 0-143, only HBlank raises the line
+        let interrupts = stat_interrupts_until(&mut gpu, |gpu| gpu.mode == Mode::VerticalBlank);
+        assert_eq!(interrupts, 144);
+        let interrupts = stat_interrupts_until(&mut gpu, |gpu| gpu.mode == Mode::OAMAccess);
+        assert_eq!(interrupts, 1);
+    }
+
+    #[test]
+    fn test_stat_write_bug() {
+        let mut gpu = GPU::new();
+        gpu.gpu_write(0xFF45, 0x90);
+        gpu.gpu_write(0xFF40, 0x80);
+        stat_interrupts_until(&mut gpu, |gpu| gpu.mode == Mode::VRAMAccess);
+        assert!(!gpu.gpu_write(0xFF41, 0x00));
+        stat_interrupts_until(&mut gpu, |gpu| gpu.mode == Mode::HorizontalBlank);
+        assert!(gpu.gpu_write(0xFF41, 0x00));
+        // Only on a rising edge, HBlank keeps the line high after this one
+        assert!(gpu.gpu_write(0xFF41, 0x08));
+        assert!(!gpu.gpu_write(0xFF41, 0x08));
+    }
+
+    #[test]
+    fn test_line_153_compares_as_0() {
+        let mut gpu = GPU::new();
+        gpu.gpu_write(0xFF45, 0);
+        gpu.gpu_write(0xFF41, 0x40);
+        gpu.gpu_write(0xFF40, 0x80);
+        stat_interrupts_until(&mut gpu, |gpu| gpu.line == 153);
+
+        let interrupts = stat_interrupts_until(&mut gpu, |gpu| gpu.line == 0);
+        assert_eq!(gpu.mode, Mode::VerticalBlank);
+        assert_eq!(interrupts, 1);
+        assert_eq!(gpu.gpu_read(0xFF41) & 0x04, 0x04);
+
+        // Still the same match once the next frame starts, no new interrupt
+        let interrupts = stat_interrupts_until(&mut gpu, |gpu| gpu.mode == Mode::VRAMAccess);
+        assert_eq!(interrupts, 0);
+    }
+
+    // Tile 1 is all colour 3 and tile 2 all colour 1, the background is tile 0 (colour 0)
+    fn object_test_gpu() -> GPU {
+        let mut gpu = GPU::new();
+        for index in 0..16 {
+            gpu.write_vram(0x10 + index, 0xFF);
+        }
+        for index in 0..8 {
+            gpu.write_vram(0x20 + index * 2, 0xFF);
+        }
+        gpu.gpu_write(0xFF40, 0x93); // LCD, 0x8000 data, objects, BG
+        gpu.gpu_write(0xFF47, 0xE4);
+        gpu.gpu_write(0xFF48, 0xE4);
+        gpu
+    }
+
+    fn place_object(gpu: &mut GPU, index: usize, x: i16, y: i16, tile: u8, flags: u8) {
+        gpu.write_oam(index * 4, (y + 16) as u8);
+        gpu.write_oam(index * 4 + 1, (x + 8) as u8);
+        gpu.write_oam(index * 4 + 2, tile);
+        gpu.write_oam(index * 4 + 3, flags);
+    }
+
+    #[test]
+    fn test_ten_objects_per_line() {
+        let mut gpu = object_test_gpu();
+        // Off screen horizontally but still takes one of the 10 slots
+        place_object(&mut gpu, 0, -8, 0, 1, 0);
+        for index in 1..=10 {
+            place_object(&mut gpu, index, (index as i16 - 1) * 8, 0, 1, 0);
+        }
+        gpu.line = 0;
+        gpu.render_scan_line();
+        assert_eq!(pixel(&gpu, 64, 0), Color::Black as u8);
+        assert_eq!(pixel(&gpu, 72, 0), Color::White as u8);
+    }
+
+    #[test]
+    fn test_object_priority() {
+        let mut gpu = object_test_gpu();
+        // The smaller X wins even with a higher OAM index
+        place_object(&mut gpu, 0, 4, 0, 2, 0);
+        place_object(&mut gpu, 1, 0, 0, 1, 0);
+        // Same X, the lower OAM index wins
+        place_object(&mut gpu, 2, 20, 0, 1, 0);
+        place_object(&mut gpu, 3, 20, 0, 2, 0);
+        gpu.line = 0;
+        gpu.render_scan_line();
+        assert_eq!(pixel(&gpu, 7, 0), Color::Black as u8);
+        assert_eq!(pixel(&gpu, 8, 0), Color::LightGray as u8);
+        assert_eq!(pixel(&gpu, 20, 0), Color::Black as u8);
+    }
+
+    #[test]
+    fn test_object_palettes() {
+        let mut gpu = object_test_gpu();
+        gpu.gpu_write(0xFF49, 0x90);
+        place_object(&mut gpu, 0, 0, 0, 1, 0x00);
+        place_object(&mut gpu, 1, 8, 0, 1, 0x10);
+        gpu.line = 0;
+        gpu.render_scan_line();
+        assert_eq!(pixel(&gpu, 0, 0), Color::Black as u8);
+        assert_eq!(pixel(&gpu, 8, 0), Color::DarkGray as u8);
+    }
+
+    #[test]
+    fn test_background_over_object_priority() {
+        let mut gpu = object_test_gpu();
+        gpu.write_vram(0x1801, 2);
+        // BG colour 0 maps to a dark shade, the object still shows on top of it
+        gpu.gpu_write(0xFF47, 0xE7);
+        gpu.gpu_write(0xFF48, 0x24);
+        place_object(&mut gpu, 0, 0, 0, 1, 0x80);
+        place_object(&mut gpu, 1, 8, 0, 1, 0x80);
+        gpu.line = 0;
+        gpu.render_scan_line();
+        assert_eq!(pixel(&gpu, 0, 0), Color::White as u8);
+        assert_eq!(pixel(&gpu, 8, 0), Color::LightGray as u8);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,56 @@
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Envelope {
+    initial_volume: u8,
+    increase: bool,
+    period: u8,
+    timer: u8,
+    pub volume: u8,
+}
+
+impl Envelope {
+    pub fn new() -> Envelope {
+        Envelope {
+            initial_volume: 0,
+            increase: false,
+            period: 0,
+            timer: 0,
+            volume: 0,
+        }
+    }
+
+    pub fn read(&self) -> u8 {
+        self.initial_volume << 4 | (if self.increase { 0x08 } else { 0 }) | self.period
+    }
+
+    pub fn write(&mut self, value: u8) {
+        self.initial_volume = value >> 4;
+        self.increase = (value & 0x08) != 0;
+        self.period = value & 0x07;
+    }
+
+    // The DAC is powered as long as the upper 5 bits of NRx2 aren't all 0
+    pub fn dac_enabled(&self) -> bool {
+        (self.read() & 0xF8) != 0
+    }
+
+    pub fn trigger(&mut self) {
+        self.timer = if self.period == 0 { 8 } else { self.period };
+        self.volume = self.initial_volume;
+    }
+
+    // Clocked at 64 Hz by the frame sequencer
+    pub fn clock(&mut self) {
+        if self.period == 0 {
+            return;
+        }
+        self.timer = self.timer.saturating_sub(1);
+        if self.timer == 0 {
+            self.timer = self.period;
+            if self.increase && self.volume < 15 {
+                self.volume += 1;
+            } else if !self.increase && self.volume > 0 {
+                self.volume -= 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug)]
+pub struct LengthCounter {
+    pub enabled: bool,
+    counter: u16,
+    max: u16,
+}
+
+impl LengthCounter {
+    pub fn new(max: u16) -> LengthCounter {
+        LengthCounter {
+            enabled: false,
+            counter: 0,
+            max,
+        }
+    }
+
+    pub fn load(&mut self, value: u8) {
+        self.counter = self.max - value as u16;
+    }
+
+    // Clocked at 256 Hz by the frame sequencer, returns true when the channel expires
+    pub fn clock(&mut self) -> bool {
+        if self.enabled && self.counter > 0 {
+            self.counter -= 1;
+            return self.counter == 0;
+        }
+        false
+    }
+
+    // Enabling the counter while the frame sequencer is in the half of its period that
+    // doesn't clock length gives it an extra clock. Returns true when that expires it.
+    pub fn set_enabled(&mut self, enabled: bool, first_half: bool) -> bool {
+        let was_enabled = self.enabled;
+        self.enabled = enabled;
+        if !was_enabled && first_half {
+            return self.clock();
+        }
+        false
+    }
+
+    pub fn trigger(&mut self, first_half: bool) {
+        if self.counter == 0 {
+            self.counter = self.max;
+            if self.enabled && first_half {
+                self.counter -= 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,345 @@
+use crate::io::apu::noise::NoiseChannel;
+use crate::io::apu::resampler::Resampler;
+use crate::io::apu::square::SquareChannel;
+use crate::io::apu::wave::WaveChannel;
+
+mod envelope;
+mod length;
+mod noise;
+mod resampler;
+mod square;
+mod wave;
+
+// Bits that always read back as 1 for 0xFF10-0xFF2F, write-only bits included
+const READ_MASKS: [u8; 0x20] = [
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, // NR10-NR14
+    0xFF, 0x3F, 0x00, 0xFF, 0xBF, // NR20-NR24
+    0x7F, 0xFF, 0x9F, 0xFF, 0xBF, // NR30-NR34
+    0xFF, 0xFF, 0x00, 0x00, 0xBF, // NR40-NR44
+    0x00, 0x00, 0x70,             // NR50-NR52
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+];
+
+// The frame sequencer is clocked at 512 Hz by the falling edge of DIV bit 4
+const FRAME_SEQUENCER_BIT: u16 = 1 << 12;
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct APU {
+    enabled: bool,
+    channel1: SquareChannel,
+    channel2: SquareChannel,
+    channel3: WaveChannel,
+    channel4: NoiseChannel,
+    master_volume: u8, // NR50
+    panning: u8,       // NR51
+    frame_step: u8,
+    frame_sequencer_bit: bool,
+    // Host side audio output, only created once the frontend asks for samples
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    resampler: Option<Resampler>,
+}
+
+impl APU {
+    pub fn new() -> APU {
+        APU {
+            enabled: false,
+            channel1: SquareChannel::new(true),
+            channel2: SquareChannel::new(false),
+            channel3: WaveChannel::new(),
+            channel4: NoiseChannel::new(),
+            master_volume: 0,
+            panning: 0,
+            frame_step: 0,
+            frame_sequencer_bit: false,
+            resampler: None,
+        }
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.resampler = Some(Resampler::new(sample_rate));
+    }
+
+    // Mimics the capacitor that blocks the DC offset of the DACs, on by default
+    pub fn set_high_pass(&mut self, enabled: bool) {
+        if let Some(resampler) = &mut self.resampler {
+            resampler.set_high_pass(enabled);
+        }
+    }
+
+    pub fn samples_available(&self) -> usize {
+        self.resampler.as_ref().map_or(0, |resampler| resampler.samples_available())
+    }
+
+    // Appends the pending samples, interleaved left/right, to the given buffer
+    pub fn drain_samples(&mut self, samples: &mut Vec<f32>) {
+        if let Some(resampler) = &mut self.resampler {
+            resampler.drain_samples(samples);
+        }
+    }
+
+    // Save states don't carry the host audio output, a restored APU keeps the current one
+    #[cfg(feature = "serialize")]
+    pub fn take_audio_output(&mut self, other: &mut APU) {
+        self.resampler = other.resampler.take();
+    }
+
+    pub fn step(&mut self, cycles: u8, system_counter: u16) {
+        let frame_sequencer_bit = (system_counter & FRAME_SEQUENCER_BIT) != 0;
+        if self.frame_sequencer_bit && !frame_sequencer_bit {
+            self.frame_sequencer_step();
+        }
+        self.frame_sequencer_bit = frame_sequencer_bit;
+
+        if self.resampler.is_none() {
+            self.step_channels(cycles);
+            return;
+        }
+        // The output is sampled every M-cycle, even while the APU is off
+        let mut cycles = cycles;
+        while cycles > 0 {
+            let m_cycle = cycles.min(4);
+            cycles -= m_cycle;
+            self.step_channels(m_cycle);
+            let (left, right) = self.output();
+            if let Some(resampler) = &mut self.resampler {
+                resampler.push(left, right);
+            }
+        }
+    }
+
+    fn step_channels(&mut self, cycles: u8) {
+        if !self.enabled {
+            return;
+        }
+        self.channel1.step(cycles);
+        self.channel2.step(cycles);
+        self.channel3.step(cycles);
+        self.channel4.step(cycles);
+    }
+
+    fn frame_sequencer_step(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        match self.frame_step {
+            0 | 4 => self.clock_lengths(),
+            2 | 6 => {
+                self.clock_lengths();
+                self.channel1.clock_sweep();
+            }
+            7 => {
+                self.channel1.clock_envelope();
+                self.channel2.clock_envelope();
+                self.channel4.clock_envelope();
+            }
+            _ => {}
+        }
+        self.frame_step = (self.frame_step + 1) & 0x07;
+    }
+
+    fn clock_lengths(&mut self) {
+        self.channel1.clock_length();
+        self.channel2.clock_length();
+        self.channel3.clock_length();
+        self.channel4.clock_length();
+    }
+
+    // True when the next frame sequencer step won't clock the length counters
+    fn first_half(&self) -> bool {
+        self.frame_step % 2 == 1
+    }
+
+    pub fn apu_read(&self, address: u16) -> u8 {
+        let value = match address {
+            0xFF10..=0xFF14 => self.channel1.read(address - 0xFF10),
+            0xFF15..=0xFF19 => self.channel2.read(address - 0xFF15),
+            0xFF1A..=0xFF1E => self.channel3.read(address - 0xFF1A),
+            0xFF1F => 0,
+            0xFF20..=0xFF23 => self.channel4.read(address - 0xFF1F),
+            0xFF24 => self.master_volume,
+            0xFF25 => self.panning,
+            0xFF26 => {
+                (if self.enabled { 0x80 } else { 0 })
+                    | (if self.channel4.enabled { 0x08 } else { 0 })
+                    | (if self.channel3.enabled { 0x04 } else { 0 })
+                    | (if self.channel2.enabled { 0x02 } else { 0 })
+                    | (if self.channel1.enabled { 0x01 } else { 0 })
+            }
+            0xFF27..=0xFF2F => 0,
+            0xFF30..=0xFF3F => return self.channel3.wave_ram_read(address as usize - 0xFF30),
+            _ => panic!("APU read address not implemented: {:04X}", address)
+        };
+        value | READ_MASKS[address as usize - 0xFF10]
+    }
+
+    pub fn apu_write(&mut self, address: u16, value: u8) {
+        if address == 0xFF26 {
+            self.set_power((value & 0x80) != 0);
+            return;
+        }
+        if let 0xFF30..=0xFF3F = address {
+            self.channel3.wave_ram_write(address as usize - 0xFF30, value);
+            return;
+        }
+        if !self.enabled {
+            // While powered off the registers ignore writes, except the DMG length counters
+            match address {
+                0xFF11 => self.channel1.length.load(value & 0x3F),
+                0xFF16 => self.channel2.length.load(value & 0x3F),
+                0xFF1B => self.channel3.length.load(value),
+                0xFF20 => self.channel4.length.load(value & 0x3F),
+                _ => {}
+            }
+            return;
+        }
+
+        let first_half = self.first_half();
+        match address {
+            0xFF10..=0xFF14 => self.channel1.write(address - 0xFF10, value, first_half),
+            0xFF15..=0xFF19 => self.channel2.write(address - 0xFF15, value, first_half),
+            0xFF1A..=0xFF1E => self.channel3.write(address - 0xFF1A, value, first_half),
+            0xFF1F => {}
+            0xFF20..=0xFF23 => self.channel4.write(address - 0xFF1F, value, first_half),
+            0xFF24 => self.master_volume = value,
+            0xFF25 => self.panning = value,
+            0xFF27..=0xFF2F => {}
+            _ => panic!("APU write address not implemented: {:04X}", address)
+        }
+    }
+
+    fn set_power(&mut self, enabled: bool) {
+        if self.enabled && !enabled {
+            self.channel1.power_off();
+            self.channel2.power_off();
+            self.channel3.power_off();
+            self.channel4.power_off();
+            self.master_volume = 0;
+            self.panning = 0;
+        } else if !self.enabled && enabled {
+            self.frame_step = 0;
+        }
+        self.enabled = enabled;
+    }
+
+    // Current left and right output in the -1.0..=1.0 range. Each DAC turns its 0-15
+    // input into an analog level, NR51 routes them and NR50 scales both sides.
+    pub fn output(&self) -> (f32, f32) {
+        if !self.enabled {
+            return (0.0, 0.0);
+        }
+        let channels = [
+            (self.channel1.dac_enabled(), self.channel1.output()),
+            (self.channel2.dac_enabled(), self.channel2.output()),
+            (self.channel3.dac_enabled(), self.channel3.output()),
+            (self.channel4.dac_enabled(), self.channel4.output()),
+        ];
+
+        let mut left = 0.0;
+        let mut right = 0.0;
+        for (index, (dac_enabled, output)) in channels.iter().enumerate() {
+            if !dac_enabled {
+                continue;
+            }
+            let analog = 1.0 - *output as f32 / 7.5;
+            if (self.panning & (0x10 << index)) != 0 {
+                left += analog;
+            }
+            if (self.panning & (0x01 << index)) != 0 {
+                right += analog;
+            }
+        }
+
+        let left_volume = ((self.master_volume >> 4) & 0x07) as f32 + 1.0;
+        let right_volume = (self.master_volume & 0x07) as f32 + 1.0;
+        (left / 4.0 * left_volume / 8.0, right / 4.0 * right_volume / 8.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs the frame sequencer for the given number of 512 Hz steps
+    fn run_frame_sequencer(apu: &mut APU, steps: usize) {
+        for _ in 0..steps {
+            apu.step(0, FRAME_SEQUENCER_BIT);
+            apu.step(0, 0);
+        }
+    }
+
+    #[test]
+    fn test_register_read_masks() {
+        let mut apu = APU::new();
+        apu.apu_write(0xFF26, 0x80);
+        for address in 0xFF10..=0xFF25 {
+            apu.apu_write(address, 0x00);
+        }
+        let expected = [
+            0x80, 0x3F, 0x00, 0xFF, 0xBF, 0xFF, 0x3F, 0x00, 0xFF, 0xBF, 0x7F,
+            0xFF, 0x9F, 0xFF, 0xBF, 0xFF, 0xFF, 0x00, 0x00, 0xBF, 0x00, 0x00,
+        ];
+        for (offset, value) in expected.iter().enumerate() {
+            assert_eq!(apu.apu_read(0xFF10 + offset as u16), *value);
+        }
+        assert_eq!(apu.apu_read(0xFF26), 0xF0);
+        assert_eq!(apu.apu_read(0xFF27), 0xFF);
+    }
+
+    #[test]
+    fn test_power_off_clears_registers() {
+        let mut apu = APU::new();
+        apu.apu_write(0xFF26, 0x80);
+        apu.apu_write(0xFF12, 0xF3);
+        apu.apu_write(0xFF25, 0xFF);
+        apu.apu_write(0xFF30, 0x12);
+
+        apu.apu_write(0xFF26, 0x00);
+        assert_eq!(apu.apu_read(0xFF26), 0x70);
+        apu.apu_write(0xFF12, 0xF3);
+        assert_eq!(apu.apu_read(0xFF12), 0x00);
+        assert_eq!(apu.apu_read(0xFF25), 0x00);
+        assert_eq!(apu.apu_read(0xFF30), 0x12);
+    }
+
+    #[test]
+    fn test_length_counter_disables_channel() {
+        let mut apu = APU::new();
+        apu.apu_write(0xFF26, 0x80);
+        apu.apu_write(0xFF12, 0xF0);
+        apu.apu_write(0xFF11, 0x3E); // length of 2
+        apu.apu_write(0xFF14, 0xC0);
+        assert_eq!(apu.apu_read(0xFF26) & 0x01, 0x01);
+
+        // Steps 0 and 2 clock the length counter
+        run_frame_sequencer(&mut apu, 2);
+        assert_eq!(apu.apu_read(0xFF26) & 0x01, 0x01);
+        run_frame_sequencer(&mut apu, 1);
+        assert_eq!(apu.apu_read(0xFF26) & 0x01, 0x00);
+    }
+
+    #[test]
+    fn test_sweep_overflow_disables_channel() {
+        let mut apu = APU::new();
+        apu.apu_write(0xFF26, 0x80);
+        apu.apu_write(0xFF12, 0xF0);
+        apu.apu_write(0xFF10, 0x11); // period 1, increase, shift 1
+        apu.apu_write(0xFF13, 0x00);
+        apu.apu_write(0xFF14, 0x85); // frequency 0x500
+        assert_eq!(apu.apu_read(0xFF26) & 0x01, 0x01);
+
+        // 0x500 -> 0x780, whose next value 0xB40 overflows
+        run_frame_sequencer(&mut apu, 3);
+        assert_eq!(apu.apu_read(0xFF26) & 0x01, 0x00);
+    }
+
+    #[test]
+    fn test_dac_off_silences_channel() {
+        let mut apu = APU::new();
+        apu.apu_write(0xFF26, 0x80);
+        apu.apu_write(0xFF1A, 0x80);
+        apu.apu_write(0xFF1E, 0x80);
+        assert_eq!(apu.apu_read(0xFF26) & 0x04, 0x04);
+        apu.apu_write(0xFF1A, 0x00);
+        assert_eq!(apu.apu_read(0xFF26) & 0x04, 0x00);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::io::apu::envelope::Envelope;
+use crate::io::apu::length::LengthCounter;
+
+const DIVISORS: [u32; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct NoiseChannel {
+    pub enabled: bool,
+    clock_shift: u8,
+    width_mode: bool, // 7 bit LFSR instead of 15
+    divisor_code: u8,
+    timer: u32,
+    lfsr: u16,
+    pub length: LengthCounter,
+    envelope: Envelope,
+}
+
+impl NoiseChannel {
+    pub fn new() -> NoiseChannel {
+        NoiseChannel {
+            enabled: false,
+            clock_shift: 0,
+            width_mode: false,
+            divisor_code: 0,
+            timer: DIVISORS[0],
+            lfsr: 0x7FFF,
+            length: LengthCounter::new(64),
+            envelope: Envelope::new(),
+        }
+    }
+
+    pub fn read(&self, register: u16) -> u8 {
+        match register {
+            1 => 0,
+            2 => self.envelope.read(),
+            3 => self.clock_shift << 4 | (if self.width_mode { 0x08 } else { 0 }) | self.divisor_code,
+            4 => if self.length.enabled { 0x40 } else { 0 },
+            _ => panic!("Noise channel register not implemented: {}", register)
+        }
+    }
+
+    pub fn write(&mut self, register: u16, value: u8, first_half: bool) {
+        match register {
+            1 => self.length.load(value & 0x3F),
+            2 => {
+                self.envelope.write(value);
+                if !self.envelope.dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => {
+                self.clock_shift = value >> 4;
+                self.width_mode = (value & 0x08) != 0;
+                self.divisor_code = value & 0x07;
+            }
+            4 => {
+                if self.length.set_enabled((value & 0x40) != 0, first_half) {
+                    self.enabled = false;
+                }
+                if (value & 0x80) != 0 {
+                    self.enabled = self.envelope.dac_enabled();
+                    self.timer = self.period();
+                    self.lfsr = 0x7FFF;
+                    self.length.trigger(first_half);
+                    self.envelope.trigger();
+                }
+            }
+            _ => panic!("Noise channel register not implemented: {}", register)
+        }
+    }
+
+    // Clears every register on power off, DMG length counters keep their value
+    pub fn power_off(&mut self) {
+        let mut length = self.length;
+        length.enabled = false;
+        *self = NoiseChannel::new();
+        self.length = length;
+    }
+
+    fn period(&self) -> u32 {
+        DIVISORS[self.divisor_code as usize] << self.clock_shift
+    }
+
+    pub fn step(&mut self, cycles: u8) {
+        let mut cycles = cycles as u32;
+        while cycles >= self.timer {
+            cycles -= self.timer;
+            self.timer = self.period();
+            // Shifts of 14 and 15 don't clock the LFSR at all
+            if self.clock_shift < 14 {
+                self.clock_lfsr();
+            }
+        }
+        self.timer -= cycles;
+    }
+
+    fn clock_lfsr(&mut self) {
+        let bit = (self.lfsr & 0x01) ^ ((self.lfsr >> 1) & 0x01);
+        self.lfsr = (self.lfsr >> 1) | (bit << 14);
+        if self.width_mode {
+            self.lfsr = (self.lfsr & !0x40) | (bit << 6);
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn dac_enabled(&self) -> bool {
+        self.envelope.dac_enabled()
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.enabled || (self.lfsr & 0x01) != 0 {
+            return 0;
+        }
+        self.envelope.volume
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+// The APU output is sampled once per M-cycle
+pub const NATIVE_SAMPLE_RATE: u32 = 1048576;
+
+const KERNEL_SIZE: usize = 16;
+const PHASES: usize = 64;
+const CUTOFF: f64 = 0.45; // relative to the output sample rate
+
+// Charge factor of the DMG high-pass capacitor for every 4 MiHz clock
+const CAPACITOR_CHARGE_FACTOR: f64 = 0.999958;
+
+// Band-limited resampler in the style of blip_buf: each change of the input level is
+// added to the output as a windowed-sinc step placed at its exact fractional time, so
+// the square waves don't alias when brought down to the host rate.
+pub struct Resampler {
+    kernel: Vec<[f32; KERNEL_SIZE]>,
+    sample_rate: u32,
+    ratio: f64, // output samples per native sample
+    time: f64,  // position of the next native sample within the current output sample
+    level: [f32; 2],
+    pending: [[f32; KERNEL_SIZE + 1]; 2], // deltas for the upcoming output samples
+    integrator: [f32; 2],
+    high_pass: Option<HighPass>,
+    samples: VecDeque<f32>, // interleaved left/right
+    capacity: usize,
+}
+
+struct HighPass {
+    charge_factor: f32,
+    capacitor: [f32; 2],
+}
+
+impl HighPass {
+    fn new(sample_rate: u32) -> HighPass {
+        HighPass {
+            charge_factor: CAPACITOR_CHARGE_FACTOR.powf(4194304.0 / sample_rate as f64) as f32,
+            capacitor: [0.0; 2],
+        }
+    }
+}
+
+impl Resampler {
+    pub fn new(sample_rate: u32) -> Resampler {
+        Resampler {
+            kernel: build_kernel(),
+            sample_rate,
+            ratio: sample_rate as f64 / NATIVE_SAMPLE_RATE as f64,
+            time: 0.0,
+            level: [0.0; 2],
+            pending: [[0.0; KERNEL_SIZE + 1]; 2],
+            integrator: [0.0; 2],
+            high_pass: Some(HighPass::new(sample_rate)),
+            // Up to a second of audio is kept if the frontend doesn't keep up
+            samples: VecDeque::with_capacity(2 * sample_rate as usize),
+            capacity: 2 * sample_rate as usize,
+        }
+    }
+
+    pub fn set_high_pass(&mut self, enabled: bool) {
+        self.high_pass = if enabled { Some(HighPass::new(self.sample_rate)) } else { None };
+    }
+
+    pub fn push(&mut self, left: f32, right: f32) {
+        let phase = ((self.time * PHASES as f64) as usize).min(PHASES - 1);
+        for (channel, value) in [left, right].into_iter().enumerate() {
+            let delta = value - self.level[channel];
+            if delta != 0.0 {
+                self.level[channel] = value;
+                for (pending, weight) in self.pending[channel].iter_mut().zip(self.kernel[phase].iter()) {
+                    *pending += delta * weight;
+                }
+            }
+        }
+
+        self.time += self.ratio;
+        while self.time >= 1.0 {
+            self.time -= 1.0;
+            self.emit();
+        }
+    }
+
+    fn emit(&mut self) {
+        if self.samples.len() + 2 > self.capacity {
+            self.samples.drain(..2);
+        }
+        for channel in 0..2 {
+            self.integrator[channel] += self.pending[channel][0];
+            self.pending[channel].rotate_left(1);
+            self.pending[channel][KERNEL_SIZE] = 0.0;
+
+            let mut sample = self.integrator[channel];
+            if let Some(high_pass) = &mut self.high_pass {
+                let output = sample - high_pass.capacitor[channel];
+                high_pass.capacitor[channel] = sample - output * high_pass.charge_factor;
+                sample = output;
+            }
+            self.samples.push_back(sample);
+        }
+    }
+
+    // Number of stereo samples waiting to be drained
+    pub fn samples_available(&self) -> usize {
+        self.samples.len() / 2
+    }
+
+    pub fn drain_samples(&mut self, samples: &mut Vec<f32>) {
+        samples.extend(self.samples.drain(..));
+    }
+}
+
+// Windowed sinc impulses, one per fractional position. Integrating them when the
+// samples are emitted turns each one into a band-limited step.
+fn build_kernel() -> Vec<[f32; KERNEL_SIZE]> {
+    let half = (KERNEL_SIZE / 2) as f64;
+    (0..PHASES)
+        .map(|phase| {
+            let center = half - 1.0 + phase as f64 / PHASES as f64;
+            let mut taps = [0.0; KERNEL_SIZE];
+            for (index, tap) in taps.iter_mut().enumerate() {
+                let t = index as f64 - center;
+                let sinc = if t == 0.0 {
+                    1.0
+                } else {
+                    (PI * 2.0 * CUTOFF * t).sin() / (PI * 2.0 * CUTOFF * t)
+                };
+                let window = if t.abs() >= half {
+                    0.0
+                } else {
+                    0.42 + 0.5 * (PI * t / half).cos() + 0.08 * (2.0 * PI * t / half).cos()
+                };
+                *tap = sinc * window;
+            }
+            let sum: f64 = taps.iter().sum();
+            let mut kernel = [0.0; KERNEL_SIZE];
+            for (weight, tap) in kernel.iter_mut().zip(taps.iter()) {
+                *weight = (tap / sum) as f32;
+            }
+            kernel
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_rate() {
+        let mut resampler = Resampler::new(48000);
+        for _ in 0..NATIVE_SAMPLE_RATE {
+            resampler.push(0.0, 0.0);
+        }
+        assert!((47999..=48000).contains(&resampler.samples_available()));
+    }
+
+    #[test]
+    fn test_step_settles_to_input_level() {
+        let mut resampler = Resampler::new(44100);
+        resampler.set_high_pass(false);
+        for _ in 0..1000 {
+            resampler.push(0.5, -0.25);
+        }
+        let mut samples = Vec::new();
+        resampler.drain_samples(&mut samples);
+        let (left, right) = (samples[samples.len() - 2], samples[samples.len() - 1]);
+        assert!((left - 0.5).abs() < 0.001);
+        assert!((right + 0.25).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_high_pass_removes_dc() {
+        let mut resampler = Resampler::new(44100);
+        for _ in 0..NATIVE_SAMPLE_RATE {
+            resampler.push(0.5, 0.5);
+        }
+        let mut samples = Vec::new();
+        resampler.drain_samples(&mut samples);
+        assert!(samples[samples.len() - 1].abs() < 0.01);
+    }
+}
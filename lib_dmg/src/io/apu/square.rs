@@ -0,0 +1,215 @@
+use crate::io::apu::envelope::Envelope;
+use crate::io::apu::length::LengthCounter;
+
+const DUTY_PATTERNS: [[u8; 8]; 4] = [
+    [0, 0, 0, 0, 0, 0, 0, 1], // 12.5%
+    [1, 0, 0, 0, 0, 0, 0, 1], // 25%
+    [1, 0, 0, 0, 0, 1, 1, 1], // 50%
+    [0, 1, 1, 1, 1, 1, 1, 0], // 75%
+];
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Sweep {
+    period: u8,
+    negate: bool,
+    shift: u8,
+    timer: u8,
+    enabled: bool,
+    shadow_frequency: u16,
+    negate_used: bool, // a subtraction happened since the last trigger
+}
+
+impl Sweep {
+    fn new() -> Sweep {
+        Sweep {
+            period: 0,
+            negate: false,
+            shift: 0,
+            timer: 0,
+            enabled: false,
+            shadow_frequency: 0,
+            negate_used: false,
+        }
+    }
+
+    fn reload_timer(&mut self) {
+        self.timer = if self.period == 0 { 8 } else { self.period };
+    }
+
+    // Returns None when the new frequency overflows, which disables the channel
+    fn next_frequency(&mut self) -> Option<u16> {
+        let delta = self.shadow_frequency >> self.shift;
+        let frequency = if self.negate {
+            self.negate_used = true;
+            self.shadow_frequency - delta
+        } else {
+            self.shadow_frequency + delta
+        };
+        if frequency > 2047 { None } else { Some(frequency) }
+    }
+}
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct SquareChannel {
+    pub enabled: bool,
+    duty: u8,
+    duty_position: u8,
+    frequency: u16,
+    timer: u16,
+    pub length: LengthCounter,
+    envelope: Envelope,
+    sweep: Option<Sweep>, // only channel 1 has one
+}
+
+impl SquareChannel {
+    pub fn new(has_sweep: bool) -> SquareChannel {
+        SquareChannel {
+            enabled: false,
+            duty: 0,
+            duty_position: 0,
+            frequency: 0,
+            timer: 2048 * 4,
+            length: LengthCounter::new(64),
+            envelope: Envelope::new(),
+            sweep: if has_sweep { Some(Sweep::new()) } else { None },
+        }
+    }
+
+    pub fn read(&self, register: u16) -> u8 {
+        match register {
+            0 => match &self.sweep {
+                Some(sweep) => sweep.period << 4 | (if sweep.negate { 0x08 } else { 0 }) | sweep.shift,
+                None => 0,
+            },
+            1 => self.duty << 6,
+            2 => self.envelope.read(),
+            3 => 0,
+            4 => if self.length.enabled { 0x40 } else { 0 },
+            _ => panic!("Square channel register not implemented: {}", register)
+        }
+    }
+
+    pub fn write(&mut self, register: u16, value: u8, first_half: bool) {
+        match register {
+            0 => {
+                if let Some(sweep) = &mut self.sweep {
+                    sweep.period = (value >> 4) & 0x07;
+                    sweep.negate = (value & 0x08) != 0;
+                    sweep.shift = value & 0x07;
+                    // Leaving negate mode after a subtraction was used disables the channel
+                    if !sweep.negate && sweep.negate_used {
+                        self.enabled = false;
+                    }
+                }
+            }
+            1 => {
+                self.duty = value >> 6;
+                self.length.load(value & 0x3F);
+            }
+            2 => {
+                self.envelope.write(value);
+                if !self.envelope.dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => self.frequency = (self.frequency & 0x0700) | value as u16,
+            4 => {
+                self.frequency = (self.frequency & 0x00FF) | (((value & 0x07) as u16) << 8);
+                if self.length.set_enabled((value & 0x40) != 0, first_half) {
+                    self.enabled = false;
+                }
+                if (value & 0x80) != 0 {
+                    self.trigger(first_half);
+                }
+            }
+            _ => panic!("Square channel register not implemented: {}", register)
+        }
+    }
+
+    // Clears every register on power off, DMG length counters keep their value
+    pub fn power_off(&mut self) {
+        let mut length = self.length;
+        length.enabled = false;
+        *self = SquareChannel::new(self.sweep.is_some());
+        self.length = length;
+    }
+
+    fn trigger(&mut self, first_half: bool) {
+        self.enabled = self.envelope.dac_enabled();
+        self.timer = self.period();
+        self.length.trigger(first_half);
+        self.envelope.trigger();
+
+        if let Some(sweep) = &mut self.sweep {
+            sweep.shadow_frequency = self.frequency;
+            sweep.reload_timer();
+            sweep.enabled = sweep.period != 0 || sweep.shift != 0;
+            sweep.negate_used = false;
+            if sweep.shift != 0 && sweep.next_frequency().is_none() {
+                self.enabled = false;
+            }
+        }
+    }
+
+    fn period(&self) -> u16 {
+        (2048 - self.frequency) * 4
+    }
+
+    pub fn step(&mut self, cycles: u8) {
+        let mut cycles = cycles as u16;
+        while cycles >= self.timer {
+            cycles -= self.timer;
+            self.timer = self.period();
+            self.duty_position = (self.duty_position + 1) & 0x07;
+        }
+        self.timer -= cycles;
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn clock_sweep(&mut self) {
+        let sweep = match &mut self.sweep {
+            Some(sweep) => sweep,
+            None => return,
+        };
+        sweep.timer = sweep.timer.saturating_sub(1);
+        if sweep.timer != 0 {
+            return;
+        }
+        sweep.reload_timer();
+        if !sweep.enabled || sweep.period == 0 {
+            return;
+        }
+        match sweep.next_frequency() {
+            Some(frequency) if sweep.shift != 0 => {
+                sweep.shadow_frequency = frequency;
+                self.frequency = frequency;
+                // The new frequency is checked for overflow again straight away
+                if sweep.next_frequency().is_none() {
+                    self.enabled = false;
+                }
+            }
+            Some(_) => {}
+            None => self.enabled = false,
+        }
+    }
+
+    pub fn dac_enabled(&self) -> bool {
+        self.envelope.dac_enabled()
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.enabled {
+            return 0;
+        }
+        DUTY_PATTERNS[self.duty as usize][self.duty_position as usize] * self.envelope.volume
+    }
+}
@@ -0,0 +1,132 @@
+use crate::io::apu::length::LengthCounter;
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct WaveChannel {
+    pub enabled: bool,
+    dac_enabled: bool,
+    volume_code: u8,
+    frequency: u16,
+    timer: u16,
+    position: u8, // 32 4-bit samples, high nibble first
+    sample: u8,
+    pub length: LengthCounter,
+    wave_ram: [u8; 16],
+}
+
+impl WaveChannel {
+    pub fn new() -> WaveChannel {
+        WaveChannel {
+            enabled: false,
+            dac_enabled: false,
+            volume_code: 0,
+            frequency: 0,
+            timer: 2048 * 2,
+            position: 0,
+            sample: 0,
+            length: LengthCounter::new(256),
+            wave_ram: [0; 16],
+        }
+    }
+
+    pub fn read(&self, register: u16) -> u8 {
+        match register {
+            0 => if self.dac_enabled { 0x80 } else { 0 },
+            1 => 0,
+            2 => self.volume_code << 5,
+            3 => 0,
+            4 => if self.length.enabled { 0x40 } else { 0 },
+            _ => panic!("Wave channel register not implemented: {}", register)
+        }
+    }
+
+    pub fn write(&mut self, register: u16, value: u8, first_half: bool) {
+        match register {
+            0 => {
+                self.dac_enabled = (value & 0x80) != 0;
+                if !self.dac_enabled {
+                    self.enabled = false;
+                }
+            }
+            1 => self.length.load(value),
+            2 => self.volume_code = (value >> 5) & 0x03,
+            3 => self.frequency = (self.frequency & 0x0700) | value as u16,
+            4 => {
+                self.frequency = (self.frequency & 0x00FF) | (((value & 0x07) as u16) << 8);
+                if self.length.set_enabled((value & 0x40) != 0, first_half) {
+                    self.enabled = false;
+                }
+                if (value & 0x80) != 0 {
+                    self.enabled = self.dac_enabled;
+                    // The first sample is delayed by 6 cycles after a trigger
+                    self.timer = self.period() + 6;
+                    self.position = 0;
+                    self.length.trigger(first_half);
+                }
+            }
+            _ => panic!("Wave channel register not implemented: {}", register)
+        }
+    }
+
+    // While the channel plays, the CPU only sees the byte the channel is reading
+    pub fn wave_ram_read(&self, index: usize) -> u8 {
+        if self.enabled {
+            self.wave_ram[self.position as usize / 2]
+        } else {
+            self.wave_ram[index]
+        }
+    }
+
+    pub fn wave_ram_write(&mut self, index: usize, value: u8) {
+        if self.enabled {
+            self.wave_ram[self.position as usize / 2] = value;
+        } else {
+            self.wave_ram[index] = value;
+        }
+    }
+
+    // Clears every register on power off, wave RAM and the length counter are kept
+    pub fn power_off(&mut self) {
+        let mut length = self.length;
+        length.enabled = false;
+        let wave_ram = self.wave_ram;
+        *self = WaveChannel::new();
+        self.length = length;
+        self.wave_ram = wave_ram;
+    }
+
+    fn period(&self) -> u16 {
+        (2048 - self.frequency) * 2
+    }
+
+    pub fn step(&mut self, cycles: u8) {
+        let mut cycles = cycles as u16;
+        while cycles >= self.timer {
+            cycles -= self.timer;
+            self.timer = self.period();
+            self.position = (self.position + 1) & 0x1F;
+            let byte = self.wave_ram[self.position as usize / 2];
+            self.sample = if (self.position & 0x01) == 0 { byte >> 4 } else { byte & 0x0F };
+        }
+        self.timer -= cycles;
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn dac_enabled(&self) -> bool {
+        self.dac_enabled
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.enabled {
+            return 0;
+        }
+        match self.volume_code {
+            0 => 0,
+            code => self.sample >> (code - 1),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct InterruptFlags {
     pub vblank: bool,
     pub lcdstat: bool,
@@ -1,13 +1,13 @@
 use crate::utils::bit;
 
-#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Column {
     Zero,
     One,
 }
 
-#[cfg_attr(feature = "serialize", derive(Serialize))]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug)]
 pub struct Joypad {
     pub column: Column,
@@ -19,6 +19,7 @@ pub struct Joypad {
     pub up: bool,
     pub left: bool,
     pub right: bool,
+    lines: u8, // Selected lines as last seen by falling_edge
 }
 
 impl Joypad {
@@ -33,6 +34,7 @@ impl Joypad {
             up: false,
             left: false,
             right: false,
+            lines: 0x0F,
         }
     }
 
@@ -59,6 +61,22 @@ impl Joypad {
         column_bit | row_bits
     }
 
+    // True when a pressed button pulls one of the selected lines low, this is what
+    // wakes the CPU up from STOP
+    pub fn any_line_low(&self) -> bool {
+        self.to_byte() & 0x0F != 0x0F
+    }
+
+    // True when one of the selected lines went from high to low since the last call,
+    // either from a press or from selecting a column where a button is held. That's
+    // what requests the joypad interrupt
+    pub fn falling_edge(&mut self) -> bool {
+        let lines = self.to_byte() & 0x0F;
+        let falling = self.lines & !lines != 0;
+        self.lines = lines;
+        falling
+    }
+
     fn reading_column_0(&self) -> bool {
         self.column == Column::Zero
     }
@@ -1,16 +1,24 @@
+use crate::io::apu::APU;
 use crate::io::interrupt::InterruptFlags;
 use crate::io::joypad::Joypad;
+use crate::io::serial::Serial;
 use crate::io::timer::Timer;
 
+mod apu;
 mod timer;
 mod interrupt;
 mod joypad;
+mod serial;
 
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct IO {
     pub timer: Timer,
     pub interrupt_flag: InterruptFlags,
     pub interrupt_enable: InterruptFlags,
     pub joypad: Joypad,
+    pub serial: Serial,
+    pub apu: APU,
+    #[cfg_attr(feature = "serialize", serde(with = "crate::utils::byte_array"))]
     io: [u8; 0x80]
 }
 
@@ -21,6 +29,8 @@ impl IO {
             interrupt_flag: InterruptFlags::new(),
             interrupt_enable: InterruptFlags::new(),
             joypad: Joypad::new(),
+            serial: Serial::new(),
+            apu: APU::new(),
             io: [0;0x80]
         }
     }
@@ -28,8 +38,9 @@ impl IO {
     pub fn io_read(&mut self, mut address: u16) -> u8 {
         match address {
             0xFF00 => self.joypad.to_byte(),
+            0xFF01..=0xFF02 => self.serial.serial_read(address),
             0xFF04..=0xFF07 => self.timer.timer_read(address),
-            0xFF10..=0xFF26 => 0,
+            0xFF10..=0xFF3F => self.apu.apu_read(address),
             0xFF0F => self.interrupt_flag.to_byte(),
             0xFFFF => self.interrupt_enable.to_byte(),
             _ => {
@@ -48,12 +59,16 @@ impl IO {
                     joypad::Column::Zero
                 };
             }
-            0xFF01..=0xFF02 => {/* SERIAL */}
+            0xFF01..=0xFF02 => self.serial.serial_write(address, value),
 
-            0xFF04..=0xFF07 => self.timer.timer_write(address, value),
+            0xFF04..=0xFF07 => {
+                if self.timer.timer_write(address, value) {
+                    self.interrupt_flag.timer = true;
+                }
+            }
 
             0xFF0F => self.interrupt_flag.from_byte(value),
-            0xFF10..=0xFF3F => { /* Normally for sound */ }
+            0xFF10..=0xFF3F => self.apu.apu_write(address, value),
             0xFF51..=0xFF7F => { /* Gameboy color */ }
 
             0xFFFF => self.interrupt_enable.from_byte(value),
@@ -0,0 +1,91 @@
+// Serial port with nothing plugged in. A transfer on the internal clock still
+// shifts its 8 bits out, and shifts in 1s since nobody drives the line. Transfers
+// waiting on an external clock never finish
+const CYCLES_PER_BIT: u16 = 512; // 8192 Hz
+
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Serial {
+    data: u8,
+    control: u8,
+    bits_left: u8,
+    cycles: u16,
+}
+
+impl Serial {
+    pub fn new() -> Serial {
+        Serial {
+            data: 0,
+            control: 0,
+            bits_left: 0,
+            cycles: 0,
+        }
+    }
+
+    // Returns true when a transfer completes, which requests the serial interrupt
+    pub fn step(&mut self, cpu_cycles: u8) -> bool {
+        if self.bits_left == 0 {
+            return false;
+        }
+        self.cycles += cpu_cycles as u16;
+        while self.cycles >= CYCLES_PER_BIT && self.bits_left > 0 {
+            self.cycles -= CYCLES_PER_BIT;
+            self.data = (self.data << 1) | 1;
+            self.bits_left -= 1;
+        }
+        if self.bits_left == 0 {
+            self.control &= 0x7F;
+            return true;
+        }
+        false
+    }
+
+    pub fn serial_read(&self, address: u16) -> u8 {
+        match address {
+            0xFF01 => self.data,
+            0xFF02 => self.control | 0x7E,
+            _ => panic!("Serial read address not implemented: {:04X}", address)
+        }
+    }
+
+    pub fn serial_write(&mut self, address: u16, value: u8) {
+        match address {
+            0xFF01 => self.data = value,
+            0xFF02 => {
+                self.control = value & 0x81;
+                let internal_clock = (value & 0x81) == 0x81;
+                self.bits_left = if internal_clock { 8 } else { 0 };
+                self.cycles = 0;
+            }
+            _ => panic!("Serial write address not implemented: {:04X}", address)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transfer_without_partner() {
+        let mut serial = Serial::new();
+        serial.serial_write(0xFF01, 0x42);
+        serial.serial_write(0xFF02, 0x81);
+
+        for _ in 0..(8 * CYCLES_PER_BIT / 4 - 1) {
+            assert!(!serial.step(4));
+        }
+        assert!(serial.step(4));
+        assert_eq!(serial.serial_read(0xFF01), 0xFF);
+        assert_eq!(serial.serial_read(0xFF02), 0x7F);
+    }
+
+    #[test]
+    fn test_external_clock_never_completes() {
+        let mut serial = Serial::new();
+        serial.serial_write(0xFF02, 0x80);
+        for _ in 0..0x1000 {
+            assert!(!serial.step(4));
+        }
+        assert_eq!(serial.serial_read(0xFF02), 0xFE);
+    }
+}
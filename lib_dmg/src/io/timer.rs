@@ -6,22 +6,23 @@ pub enum Frequency {
 }
 
 impl Frequency {
-    fn cycles_per_tick(&self) -> usize {
+    // TIMA is incremented on the falling edge of this bit of the system counter
+    fn counter_bit(&self) -> u16 {
         match self {
-            Frequency::F4096 => 1024,
-            Frequency::F16384 => 256,
-            Frequency::F262144 => 16,
-            Frequency::F65536 => 64,
+            Frequency::F4096 => 1 << 9,
+            Frequency::F16384 => 1 << 7,
+            Frequency::F262144 => 1 << 3,
+            Frequency::F65536 => 1 << 5,
         }
     }
 }
 
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Timer {
-    div: u16,     // Using u16 to ensure the DIV overflow behaves correctly
+    div: u16,     // 16 bit system counter incremented every cycle, DIV is its upper byte
     tima: u8,
     tma: u8,
     tac: u8,
-    cycles: usize,
 }
 
 impl Timer {
@@ -31,7 +32,6 @@ impl Timer {
             tima: 0,
             tma: 0,
             tac: 0,
-            cycles: 0,
         }
     }
 
@@ -39,53 +39,107 @@ impl Timer {
         self.div = 0xAC00;
     }
 
-    pub fn step(&mut self, cpu_cycles: u8) -> bool {
-        self.cycles += cpu_cycles as usize;
-
-        while self.cycles >= 256 {
-            self.cycles -= 256;
-            self.div.wrapping_add(1);
-        }
+    pub fn system_counter(&self) -> u16 {
+        self.div
+    }
 
-        if self.tac & 0x04 != 0 {
-            let freq = match self.tac & 0x03 {
-                0 => Frequency::F4096,
-                1 => Frequency::F262144,
-                2 => Frequency::F65536,
-                3 => Frequency::F16384,
-                _ => unreachable!(),
-            };
-
-            while self.cycles >= freq.cycles_per_tick() {
-                self.cycles -= freq.cycles_per_tick();
-                self.tima = self.tima.wrapping_add(1);
-
-                if self.tima == 0 {
-                    self.tima = self.tma;
-                    return true; //Fire interrupt
-                }
+    pub fn step(&mut self, cpu_cycles: u8) -> bool {
+        let mut interrupt = false;
+        for _ in 0..cpu_cycles {
+            let input = self.timer_input();
+            self.div = self.div.wrapping_add(1);
+            if input && !self.timer_input() {
+                interrupt |= self.increment_tima();
             }
         }
-        return false;
+        interrupt
     }
 
     pub fn timer_read(&mut self, address: u16) -> u8 {
         match address {
-            0xFF04 => (self.div & 0xFF) as u8,
+            0xFF04 => (self.div >> 8) as u8,
             0xFF05 => self.tima,
             0xFF06 => self.tma,
-            0xFF07 => self.tac,
-            _ => panic!("Timer read address not implemented: {:04X}", address)
+            0xFF07 => self.tac | 0xF8,
+            _ => 0xFF, // Not mapped
         }
     }
 
-    pub fn timer_write(&mut self, address: u16, value: u8) {
+    pub fn timer_write(&mut self, address: u16, value: u8) -> bool {
+        // Both resetting DIV and changing TAC can produce a falling edge on the timer
+        // input, which increments TIMA like a regular tick would.
+        let input = self.timer_input();
         match address {
             0xFF04 => self.div = 0,
             0xFF05 => self.tima = value,
             0xFF06 => self.tma = value,
-            0xFF07 => self.tac = value,
-            _ => panic!("Timer write address not implemented: {:04X}", address)
+            0xFF07 => self.tac = value & 0x07,
+            _ => (),
+        }
+        if input && !self.timer_input() {
+            return self.increment_tima();
+        }
+        false
+    }
+
+    fn frequency(&self) -> Frequency {
+        match self.tac & 0x03 {
+            0 => Frequency::F4096,
+            1 => Frequency::F262144,
+            2 => Frequency::F65536,
+            3 => Frequency::F16384,
+            _ => unreachable!(),
+        }
+    }
+
+    fn timer_input(&self) -> bool {
+        (self.tac & 0x04) != 0 && (self.div & self.frequency().counter_bit()) != 0
+    }
+
+    fn increment_tima(&mut self) -> bool {
+        self.tima = self.tima.wrapping_add(1);
+        if self.tima == 0 {
+            self.tima = self.tma;
+            return true; //Fire interrupt
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_div_counts_every_256_cycles() {
+        let mut timer = Timer::new();
+        timer.timer_write(0xFF04, 0);
+        for _ in 0..64 {
+            timer.step(4);
         }
+        assert_eq!(timer.timer_read(0xFF04), 1);
+    }
+
+    #[test]
+    fn test_tima_overflow_reloads_tma() {
+        let mut timer = Timer::new();
+        timer.timer_write(0xFF04, 0);
+        timer.timer_write(0xFF06, 0xF0);
+        timer.timer_write(0xFF05, 0xFF);
+        timer.timer_write(0xFF07, 0x05); // 262144 Hz, one tick every 16 cycles
+
+        assert!(!timer.step(15));
+        assert!(timer.step(1));
+        assert_eq!(timer.timer_read(0xFF05), 0xF0);
+    }
+
+    #[test]
+    fn test_div_reset_ticks_tima() {
+        let mut timer = Timer::new();
+        timer.timer_write(0xFF04, 0);
+        timer.timer_write(0xFF07, 0x05);
+        timer.step(8);
+        timer.timer_write(0xFF04, 0);
+        assert_eq!(timer.timer_read(0xFF05), 1);
     }
 }
@@ -1,7 +1,18 @@
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde;
+
 pub mod cpu;
 mod bus;
-mod cart;
+pub mod cart;
 mod ram;
 mod io;
 mod gpu;
 mod utils;
+pub mod error;
+#[cfg(feature = "serialize")]
+pub mod savestate;
+#[cfg(feature = "serialize")]
+pub mod rewind;
+
+pub use crate::gpu::Renderer;
@@ -1,7 +1,7 @@
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct RAM {
     wram: Vec<u8>,
     hram: Vec<u8>,
-    eram: Vec<u8>,
 }
 
 impl RAM {
@@ -9,43 +9,43 @@ impl RAM {
         RAM {
             wram: vec![0; 0x2000],
             hram: vec![0; 0x80],
-            eram: vec![0; 0x2000],
         }
     }
 
     pub fn ram_read(&mut self, mut address: u16) -> u8 {
         match address {
-            0xA000..=0xBFFF => {
-                address -= 0xA000;
-                self.eram[address as usize]
-            },
             0xC000..=0xDFFF => {
                 address -= 0xC000;
                 self.wram[address as usize]
             }
+            0xE000..=0xFDFF => {
+                // Echo RAM
+                address -= 0xE000;
+                self.wram[address as usize]
+            }
             0xFF80..=0xFFFE => {
                 address -= 0xFF80;
                 self.hram[address as usize]
             }
-            _ => panic!("RAM read address not implemented: {:04X}", address)
+            _ => 0xFF, // Not mapped
         }
     }
 
     pub fn ram_write(&mut self, mut address: u16, value: u8) {
         match address {
-            0xA000..=0xBFFF => {
-                address -= 0xA000;
-                self.eram[address as usize] = value
-            },
             0xC000..=0xDFFF => {
                 address -= 0xC000;
                 self.wram[address as usize] = value
             }
+            0xE000..=0xFDFF => {
+                address -= 0xE000;
+                self.wram[address as usize] = value
+            }
             0xFF80..=0xFFFE => {
                 address -= 0xFF80;
                 self.hram[address as usize] = value
             }
-            _ => panic!("RAM read address not implemented: {:04X}", address)
+            _ => (),
         }
     }
 }
\ No newline at end of file
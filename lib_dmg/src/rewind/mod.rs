@@ -0,0 +1,234 @@
+use std::collections::VecDeque;
+use crate::cpu::CPU;
+use crate::savestate::SaveStateError;
+
+// Only the newest snapshot is kept in full. Every older one is stored as the XOR
+// of itself with the snapshot that follows it, run-length encoded: between two
+// frames most of the machine doesn't change so those deltas are mostly zeros.
+pub struct RewindBuffer {
+    latest: Option<Vec<u8>>,
+    deltas: VecDeque<Vec<u8>>, // oldest first, deltas[i] rebuilds snapshot i from snapshot i + 1
+    frame_interval: usize,
+    frames_since_snapshot: usize,
+    memory_budget: usize,
+    memory_used: usize,
+}
+
+impl RewindBuffer {
+    // Takes a snapshot every frame_interval frames and drops the oldest ones once the
+    // stored snapshots take more than memory_budget bytes.
+    pub fn new(frame_interval: usize, memory_budget: usize) -> RewindBuffer {
+        RewindBuffer {
+            latest: None,
+            deltas: VecDeque::new(),
+            frame_interval: frame_interval.max(1),
+            frames_since_snapshot: 0,
+            memory_budget,
+            memory_used: 0,
+        }
+    }
+
+    pub fn memory_used(&self) -> usize {
+        self.memory_used
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+        self.frames_since_snapshot = 0;
+        self.memory_used = 0;
+    }
+
+    // To be called once per emulated frame
+    pub fn push_frame(&mut self, cpu: &CPU) {
+        self.frames_since_snapshot += 1;
+        if self.frames_since_snapshot < self.frame_interval {
+            return;
+        }
+        self.frames_since_snapshot = 0;
+
+        let snapshot = cpu.save_state();
+        self.memory_used += snapshot.len();
+        if let Some(previous) = self.latest.replace(snapshot) {
+            let delta = encode_delta(self.latest.as_ref().unwrap(), &previous);
+            self.memory_used += delta.len();
+            self.memory_used -= previous.len();
+            self.deltas.push_back(delta);
+        }
+
+        while self.memory_used > self.memory_budget {
+            match self.deltas.pop_front() {
+                Some(delta) => self.memory_used -= delta.len(),
+                None => break,
+            }
+        }
+    }
+
+    // Restores the most recent snapshot and forgets it, so calling this repeatedly
+    // walks back through the history one snapshot at a time. Returns false once
+    // there is nothing left to rewind to. If the snapshot can't be loaded, e.g. it was
+    // taken from another game, the whole history is dropped.
+    pub fn rewind_one_frame(&mut self, cpu: &mut CPU) -> Result<bool, SaveStateError> {
+        let snapshot = match self.latest.take() {
+            Some(snapshot) => snapshot,
+            None => return Ok(false),
+        };
+        if let Err(error) = cpu.load_state(&snapshot) {
+            self.clear();
+            return Err(error);
+        }
+
+        self.memory_used -= snapshot.len();
+        if let Some(delta) = self.deltas.pop_back() {
+            let previous = decode_delta(&snapshot, &delta);
+            self.memory_used -= delta.len();
+            self.memory_used += previous.len();
+            self.latest = Some(previous);
+        }
+        self.frames_since_snapshot = 0;
+        Ok(true)
+    }
+}
+
+// Layout: the target length as a little endian u32, then (zero run, literal length,
+// literal bytes) groups with both lengths stored as LEB128. The base is treated as
+// zero padded when the two snapshots don't have the same length.
+fn encode_delta(base: &[u8], target: &[u8]) -> Vec<u8> {
+    let xor = |index: usize| target[index] ^ base.get(index).copied().unwrap_or(0);
+    let mut delta = Vec::new();
+    delta.extend_from_slice(&(target.len() as u32).to_le_bytes());
+
+    let mut index = 0;
+    while index < target.len() {
+        let zero_start = index;
+        while index < target.len() && xor(index) == 0 {
+            index += 1;
+        }
+        let literal_start = index;
+        while index < target.len() && xor(index) != 0 {
+            index += 1;
+        }
+        write_length(&mut delta, literal_start - zero_start);
+        write_length(&mut delta, index - literal_start);
+        delta.extend((literal_start..index).map(xor));
+    }
+    delta
+}
+
+fn decode_delta(base: &[u8], delta: &[u8]) -> Vec<u8> {
+    let mut length = [0; 4];
+    length.copy_from_slice(&delta[..4]);
+    let length = u32::from_le_bytes(length) as usize;
+
+    let mut target: Vec<u8> = (0..length).map(|index| base.get(index).copied().unwrap_or(0)).collect();
+    let mut position = 4;
+    let mut index = 0;
+    while index < length {
+        index += read_length(delta, &mut position);
+        let literal_length = read_length(delta, &mut position);
+        for byte in &delta[position..position + literal_length] {
+            target[index] ^= byte;
+            index += 1;
+        }
+        position += literal_length;
+    }
+    target
+}
+
+fn write_length(data: &mut Vec<u8>, mut length: usize) {
+    while length >= 0x80 {
+        data.push((length as u8) | 0x80);
+        length >>= 7;
+    }
+    data.push(length as u8);
+}
+
+fn read_length(data: &[u8], position: &mut usize) -> usize {
+    let mut length = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[*position];
+        *position += 1;
+        length |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return length;
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cart::header::test_rom;
+
+    #[test]
+    fn test_delta_round_trip() {
+        let base: Vec<u8> = (0..1000).map(|i| (i % 251) as u8).collect();
+        let mut target = base.clone();
+        target[10] = 0xFF;
+        target[500..700].fill(0x12);
+        target.extend_from_slice(&[1, 2, 3]);
+
+        let delta = encode_delta(&base, &target);
+        assert!(delta.len() < 250);
+        assert_eq!(decode_delta(&base, &delta), target);
+        assert_eq!(decode_delta(&target, &encode_delta(&target, &base)), base);
+    }
+
+    #[test]
+    fn test_rewind_walks_back_through_snapshots() {
+        let mut cpu = CPU::new(&test_rom(0x01, 0x00)).unwrap();
+        let mut rewind = RewindBuffer::new(2, usize::MAX);
+        let mut snapshot_pcs = Vec::new();
+        for frame in 1..=10 {
+            for _ in 0..200 {
+                cpu.step().unwrap();
+            }
+            rewind.push_frame(&cpu);
+            if frame % 2 == 0 {
+                snapshot_pcs.push(cpu.registers.pc);
+            }
+        }
+
+        while let Some(pc) = snapshot_pcs.pop() {
+            assert_eq!(rewind.rewind_one_frame(&mut cpu), Ok(true));
+            assert_eq!(cpu.registers.pc, pc);
+        }
+        assert_eq!(rewind.rewind_one_frame(&mut cpu), Ok(false));
+        assert_eq!(rewind.memory_used(), 0);
+    }
+
+    #[test]
+    fn test_memory_budget_drops_oldest_snapshots() {
+        let mut cpu = CPU::new(&test_rom(0x01, 0x00)).unwrap();
+        let budget = cpu.save_state().len() + 64;
+        let mut rewind = RewindBuffer::new(1, budget);
+        for _ in 0..50 {
+            for _ in 0..200 {
+                cpu.step().unwrap();
+            }
+            rewind.push_frame(&cpu);
+            assert!(rewind.memory_used() <= budget);
+        }
+
+        let mut rewound = 0;
+        while rewind.rewind_one_frame(&mut cpu).unwrap() {
+            rewound += 1;
+        }
+        assert!((1..50).contains(&rewound));
+    }
+
+    #[test]
+    fn test_rewind_into_another_game() {
+        let mut cpu = CPU::new(&test_rom(0x01, 0x00)).unwrap();
+        let mut rewind = RewindBuffer::new(1, usize::MAX);
+        rewind.push_frame(&cpu);
+        rewind.push_frame(&cpu);
+
+        let mut other = CPU::new(&test_rom(0x00, 0x00)).unwrap();
+        assert!(matches!(rewind.rewind_one_frame(&mut other), Err(SaveStateError::RomMismatch { .. })));
+        assert_eq!(rewind.memory_used(), 0);
+        assert_eq!(rewind.rewind_one_frame(&mut cpu), Ok(false));
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+use crate::cpu::CPU;
+
+const MAGIC: &[u8; 8] = b"DMGSTATE";
+
+// Bump once per release that changes a serialized struct, states written by another
+// version are rejected instead of being decoded into garbage.
+pub const SAVE_STATE_VERSION: u32 = 1;
+
+const PREAMBLE_SIZE: usize = MAGIC.len() + 4;
+
+#[derive(Debug, PartialEq)]
+pub enum SaveStateError {
+    InvalidMagic,
+    UnsupportedVersion { expected: u32, actual: u32 },
+    RomMismatch { expected: String, actual: String },
+    Corrupted(String),
+}
+
+impl fmt::Display for SaveStateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveStateError::InvalidMagic =>
+                write!(f, "not a save state"),
+            SaveStateError::UnsupportedVersion { expected, actual } =>
+                write!(f, "save state version {} is not supported, expected version {}", actual, expected),
+            SaveStateError::RomMismatch { expected, actual } =>
+                write!(f, "save state was made with \"{}\" but \"{}\" is loaded", actual, expected),
+            SaveStateError::Corrupted(reason) =>
+                write!(f, "save state is corrupted: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SaveStateError {}
+
+impl CPU {
+    // Layout: "DMGSTATE", the format version as a little endian u32, then the whole
+    // machine encoded with bincode. The ROM itself is left out.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(PREAMBLE_SIZE);
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&SAVE_STATE_VERSION.to_le_bytes());
+        bincode::serialize_into(&mut data, self).expect("Failed to serialize save state");
+        data
+    }
+
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), SaveStateError> {
+        if data.len() < PREAMBLE_SIZE || &data[..MAGIC.len()] != MAGIC {
+            return Err(SaveStateError::InvalidMagic);
+        }
+        let mut version = [0; 4];
+        version.copy_from_slice(&data[MAGIC.len()..PREAMBLE_SIZE]);
+        let version = u32::from_le_bytes(version);
+        if version != SAVE_STATE_VERSION {
+            return Err(SaveStateError::UnsupportedVersion { expected: SAVE_STATE_VERSION, actual: version });
+        }
+
+        let mut state: CPU = bincode::deserialize(&data[PREAMBLE_SIZE..])
+            .map_err(|error| SaveStateError::Corrupted(error.to_string()))?;
+
+        // The header covers the title, mapper and checksums, a mismatch means the
+        // banking state and RAM layout belong to another game.
+        if state.bus.cart.header != self.bus.cart.header {
+            return Err(SaveStateError::RomMismatch {
+                expected: self.bus.cart.header.title.clone(),
+                actual: state.bus.cart.header.title.clone(),
+            });
+        }
+
+        state.bus.cart.take_rom(&mut self.bus.cart);
+        state.bus.io.apu.take_audio_output(&mut self.bus.io.apu);
+        state.bus.gpu.rebuild_caches();
+        state.bus.gpu.renderer = self.bus.gpu.renderer;
+        state.bus.gpu.strict_access = self.bus.gpu.strict_access;
+        state.timing = self.timing;
+        *self = state;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cart::header::test_rom;
+
+    fn run(cpu: &mut CPU, steps: usize) {
+        for _ in 0..steps {
+            cpu.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_restored_state_replays_identically() {
+        let mut cpu = CPU::new(&test_rom(0x13, 0x03)).unwrap();
+        run(&mut cpu, 5000);
+        let state = cpu.save_state();
+
+        run(&mut cpu, 5000);
+        let expected_pc = cpu.registers.pc;
+        let expected_vram = cpu.bus.gpu.vram;
+        let expected_tile_set = cpu.bus.gpu.tile_set.clone();
+
+        cpu.load_state(&state).unwrap();
+        run(&mut cpu, 5000);
+        assert_eq!(cpu.registers.pc, expected_pc);
+        assert_eq!(cpu.bus.gpu.vram, expected_vram);
+        assert!(cpu.bus.gpu.tile_set == expected_tile_set);
+        assert_eq!(cpu.bus.cart.cart_read(0x0150), 0x00);
+    }
+
+    #[test]
+    fn test_reject_incompatible_states() {
+        let mut cpu = CPU::new(&test_rom(0x13, 0x03)).unwrap();
+        let mut state = cpu.save_state();
+
+        assert_eq!(cpu.load_state(b"garbage"), Err(SaveStateError::InvalidMagic));
+
+        state[MAGIC.len()] = 0xFF;
+        assert!(matches!(cpu.load_state(&state), Err(SaveStateError::UnsupportedVersion { .. })));
+
+        let other = CPU::new(&test_rom(0x01, 0x00)).unwrap();
+        assert!(matches!(cpu.load_state(&other.save_state()), Err(SaveStateError::RomMismatch { .. })));
+
+        let state = cpu.save_state();
+        assert!(matches!(cpu.load_state(&state[..state.len() / 2]), Err(SaveStateError::Corrupted(_))));
+    }
+}
@@ -5,4 +5,43 @@ pub fn bit(condition: bool) -> u8 {
     } else {
         0
     }
-}
\ No newline at end of file
+}
+
+// serde only implements its traits for arrays up to 32 elements, memory blocks
+// like VRAM go through this module with #[serde(with = "crate::utils::byte_array")]
+#[cfg(feature = "serialize")]
+pub mod byte_array {
+    use std::fmt;
+    use serde::de::{Error, SeqAccess, Visitor};
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer, const N: usize>(array: &[u8; N], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(array)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error> {
+        deserializer.deserialize_bytes(ByteArrayVisitor::<N>)
+    }
+
+    struct ByteArrayVisitor<const N: usize>;
+
+    impl<'de, const N: usize> Visitor<'de> for ByteArrayVisitor<N> {
+        type Value = [u8; N];
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            write!(formatter, "an array of {} bytes", N)
+        }
+
+        fn visit_bytes<E: Error>(self, bytes: &[u8]) -> Result<[u8; N], E> {
+            bytes.try_into().map_err(|_| E::invalid_length(bytes.len(), &self))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<[u8; N], A::Error> {
+            let mut array = [0; N];
+            for (index, byte) in array.iter_mut().enumerate() {
+                *byte = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(index, &self))?;
+            }
+            Ok(array)
+        }
+    }
+}
@@ -0,0 +1,121 @@
+// Runs the mooneye and blargg test ROMs. The ROMs aren't part of the repo and take a
+// few seconds each, so these are ignored by default:
+// MOONEYE_ROMS=/path/to/mts BLARGG_ROMS=/path/to/gb-test-roms cargo test --release --test test_roms -- --ignored
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use lib_dmg::cpu::{CPU, Timing};
+use lib_dmg::Renderer;
+
+const ONE_FRAME_IN_CYCLES: usize = 70224;
+// Way longer than any of the tests needs
+const TIMEOUT_IN_CYCLES: usize = 60 * 20 * ONE_FRAME_IN_CYCLES;
+
+// Mooneye's tests signal they're done by executing LD B,B
+const LD_B_B: u8 = 0x40;
+
+fn read_rom(variable: &str, name: &str) -> Vec<u8> {
+    let directory = env::var(variable)
+        .unwrap_or_else(|_| panic!("Set {} to the directory holding the test ROMs", variable));
+    let path = PathBuf::from(directory).join(name);
+    fs::read(&path).unwrap_or_else(|error| panic!("Failed to read {}: {}", path.display(), error))
+}
+
+fn load(rom: &[u8]) -> CPU {
+    // The scanline renderer has a fixed length mode 3, the PPU tests need the FIFO
+    let mut cpu = CPU::with_renderer(rom, Renderer::PixelFifo).unwrap();
+    // The timing tests need the rest of the system to see every access as it happens
+    cpu.timing = Timing::MCycle;
+    cpu
+}
+
+// A passing test leaves the Fibonacci numbers in B, C, D, E, H and L, a failing one 0x42
+fn run_mooneye(name: &str) -> Result<(), String> {
+    let mut cpu = load(&read_rom("MOONEYE_ROMS", name));
+    let mut cycles = 0;
+    while cpu.bus.bus_read(cpu.registers.pc) != LD_B_B {
+        cycles += cpu.step().map_err(|error| error.to_string())? as usize;
+        if cycles >= TIMEOUT_IN_CYCLES {
+            return Err("timed out".to_string());
+        }
+    }
+    let registers = &cpu.registers;
+    let result = [registers.b, registers.c, registers.d, registers.e, registers.h, registers.l];
+    if result != [3, 5, 8, 13, 21, 34] {
+        return Err(format!("failed with {:02X?}", result));
+    }
+    Ok(())
+}
+
+// Blargg's tests print their results over the serial port, every byte is read from SB
+// when its transfer starts
+fn run_blargg(name: &str) -> Result<(), String> {
+    let mut cpu = load(&read_rom("BLARGG_ROMS", name));
+    let mut output = String::new();
+    let mut transferring = false;
+    let mut failed_at = None;
+    let mut cycles = 0;
+    while cycles < TIMEOUT_IN_CYCLES {
+        cycles += cpu.step().map_err(|error| error.to_string())? as usize;
+
+        let started = cpu.bus.bus_read(0xFF02) & 0x80 != 0;
+        if started && !transferring {
+            output.push(cpu.bus.bus_read(0xFF01) as char);
+        }
+        transferring = started;
+
+        if output.contains("Passed") {
+            return Ok(());
+        }
+        // Gives it a few frames to print why
+        if failed_at.is_none() && output.contains("Failed") {
+            failed_at = Some(cycles);
+        }
+        if failed_at.is_some_and(|failed_at| cycles - failed_at > 10 * ONE_FRAME_IN_CYCLES) {
+            return Err(output.trim_end().to_string());
+        }
+    }
+    Err(format!("timed out: {}", output))
+}
+
+// Runs every ROM so one failure doesn't hide the others
+fn assert_all_pass(names: &[&str], run: fn(&str) -> Result<(), String>) {
+    let failures: Vec<String> = names
+        .iter()
+        .filter_map(|name| run(name).err().map(|error| format!("{}: {}", name, error)))
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// lcdon_timing-GS and lcdon_write_timing-GS are left out, the shorter first line after
+// the LCD is turned on isn't emulated
+#[test]
+#[ignore]
+fn test_mooneye_ppu() {
+    assert_all_pass(&[
+        "acceptance/ppu/hblank_ly_scx_timing-GS.gb",
+        "acceptance/ppu/intr_1_2_timing-GS.gb",
+        "acceptance/ppu/intr_2_0_timing.gb",
+        "acceptance/ppu/intr_2_mode0_timing.gb",
+        "acceptance/ppu/intr_2_mode3_timing.gb",
+        "acceptance/ppu/intr_2_oam_ok_timing.gb",
+        "acceptance/ppu/stat_irq_blocking.gb",
+        "acceptance/ppu/stat_lyc_onoff.gb",
+        "acceptance/ppu/vblank_stat_intr-GS.gb",
+    ], run_mooneye);
+}
+
+#[test]
+#[ignore]
+fn test_mooneye_timer() {
+    assert_all_pass(&["acceptance/timer/tima_reload.gb"], run_mooneye);
+}
+
+#[test]
+#[ignore]
+fn test_blargg_timing() {
+    assert_all_pass(&[
+        "instr_timing/instr_timing.gb",
+        "mem_timing/mem_timing.gb",
+    ], run_blargg);
+}
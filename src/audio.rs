@@ -0,0 +1,62 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "audio")]
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+// Plays the APU output on the default device. The emulator pushes samples into a
+// queue and the device callback drains it in real time, so how much is still queued
+// tells the frontend how far ahead of the device the emulation is.
+pub struct AudioOutput {
+    #[cfg(feature = "audio")]
+    _stream: cpal::Stream,
+    queue: Arc<Mutex<VecDeque<f32>>>,
+}
+
+impl AudioOutput {
+    #[cfg(feature = "audio")]
+    pub fn open(sample_rate: u32) -> Result<AudioOutput, String> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("no output device")?;
+        let config = cpal::StreamConfig {
+            channels: 2,
+            sample_rate: cpal::SampleRate(sample_rate),
+            buffer_size: cpal::BufferSize::Default,
+        };
+
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+        let device_queue = Arc::clone(&queue);
+        let stream = device
+            .build_output_stream(
+                &config,
+                move |data: &mut [f32], _| {
+                    // Plays silence on underruns, e.g. while the CPU is in STOP
+                    let mut queue = device_queue.lock().unwrap();
+                    for sample in data.iter_mut() {
+                        *sample = queue.pop_front().unwrap_or(0.0);
+                    }
+                },
+                |error| eprintln!("Audio stream error: {}", error),
+                None,
+            )
+            .map_err(|error| error.to_string())?;
+        stream.play().map_err(|error| error.to_string())?;
+
+        Ok(AudioOutput { _stream: stream, queue })
+    }
+
+    #[cfg(not(feature = "audio"))]
+    pub fn open(_sample_rate: u32) -> Result<AudioOutput, String> {
+        Err("built without the audio feature".to_string())
+    }
+
+    // Interleaved stereo, like APU::drain_samples
+    pub fn push(&self, samples: &[f32]) {
+        self.queue.lock().unwrap().extend(samples.iter().copied());
+    }
+
+    // Stereo frames waiting to be played
+    pub fn queued_frames(&self) -> usize {
+        self.queue.lock().unwrap().len() / 2
+    }
+}
@@ -1,9 +1,20 @@
+use std::env;
 use std::fs;
 use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::thread::sleep;
 use std::time::{Duration, Instant};
-use minifb::{Key, Window, WindowOptions};
-use lib_dmg::cpu::CPU;
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
+use lib_dmg::cart::ClockSource;
+use lib_dmg::cpu::{CPU, Timing};
+use lib_dmg::error::EmulatorError;
+use lib_dmg::rewind::RewindBuffer;
+use lib_dmg::Renderer;
+use crate::audio::AudioOutput;
+use crate::wav::WavWriter;
+
+mod audio;
+mod wav;
 
 const ENLARGEMENT_FACTOR: usize = 2;
 const WINDOW_DIMENSIONS: [usize; 2] = [(160 * ENLARGEMENT_FACTOR), (144 * ENLARGEMENT_FACTOR)];
@@ -22,12 +33,44 @@ fn main() {
     //let mut file = fs::File::open("C:\\Dev\\gb_emulator\\roms\\cpu_instrs\\individual\\10-bit ops.gb").expect("Failed to open file");         //PASSED
     //let mut file = fs::File::open("C:\\Dev\\gb_emulator\\roms\\cpu_instrs\\individual\\11-op a,(hl).gb").expect("Failed to open file");       //PASSED
     //let mut file = fs::File::open("C:\\Dev\\dmg_emulator\\src\\CRASH.gb").expect("Failed to open file");
-    let mut file = fs::File::open("C:\\Dev\\gb_emulator\\roms\\tetris\\tetris.gb").expect("Failed to open file");
+    let rom_path = Path::new("C:\\Dev\\gb_emulator\\roms\\tetris\\tetris.gb");
+    let mut file = fs::File::open(rom_path).expect("Failed to open file");
     //let mut file = fs::File::open("C:\\Dev\\gb_emulator\\roms\\supermario\\supermario.gb").expect("Failed to open file");
     file.read_to_end(&mut data).expect("Failed to read file");
 
 
-    let mut cpu: CPU = CPU::new(&data);
+    let mut cpu: CPU = match CPU::with_renderer(&data, renderer()) {
+        Ok(cpu) => cpu,
+        Err(error) => {
+            eprintln!("Failed to load cartridge: {}", error);
+            return;
+        }
+    };
+    cpu.bus.cart.set_rtc_clock_source(ClockSource::Host);
+    cpu.bus.io.apu.set_sample_rate(SAMPLE_RATE);
+    // --strict reports VRAM/OAM accesses made while the PPU is using them
+    cpu.bus.gpu.strict_access = has_flag("--strict");
+    // --m-cycle runs the timer, PPU and DMA along with every CPU memory access
+    if has_flag("--m-cycle") {
+        cpu.timing = Timing::MCycle;
+    }
+
+    let wav = match wav_path() {
+        Some(path) => match WavWriter::create(&path, SAMPLE_RATE) {
+            Ok(wav) => Some(wav),
+            Err(error) => {
+                eprintln!("Failed to create {}: {}", path.display(), error);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let save_path = rom_path.with_extension("sav");
+    let state_path = rom_path.with_extension("state");
+    if cpu.bus.cart.has_battery() {
+        load_save(&mut cpu, &save_path);
+    }
     let window = Window::new(
         "DMG-01",
         WINDOW_DIMENSIONS[0],
@@ -36,90 +79,251 @@ fn main() {
     )
         .unwrap();
 
-    run(cpu, window)
+    // Without a device the emulation is paced against the wall clock instead
+    let audio = match AudioOutput::open(SAMPLE_RATE) {
+        Ok(audio) => Some(audio),
+        Err(error) => {
+            eprintln!("No audio output, {}", error);
+            None
+        }
+    };
+
+    run(cpu, window, audio, wav, &save_path, &state_path)
 
 }
 
-const ONE_SECOND_IN_MICROS: usize = 1000000000;
-const ONE_SECOND_IN_CYCLES: usize = 4190000;
 const ONE_FRAME_IN_CYCLES: usize = 70224;
 const NUMBER_OF_PIXELS: usize = 23040;
+const SAVE_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const REWIND_FRAME_INTERVAL: usize = 2;
+const REWIND_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+const REWIND_STEP_DURATION: Duration = Duration::from_micros(16742);
+const SAMPLE_RATE: u32 = 48000;
+const MAX_AUDIO_LAG: u64 = SAMPLE_RATE as u64 / 10;
+const AUDIO_LATENCY: u64 = SAMPLE_RATE as u64 / 20;
+
+// --wav <path> dumps the audio output to a WAV file
+fn wav_path() -> Option<PathBuf> {
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--wav" {
+            return args.next().map(PathBuf::from);
+        }
+    }
+    None
+}
+
+// --pixel-fifo switches to the slower PPU that handles mid-scanline register writes
+fn renderer() -> Renderer {
+    if has_flag("--pixel-fifo") {
+        Renderer::PixelFifo
+    } else {
+        Renderer::Scanline
+    }
+}
+
+fn has_flag(flag: &str) -> bool {
+    env::args().skip(1).any(|arg| arg == flag)
+}
 
-fn run(mut cpu: CPU, mut window: Window) {
+fn run(mut cpu: CPU, mut window: Window, audio: Option<AudioOutput>, mut wav: Option<WavWriter>, save_path: &Path, state_path: &Path) {
     let mut buffer = [0; NUMBER_OF_PIXELS];
     let mut cycles_elapsed_in_frame = 0usize;
-    let mut now = Instant::now();
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-
-        if window.is_key_down(Key::A) {
-            cpu.bus.io.joypad.a = true;
-            cpu.bus.io.interrupt_enable.joypad = true;
-        }else {
-            cpu.bus.io.joypad.a = false;
-            cpu.bus.io.interrupt_enable.joypad = false;
-        }
-        if window.is_key_down(Key::S) {
-            cpu.bus.io.joypad.start = true;
-            cpu.bus.io.interrupt_enable.joypad = true;
-        } else {
-            cpu.bus.io.joypad.start = false;
-            cpu.bus.io.interrupt_enable.joypad = false;
+    let mut audio_clock = Instant::now();
+    let mut samples_played = 0u64;
+    let mut samples = Vec::new();
+    let mut last_save_flush = Instant::now();
+    let mut rewind = RewindBuffer::new(REWIND_FRAME_INTERVAL, REWIND_MEMORY_BUDGET);
+    'running: while window.is_open() && !window.is_key_down(Key::Escape) {
+
+        if window.is_key_pressed(Key::F5, KeyRepeat::No) {
+            save_state(&cpu, state_path);
         }
-        if window.is_key_down(Key::Right) {
-            cpu.bus.io.joypad.right = true;
-            cpu.bus.io.interrupt_enable.joypad = true;
-        } else {
-            cpu.bus.io.joypad.right = false;
-            cpu.bus.io.interrupt_enable.joypad = false;
+        if window.is_key_pressed(Key::F8, KeyRepeat::No) {
+            load_state(&mut cpu, state_path);
         }
-        if window.is_key_down(Key::Left) {
-            cpu.bus.io.joypad.left = true;
-            cpu.bus.io.interrupt_enable.joypad = true;
-        } else {
-            cpu.bus.io.joypad.left = false;
-            cpu.bus.io.interrupt_enable.joypad = false;
-        }
-        if window.is_key_down(Key::Up) {
-            cpu.bus.io.joypad.up = true;
-            cpu.bus.io.interrupt_enable.joypad = true;
-        } else {
-            cpu.bus.io.joypad.up = false;
-            cpu.bus.io.interrupt_enable.joypad = false;
+
+        if window.is_key_down(Key::Backspace) {
+            // Snapshots don't include the picture, run a frame from the restored one to get it back
+            let rewound = match rewind.rewind_one_frame(&mut cpu) {
+                Ok(rewound) => rewound,
+                Err(error) => {
+                    eprintln!("Failed to rewind: {}", error);
+                    false
+                }
+            };
+            if rewound {
+                let mut cycles_elapsed = 0;
+                while cycles_elapsed < ONE_FRAME_IN_CYCLES {
+                    match cpu.step() {
+                        Ok(cycles) => cycles_elapsed += cycles as usize,
+                        Err(error) => {
+                            eprintln!("Emulation stopped: {}", error);
+                            break 'running;
+                        }
+                    }
+                }
+                present_frame(&cpu, &mut window, &mut buffer);
+            } else {
+                window.update();
+            }
+            sleep(REWIND_STEP_DURATION);
+            cpu.bus.io.apu.drain_samples(&mut samples);
+            samples.clear();
+            audio_clock = Instant::now();
+            samples_played = 0;
+            cycles_elapsed_in_frame = 0;
+            continue;
         }
-        if window.is_key_down(Key::Down) {
-            cpu.bus.io.joypad.down = true;
-            cpu.bus.io.interrupt_enable.joypad = true;
-        } else {
-            cpu.bus.io.joypad.down = false;
-            cpu.bus.io.interrupt_enable.joypad = false;
+
+        cpu.bus.io.joypad.a = window.is_key_down(Key::A);
+        cpu.bus.io.joypad.start = window.is_key_down(Key::S);
+        cpu.bus.io.joypad.right = window.is_key_down(Key::Right);
+        cpu.bus.io.joypad.left = window.is_key_down(Key::Left);
+        cpu.bus.io.joypad.up = window.is_key_down(Key::Up);
+        cpu.bus.io.joypad.down = window.is_key_down(Key::Down);
+
+        // The emulation runs just far enough to produce the audio that is needed next
+        let samples_wanted = match &audio {
+            // The device plays the queue back in real time, keep it topped up
+            Some(output) => AUDIO_LATENCY.saturating_sub(output.queued_frames() as u64),
+            None => {
+                // No sound device, the audio output is consumed at the host sample rate
+                // against the wall clock
+                let samples_due = (audio_clock.elapsed().as_secs_f64() * SAMPLE_RATE as f64) as u64;
+                if samples_due > samples_played + MAX_AUDIO_LAG {
+                    // Too far behind (e.g. the window was being dragged), don't try to catch up
+                    audio_clock = Instant::now();
+                    samples_played = 0;
+                }
+                samples_due.saturating_sub(samples_played)
+            }
+        };
+
+        match emulate(&mut cpu, samples_wanted) {
+            Ok(cycles_elapsed) => cycles_elapsed_in_frame += cycles_elapsed,
+            Err(error) => {
+                eprintln!("Emulation stopped: {}", error);
+                break 'running;
+            }
         }
 
-        let time_delta = now.elapsed().subsec_nanos();
-        now = Instant::now();
-        let delta = time_delta as f64 / ONE_SECOND_IN_MICROS as f64;
-        let cycles_to_run = delta * ONE_SECOND_IN_CYCLES as f64;
+        cpu.bus.io.apu.drain_samples(&mut samples);
+        samples_played += samples.len() as u64 / 2;
+        if let Some(output) = &audio {
+            output.push(&samples);
+        }
+        if let Some(writer) = &mut wav {
+            if let Err(error) = writer.write_samples(&samples) {
+                eprintln!("Failed to write audio: {}", error);
+                wav = None;
+            }
+        }
+        samples.clear();
 
-        let mut cycles_elapsed = 0;
-        while cycles_elapsed <= cycles_to_run as usize {
-            cycles_elapsed += cpu.step() as usize;
+        // Don't hit the disk on every write, games usually write a whole block at once
+        if cpu.bus.cart.is_save_dirty() && last_save_flush.elapsed() >= SAVE_FLUSH_INTERVAL {
+            flush_save(&mut cpu, save_path);
+            last_save_flush = Instant::now();
+        }
 
+        // Only recorded with --strict
+        for access in cpu.bus.gpu.take_illegal_accesses() {
+            eprintln!("{}", access);
         }
-        cycles_elapsed_in_frame += cycles_elapsed;
 
 
 
         // TODO: Consider updating buffer after every line is rendered.
         if cycles_elapsed_in_frame >= ONE_FRAME_IN_CYCLES {
-            for (i, pixel) in cpu.bus.gpu.canvas_buffer.chunks(4).enumerate() {
-                buffer[i] = (pixel[3] as u32) << 24
-                    | (pixel[2] as u32) << 16
-                    | (pixel[1] as u32) << 8
-                    | (pixel[0] as u32)
-            }
-            window.update_with_buffer(&buffer, 160, 144).unwrap();
+            present_frame(&cpu, &mut window, &mut buffer);
+            rewind.push_frame(&cpu);
             cycles_elapsed_in_frame = 0;
         } else {
             sleep(Duration::from_nanos(2))
         }
     }
+
+    flush_save(&mut cpu, save_path);
+    if let Some(writer) = wav {
+        if let Err(error) = writer.finish() {
+            eprintln!("Failed to write audio: {}", error);
+        }
+    }
+}
+
+// Runs until the APU has samples_wanted samples ready, but for a frame at most so
+// the keys still get polled when no sound comes out, e.g. while the CPU is in STOP
+fn emulate(cpu: &mut CPU, samples_wanted: u64) -> Result<usize, EmulatorError> {
+    let mut cycles_elapsed = 0;
+    while (cpu.bus.io.apu.samples_available() as u64) < samples_wanted && cycles_elapsed < ONE_FRAME_IN_CYCLES {
+        cycles_elapsed += cpu.step()? as usize;
+    }
+    Ok(cycles_elapsed)
+}
+
+fn present_frame(cpu: &CPU, window: &mut Window, buffer: &mut [u32; NUMBER_OF_PIXELS]) {
+    for (i, pixel) in cpu.bus.gpu.canvas_buffer.chunks(4).enumerate() {
+        buffer[i] = (pixel[3] as u32) << 24
+            | (pixel[2] as u32) << 16
+            | (pixel[1] as u32) << 8
+            | (pixel[0] as u32)
+    }
+    window.update_with_buffer(buffer, 160, 144).unwrap();
+}
+
+fn load_save(cpu: &mut CPU, save_path: &Path) {
+    // A missing save file just means the game was never saved
+    if let Ok(data) = fs::read(save_path) {
+        if let Err(error) = cpu.bus.cart.load_save(&data) {
+            eprintln!("Failed to load save {}: {}", save_path.display(), error);
+        }
+    }
+}
+
+fn flush_save(cpu: &mut CPU, save_path: &Path) {
+    if let Some(data) = cpu.bus.cart.export_save() {
+        if let Err(error) = fs::write(save_path, data) {
+            eprintln!("Failed to write save {}: {}", save_path.display(), error);
+        }
+    }
+}
+
+fn save_state(cpu: &CPU, state_path: &Path) {
+    if let Err(error) = fs::write(state_path, cpu.save_state()) {
+        eprintln!("Failed to write save state {}: {}", state_path.display(), error);
+    }
+}
+
+fn load_state(cpu: &mut CPU, state_path: &Path) {
+    let result = fs::read(state_path)
+        .map_err(|error| error.to_string())
+        .and_then(|data| cpu.load_state(&data).map_err(|error| error.to_string()));
+    if let Err(error) = result {
+        eprintln!("Failed to load save state {}: {}", state_path.display(), error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stop_gives_control_back() {
+        let mut cpu = CPU::new(include_bytes!("bomberman.gb")).unwrap();
+        // STOP, then JR to itself
+        for (offset, byte) in [0x10, 0x00, 0x18, 0xFE].iter().enumerate() {
+            cpu.bus.bus_write(0xC000 + offset as u16, *byte);
+        }
+        cpu.registers.pc = 0xC000;
+
+        // No sound comes out while stopped, the frontend still gets to poll the keys
+        assert_eq!(emulate(&mut cpu, u64::MAX), Ok(ONE_FRAME_IN_CYCLES));
+        assert!(cpu.is_stopped);
+
+        cpu.bus.io.joypad.right = true;
+        emulate(&mut cpu, u64::MAX).unwrap();
+        assert!(!cpu.is_stopped);
+        assert_eq!(cpu.registers.pc, 0xC002);
+    }
 }
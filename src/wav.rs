@@ -0,0 +1,48 @@
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const HEADER_SIZE: u32 = 44;
+
+// 16 bit stereo PCM writer, the sizes in the header are patched in by finish
+pub struct WavWriter {
+    writer: BufWriter<File>,
+    data_size: u32,
+}
+
+impl WavWriter {
+    pub fn create(path: &Path, sample_rate: u32) -> std::io::Result<WavWriter> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(b"RIFF")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(b"WAVEfmt ")?;
+        writer.write_all(&16u32.to_le_bytes())?;
+        writer.write_all(&1u16.to_le_bytes())?; // PCM
+        writer.write_all(&2u16.to_le_bytes())?; // stereo
+        writer.write_all(&sample_rate.to_le_bytes())?;
+        writer.write_all(&(sample_rate * 4).to_le_bytes())?; // byte rate
+        writer.write_all(&4u16.to_le_bytes())?; // block align
+        writer.write_all(&16u16.to_le_bytes())?; // bits per sample
+        writer.write_all(b"data")?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        Ok(WavWriter { writer, data_size: 0 })
+    }
+
+    // Takes interleaved left/right samples in the -1.0..=1.0 range
+    pub fn write_samples(&mut self, samples: &[f32]) -> std::io::Result<()> {
+        for sample in samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.writer.write_all(&value.to_le_bytes())?;
+        }
+        self.data_size += samples.len() as u32 * 2;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> std::io::Result<()> {
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer.write_all(&(HEADER_SIZE - 8 + self.data_size).to_le_bytes())?;
+        self.writer.seek(SeekFrom::Start(40))?;
+        self.writer.write_all(&self.data_size.to_le_bytes())?;
+        self.writer.flush()
+    }
+}